@@ -0,0 +1 @@
+DROP TABLE eval_policy;
@@ -0,0 +1,11 @@
+CREATE TABLE eval_policy (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    eval_name TEXT NOT NULL UNIQUE,
+    individual_threshold REAL NOT NULL,
+    mean_threshold REAL NOT NULL,
+    consistency_threshold REAL NOT NULL,
+    higher_is_better BOOLEAN NOT NULL DEFAULT TRUE,
+    min_sample_count INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,2 @@
+DROP INDEX eval_name_prompt_version_id_idx;
+DROP INDEX prompt_version_name_version_idx;
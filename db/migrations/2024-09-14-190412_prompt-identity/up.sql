@@ -0,0 +1,22 @@
+-- Prompt versions used to be looked up by version only, so merge any duplicates before
+-- enforcing uniqueness. Evals are repointed to the surviving row first.
+UPDATE eval SET prompt_version_id = keep.id
+FROM prompt_version dup
+JOIN (SELECT MIN(id) AS id, name, version FROM prompt_version GROUP BY name, version) keep
+    ON keep.name = dup.name AND keep.version = dup.version
+WHERE eval.prompt_version_id = dup.id AND dup.id <> keep.id;
+
+DELETE FROM prompt_version dup USING prompt_version keep
+WHERE dup.name = keep.name AND dup.version = keep.version AND dup.id > keep.id;
+
+UPDATE eval_result SET eval_id = keep.id
+FROM eval dup
+JOIN (SELECT MIN(id) AS id, name, prompt_version_id FROM eval GROUP BY name, prompt_version_id) keep
+    ON keep.name = dup.name AND keep.prompt_version_id = dup.prompt_version_id
+WHERE eval_result.eval_id = dup.id AND dup.id <> keep.id;
+
+DELETE FROM eval dup USING eval keep
+WHERE dup.name = keep.name AND dup.prompt_version_id = keep.prompt_version_id AND dup.id > keep.id;
+
+CREATE UNIQUE INDEX prompt_version_name_version_idx ON prompt_version (name, version);
+CREATE UNIQUE INDEX eval_name_prompt_version_id_idx ON eval (name, prompt_version_id);
@@ -0,0 +1,13 @@
+ALTER TABLE eval_result ADD COLUMN scores jsonb DEFAULT '{}' NOT NULL;
+
+UPDATE eval_result er SET scores = agg.scores
+FROM (
+    SELECT eval_result_id,
+           jsonb_agg(jsonb_build_object('eval_hash', eval_hash, 'score', value) ORDER BY id) AS scores
+    FROM eval_score
+    WHERE metric = 'score'
+    GROUP BY eval_result_id
+) agg
+WHERE agg.eval_result_id = er.id;
+
+DROP TABLE eval_score;
@@ -0,0 +1,21 @@
+CREATE TABLE eval_score (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    eval_result_id INT NOT NULL,
+    eval_hash TEXT NOT NULL,
+    metric TEXT NOT NULL DEFAULT 'score',
+    value REAL NOT NULL,
+    FOREIGN KEY (eval_result_id) REFERENCES eval_result (id) ON DELETE CASCADE
+);
+
+CREATE INDEX eval_score_eval_result_id_metric_eval_hash_idx ON eval_score (eval_result_id, metric, eval_hash);
+CREATE INDEX eval_score_eval_hash_idx ON eval_score (eval_hash);
+
+-- Scores were stored as a JSONB array of {eval_hash, score}; keep their original order
+INSERT INTO eval_score (eval_result_id, eval_hash, value)
+SELECT er.id, s.value->>'eval_hash', (s.value->>'score')::REAL
+FROM eval_result er
+CROSS JOIN LATERAL jsonb_array_elements(er.scores) WITH ORDINALITY AS s(value, ordinal)
+WHERE jsonb_typeof(er.scores) = 'array'
+ORDER BY er.id, s.ordinal;
+
+ALTER TABLE eval_result DROP COLUMN scores;
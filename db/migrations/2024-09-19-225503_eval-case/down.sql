@@ -0,0 +1,3 @@
+ALTER TABLE eval_score DROP COLUMN output_hash;
+DROP TABLE eval_output;
+DROP TABLE eval_case;
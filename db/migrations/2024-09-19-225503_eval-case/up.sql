@@ -0,0 +1,14 @@
+CREATE TABLE eval_case (
+    eval_hash TEXT PRIMARY KEY,
+    input jsonb,
+    expected_output jsonb,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE eval_output (
+    content_hash TEXT PRIMARY KEY,
+    content jsonb NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+ALTER TABLE eval_score ADD COLUMN output_hash TEXT REFERENCES eval_output (content_hash);
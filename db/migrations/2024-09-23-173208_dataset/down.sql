@@ -0,0 +1,5 @@
+ALTER TABLE eval_result DROP COLUMN dataset_version_id;
+DROP TABLE dataset_version_case;
+DROP TABLE dataset_version;
+DROP TABLE dataset_case;
+DROP TABLE dataset;
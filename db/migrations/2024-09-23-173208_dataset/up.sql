@@ -0,0 +1,35 @@
+CREATE TABLE dataset (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+-- Working set of a dataset, edited until it is snapshotted into a version
+CREATE TABLE dataset_case (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    dataset_id INT NOT NULL REFERENCES dataset (id) ON DELETE CASCADE,
+    eval_hash TEXT NOT NULL REFERENCES eval_case (eval_hash),
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (dataset_id, eval_hash)
+);
+
+CREATE TABLE dataset_version (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    dataset_id INT NOT NULL REFERENCES dataset (id) ON DELETE CASCADE,
+    version INT NOT NULL,
+    case_count INT NOT NULL,
+    content_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (dataset_id, version)
+);
+
+CREATE TABLE dataset_version_case (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    dataset_version_id INT NOT NULL REFERENCES dataset_version (id) ON DELETE CASCADE,
+    eval_hash TEXT NOT NULL REFERENCES eval_case (eval_hash),
+    UNIQUE (dataset_version_id, eval_hash)
+);
+
+ALTER TABLE eval_result ADD COLUMN dataset_version_id INT REFERENCES dataset_version (id);
+CREATE INDEX eval_result_dataset_version_id_idx ON eval_result (dataset_version_id);
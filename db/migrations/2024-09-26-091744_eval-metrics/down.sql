@@ -0,0 +1,7 @@
+DELETE FROM eval_policy WHERE metric <> 'score';
+ALTER TABLE eval_policy DROP CONSTRAINT eval_policy_eval_name_metric_key;
+ALTER TABLE eval_policy ADD CONSTRAINT eval_policy_eval_name_key UNIQUE (eval_name);
+ALTER TABLE eval_policy DROP COLUMN metric;
+
+DELETE FROM eval_score WHERE metric <> 'score';
+ALTER TABLE eval_score DROP COLUMN trial_index;
@@ -0,0 +1,18 @@
+-- Metrics of the same trial share a trial index, so trials can be reassembled from their rows
+ALTER TABLE eval_score ADD COLUMN trial_index INT NOT NULL DEFAULT 0;
+
+UPDATE eval_score s
+SET trial_index = t.trial_index
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY eval_result_id ORDER BY id) - 1 AS trial_index
+    FROM eval_score
+) t
+WHERE s.id = t.id;
+
+ALTER TABLE eval_score ALTER COLUMN trial_index DROP DEFAULT;
+
+-- Policies apply to a single metric of an eval
+ALTER TABLE eval_policy ADD COLUMN metric TEXT NOT NULL DEFAULT 'score';
+ALTER TABLE eval_policy ALTER COLUMN metric DROP DEFAULT;
+ALTER TABLE eval_policy DROP CONSTRAINT eval_policy_eval_name_key;
+ALTER TABLE eval_policy ADD CONSTRAINT eval_policy_eval_name_metric_key UNIQUE (eval_name, metric);
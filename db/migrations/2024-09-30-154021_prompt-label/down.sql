@@ -0,0 +1,2 @@
+DROP TABLE prompt_label_history;
+DROP TABLE prompt_label;
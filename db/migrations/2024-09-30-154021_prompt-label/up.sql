@@ -0,0 +1,22 @@
+CREATE TABLE prompt_label (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    prompt_name TEXT NOT NULL,
+    label TEXT NOT NULL,
+    prompt_version_id INT NOT NULL REFERENCES prompt_version (id),
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (prompt_name, label)
+);
+
+-- Every move of a label, including its creation and removal
+CREATE TABLE prompt_label_history (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    prompt_name TEXT NOT NULL,
+    label TEXT NOT NULL,
+    previous_prompt_version_id INT REFERENCES prompt_version (id),
+    prompt_version_id INT REFERENCES prompt_version (id),
+    actor TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX prompt_label_history_prompt_name_label_idx ON prompt_label_history (prompt_name, label, created_at);
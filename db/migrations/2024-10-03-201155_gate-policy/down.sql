@@ -0,0 +1 @@
+DROP TABLE gate_policy;
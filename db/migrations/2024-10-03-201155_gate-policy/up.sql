@@ -0,0 +1,9 @@
+CREATE TABLE gate_policy (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    prompt_name TEXT NOT NULL UNIQUE,
+    fail_on_regression BOOLEAN NOT NULL DEFAULT TRUE,
+    max_case_regression REAL,
+    fail_on_unknown BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,8 @@
+DROP INDEX eval_result_branch_idx;
+ALTER TABLE eval_result
+    DROP COLUMN git_sha,
+    DROP COLUMN branch,
+    DROP COLUMN ci_run_url,
+    DROP COLUMN model,
+    DROP COLUMN temperature,
+    DROP COLUMN attributes;
@@ -0,0 +1,8 @@
+ALTER TABLE eval_result
+    ADD COLUMN git_sha TEXT,
+    ADD COLUMN branch TEXT,
+    ADD COLUMN ci_run_url TEXT,
+    ADD COLUMN model TEXT,
+    ADD COLUMN temperature REAL,
+    ADD COLUMN attributes JSONB NOT NULL DEFAULT '{}';
+CREATE INDEX eval_result_branch_idx ON eval_result (branch, created_at);
@@ -0,0 +1,34 @@
+CREATE TABLE webhook_endpoint (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    events TEXT[] NOT NULL,
+    eval_name TEXT,
+    prompt_name TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE webhook_delivery (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    webhook_endpoint_id INT NOT NULL REFERENCES webhook_endpoint (id) ON DELETE CASCADE,
+    event TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL,
+    attempt_count INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX webhook_delivery_webhook_endpoint_id_idx ON webhook_delivery (webhook_endpoint_id, created_at);
+CREATE INDEX webhook_delivery_status_idx ON webhook_delivery (status);
+
+CREATE TABLE webhook_delivery_attempt (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    webhook_delivery_id INT NOT NULL REFERENCES webhook_delivery (id) ON DELETE CASCADE,
+    attempt INT NOT NULL,
+    response_status INT,
+    error TEXT,
+    duration_ms INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX webhook_delivery_attempt_webhook_delivery_id_idx ON webhook_delivery_attempt (webhook_delivery_id, attempt);
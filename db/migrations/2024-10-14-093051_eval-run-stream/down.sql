@@ -0,0 +1,9 @@
+DROP TABLE eval_run_batch;
+DROP INDEX eval_result_run_key_idx;
+ALTER TABLE eval_result
+    DROP COLUMN status,
+    DROP COLUMN finished_at,
+    DROP COLUMN run_key,
+    DROP COLUMN requested_base_version,
+    DROP COLUMN requested_base_label,
+    DROP COLUMN requested_base_branch;
@@ -0,0 +1,19 @@
+ALTER TABLE eval_result
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'finished',
+    ADD COLUMN finished_at TIMESTAMPTZ,
+    ADD COLUMN run_key TEXT,
+    ADD COLUMN requested_base_version TEXT,
+    ADD COLUMN requested_base_label TEXT,
+    ADD COLUMN requested_base_branch TEXT;
+UPDATE eval_result SET finished_at = created_at;
+CREATE UNIQUE INDEX eval_result_run_key_idx ON eval_result (run_key);
+
+CREATE TABLE eval_run_batch (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    eval_result_id INT NOT NULL REFERENCES eval_result (id) ON DELETE CASCADE,
+    sequence INT NOT NULL,
+    first_trial_index INT NOT NULL,
+    trial_count INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (eval_result_id, sequence)
+);
@@ -0,0 +1 @@
+ALTER TABLE eval_result DROP COLUMN scorers;
@@ -0,0 +1 @@
+ALTER TABLE eval_result ADD COLUMN scorers JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1 @@
+DROP TABLE scorer_plugin;
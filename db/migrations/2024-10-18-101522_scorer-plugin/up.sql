@@ -0,0 +1,11 @@
+CREATE TABLE scorer_plugin (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    module BYTEA NOT NULL,
+    content_hash TEXT NOT NULL,
+    needs_expected BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (name, version)
+);
+CREATE INDEX scorer_plugin_content_hash_idx ON scorer_plugin (content_hash);
@@ -0,0 +1,2 @@
+ALTER TABLE prompt_version DROP COLUMN content_hash;
+ALTER TABLE prompt_version DROP COLUMN template;
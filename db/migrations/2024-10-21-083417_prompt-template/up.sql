@@ -0,0 +1,3 @@
+-- Content of a prompt version, absent for versions only known from eval runs
+ALTER TABLE prompt_version ADD COLUMN template JSONB;
+ALTER TABLE prompt_version ADD COLUMN content_hash TEXT;
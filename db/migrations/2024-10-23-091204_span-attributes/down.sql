@@ -0,0 +1,6 @@
+DROP INDEX span_prompt_version_id_idx;
+
+ALTER TABLE span DROP COLUMN prompt_version_id;
+ALTER TABLE span DROP COLUMN output;
+ALTER TABLE span DROP COLUMN input;
+ALTER TABLE span DROP COLUMN attributes;
@@ -0,0 +1,7 @@
+-- Attributes and captured payloads of a span, and the prompt version it used if any
+ALTER TABLE span ADD COLUMN attributes JSONB NOT NULL DEFAULT '{}';
+ALTER TABLE span ADD COLUMN input JSONB;
+ALTER TABLE span ADD COLUMN output JSONB;
+ALTER TABLE span ADD COLUMN prompt_version_id INT REFERENCES prompt_version(id);
+
+CREATE INDEX span_prompt_version_id_idx ON span (prompt_version_id);
@@ -0,0 +1,4 @@
+DROP INDEX span_parent_span_id_idx;
+DROP TABLE span_feedback;
+DROP TABLE experiment_arm;
+DROP TABLE experiment;
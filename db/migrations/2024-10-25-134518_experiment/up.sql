@@ -0,0 +1,29 @@
+-- Online experiment splitting the traffic of a prompt between versions
+CREATE TABLE experiment (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    name TEXT NOT NULL UNIQUE,
+    prompt_name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+-- Version served to a share of the subjects of an experiment, proportional to its weight
+CREATE TABLE experiment_arm (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    experiment_id INT NOT NULL REFERENCES experiment(id),
+    prompt_version_id INT NOT NULL REFERENCES prompt_version(id),
+    weight INT NOT NULL CHECK (weight > 0),
+    UNIQUE (experiment_id, prompt_version_id)
+);
+
+-- Score given to a span after the fact, e.g. a user rating of an answer
+CREATE TABLE span_feedback (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    span_id INT NOT NULL REFERENCES span(id),
+    name TEXT NOT NULL,
+    score DOUBLE PRECISION NOT NULL,
+    comment TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX span_feedback_span_id_idx ON span_feedback (span_id);
+CREATE INDEX span_parent_span_id_idx ON span (parent_span_id);
@@ -0,0 +1,3 @@
+DROP INDEX span_ts_start_idx;
+DROP TABLE span_score;
+DROP TABLE online_eval_rule;
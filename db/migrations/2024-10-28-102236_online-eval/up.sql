@@ -0,0 +1,24 @@
+-- Scorers applied to a sample of the ingested spans matching an operation name and attributes
+CREATE TABLE online_eval_rule (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    name TEXT NOT NULL UNIQUE,
+    operation_name TEXT,
+    attributes JSONB NOT NULL DEFAULT '{}',
+    sample_rate DOUBLE PRECISION NOT NULL CHECK (sample_rate > 0 AND sample_rate <= 1),
+    scorers JSONB NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+-- Score of the output of a span, computed by a scorer of an online eval rule
+CREATE TABLE span_score (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    span_id INT NOT NULL REFERENCES span(id),
+    online_eval_rule_id INT NOT NULL REFERENCES online_eval_rule(id),
+    metric TEXT NOT NULL,
+    score DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (span_id, online_eval_rule_id, metric)
+);
+
+CREATE INDEX span_ts_start_idx ON span (ts_start);
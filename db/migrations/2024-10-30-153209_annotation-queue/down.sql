@@ -0,0 +1,3 @@
+DROP TABLE annotation;
+DROP TABLE annotation_item;
+DROP TABLE annotation_queue;
@@ -0,0 +1,32 @@
+-- Named queue of spans for reviewers to annotate against a rubric
+CREATE TABLE annotation_queue (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    rubric JSONB NOT NULL,
+    annotations_per_item INT NOT NULL CHECK (annotations_per_item > 0),
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+-- Span pushed into a queue. A claim reserves the item for a reviewer until it expires.
+CREATE TABLE annotation_item (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    annotation_queue_id INT NOT NULL REFERENCES annotation_queue(id) ON DELETE CASCADE,
+    span_id INT NOT NULL REFERENCES span(id),
+    claimed_by TEXT,
+    claimed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (annotation_queue_id, span_id)
+);
+
+-- Answers of a reviewer to the rubric of the queue of an item
+CREATE TABLE annotation (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    annotation_item_id INT NOT NULL REFERENCES annotation_item(id) ON DELETE CASCADE,
+    annotator TEXT NOT NULL,
+    answers JSONB NOT NULL,
+    expected_output JSONB,
+    comment TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (annotation_item_id, annotator)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE dataset_version_case DROP COLUMN span_id;
+ALTER TABLE dataset_case DROP COLUMN span_id;
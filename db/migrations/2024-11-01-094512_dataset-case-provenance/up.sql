@@ -0,0 +1,3 @@
+-- Span a case was built from, e.g. a production answer turned into a regression case
+ALTER TABLE dataset_case ADD COLUMN span_id INT REFERENCES span(id);
+ALTER TABLE dataset_version_case ADD COLUMN span_id INT REFERENCES span(id);
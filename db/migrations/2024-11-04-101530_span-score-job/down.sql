@@ -0,0 +1 @@
+DROP TABLE span_score_job;
@@ -0,0 +1,8 @@
+-- Span sampled by an online eval rule and waiting to be scored, kept until its scoring job ran
+CREATE TABLE span_score_job (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    span_id INT NOT NULL REFERENCES span(id) ON DELETE CASCADE,
+    online_eval_rule_id INT NOT NULL REFERENCES online_eval_rule(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (span_id, online_eval_rule_id)
+);
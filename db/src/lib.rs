@@ -13,3 +13,14 @@ pub fn establish_connection() -> PgConnection {
     PgConnection::establish(&database_url)
         .unwrap_or_else(|_| panic!("Error connecting to {}", database_url))
 }
+
+/// Connect to the database, reporting a missing DATABASE_URL or an unreachable database as an
+/// error rather than panicking
+pub fn try_establish_connection() -> ConnectionResult<PgConnection> {
+    dotenv().ok();
+
+    let database_url = env::var("DATABASE_URL").map_err(|_| {
+        ConnectionError::InvalidConnectionUrl("DATABASE_URL must be set".to_string())
+    })?;
+    PgConnection::establish(&database_url)
+}
@@ -0,0 +1,72 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::annotation::dsl::annotation;
+use diesel::prelude::*;
+
+/// Answers of a reviewer to the rubric of the queue of an item
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::annotation)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Annotation {
+    pub id: i32,
+    pub annotation_item_id: i32,
+    pub annotator: String,
+    pub answers: serde_json::Value,
+    pub expected_output: Option<serde_json::Value>,
+    pub comment: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::annotation)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableAnnotation {
+    pub annotation_item_id: i32,
+    pub annotator: String,
+    pub answers: serde_json::Value,
+    pub expected_output: Option<serde_json::Value>,
+    pub comment: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, annotation> {
+    type Entity = Annotation;
+    type InsertableEntity = InsertableAnnotation;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::annotation::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, annotation> {
+    /// Annotations of the items of a queue, ordered by item and submission
+    pub fn find_by_queue(&mut self, annotation_queue_id: i32) -> QueryResult<Vec<Annotation>> {
+        use crate::schema::{annotation::columns, annotation_item};
+
+        self.table
+            .inner_join(annotation_item::table)
+            .filter(annotation_item::annotation_queue_id.eq(annotation_queue_id))
+            .select(Annotation::as_select())
+            .order((columns::annotation_item_id.asc(), columns::id.asc()))
+            .load::<Annotation>(self.connection)
+    }
+}
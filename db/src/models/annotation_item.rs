@@ -0,0 +1,72 @@
+use crate::models::repository::{insert_batch_size, DieselRepository, Repository};
+use crate::schema::annotation_item::dsl::annotation_item;
+use diesel::prelude::*;
+
+const INSERT_BATCH_SIZE: usize = insert_batch_size(5);
+
+/// Span pushed into an annotation queue
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::annotation_item)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AnnotationItem {
+    pub id: i32,
+    pub annotation_queue_id: i32,
+    pub span_id: i32,
+    pub claimed_by: Option<String>,
+    pub claimed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::annotation_item)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableAnnotationItem {
+    pub annotation_queue_id: i32,
+    pub span_id: i32,
+    pub claimed_by: Option<String>,
+    pub claimed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, annotation_item> {
+    type Entity = AnnotationItem;
+    type InsertableEntity = InsertableAnnotationItem;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::annotation_item::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, annotation_item> {
+    /// Push spans into a queue, skipping the spans already in it
+    pub fn create_missing(&mut self, entities: &[InsertableAnnotationItem]) -> QueryResult<usize> {
+        let mut inserted = 0;
+        for chunk in entities.chunks(INSERT_BATCH_SIZE) {
+            inserted += diesel::insert_into(self.table)
+                .values(chunk)
+                .on_conflict_do_nothing()
+                .execute(self.connection)?;
+        }
+        Ok(inserted)
+    }
+}
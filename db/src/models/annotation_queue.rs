@@ -0,0 +1,67 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::annotation_queue::dsl::annotation_queue;
+use diesel::prelude::*;
+
+/// Named queue of spans for reviewers to annotate against a rubric
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::annotation_queue)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AnnotationQueue {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub rubric: serde_json::Value,
+    pub annotations_per_item: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::annotation_queue)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableAnnotationQueue {
+    pub name: String,
+    pub description: Option<String>,
+    pub rubric: serde_json::Value,
+    pub annotations_per_item: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, annotation_queue> {
+    type Entity = AnnotationQueue;
+    type InsertableEntity = InsertableAnnotationQueue;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::annotation_queue::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, annotation_queue> {
+    pub fn find_by_name(&mut self, name: &str) -> QueryResult<Option<AnnotationQueue>> {
+        use crate::schema::annotation_queue::columns;
+
+        self.table
+            .filter(columns::name.eq(name))
+            .first::<AnnotationQueue>(self.connection)
+            .optional()
+    }
+}
@@ -0,0 +1,62 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::dataset::dsl::dataset;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::dataset)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Dataset {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::dataset)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableDataset {
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, dataset> {
+    type Entity = Dataset;
+    type InsertableEntity = InsertableDataset;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::dataset::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, dataset> {
+    pub fn find_by_name(&mut self, name: &str) -> QueryResult<Option<Dataset>> {
+        use crate::schema::dataset::columns;
+
+        self.table
+            .filter(columns::name.eq(name))
+            .first::<Dataset>(self.connection)
+            .optional()
+    }
+}
@@ -0,0 +1,97 @@
+use crate::models::repository::{insert_batch_size, DieselRepository, Repository};
+use crate::schema::dataset_case::dsl::dataset_case;
+use diesel::prelude::*;
+
+const INSERT_BATCH_SIZE: usize = insert_batch_size(4);
+
+/// Case in the working set of a dataset
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::dataset_case)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DatasetCase {
+    pub id: i32,
+    pub dataset_id: i32,
+    pub eval_hash: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub span_id: Option<i32>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::dataset_case)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableDatasetCase {
+    pub dataset_id: i32,
+    pub eval_hash: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub span_id: Option<i32>,
+}
+
+impl<'a> Repository for DieselRepository<'a, dataset_case> {
+    type Entity = DatasetCase;
+    type InsertableEntity = InsertableDatasetCase;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::dataset_case::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, dataset_case> {
+    /// Add cases to the working set, skipping those already in it
+    pub fn create_missing(&mut self, entities: &[InsertableDatasetCase]) -> QueryResult<usize> {
+        let mut inserted = 0;
+        for chunk in entities.chunks(INSERT_BATCH_SIZE) {
+            inserted += diesel::insert_into(self.table)
+                .values(chunk)
+                .on_conflict_do_nothing()
+                .execute(self.connection)?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn delete_by_hashes(
+        &mut self,
+        dataset_id: i32,
+        eval_hashes: &[String],
+    ) -> QueryResult<usize> {
+        use crate::schema::dataset_case::columns;
+
+        diesel::delete(
+            self.table
+                .filter(columns::dataset_id.eq(dataset_id))
+                .filter(columns::eval_hash.eq_any(eval_hashes)),
+        )
+        .execute(self.connection)
+    }
+
+    /// Hashes of the cases in the working set along with the spans they were built from, in
+    /// sorted order
+    pub fn find_provenance(&mut self, dataset_id: i32) -> QueryResult<Vec<(String, Option<i32>)>> {
+        use crate::schema::dataset_case::columns;
+
+        self.table
+            .filter(columns::dataset_id.eq(dataset_id))
+            .order(columns::eval_hash.asc())
+            .select((columns::eval_hash, columns::span_id))
+            .load::<(String, Option<i32>)>(self.connection)
+    }
+}
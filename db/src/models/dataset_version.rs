@@ -0,0 +1,82 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::dataset_version::dsl::dataset_version;
+use diesel::prelude::*;
+
+/// Immutable snapshot of the working set of a dataset
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::dataset_version)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DatasetVersion {
+    pub id: i32,
+    pub dataset_id: i32,
+    pub version: i32,
+    pub case_count: i32,
+    pub content_hash: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::dataset_version)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableDatasetVersion {
+    pub dataset_id: i32,
+    pub version: i32,
+    pub case_count: i32,
+    pub content_hash: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, dataset_version> {
+    type Entity = DatasetVersion;
+    type InsertableEntity = InsertableDatasetVersion;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::dataset_version::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, dataset_version> {
+    pub fn find_latest(&mut self, dataset_id: i32) -> QueryResult<Option<DatasetVersion>> {
+        use crate::schema::dataset_version::columns;
+
+        self.table
+            .filter(columns::dataset_id.eq(dataset_id))
+            .order(columns::version.desc())
+            .first::<DatasetVersion>(self.connection)
+            .optional()
+    }
+
+    pub fn find_by_version(
+        &mut self,
+        dataset_id: i32,
+        version: i32,
+    ) -> QueryResult<Option<DatasetVersion>> {
+        use crate::schema::dataset_version::columns;
+
+        self.table
+            .filter(columns::dataset_id.eq(dataset_id))
+            .filter(columns::version.eq(version))
+            .first::<DatasetVersion>(self.connection)
+            .optional()
+    }
+}
@@ -0,0 +1,76 @@
+use crate::models::repository::{insert_batch_size, DieselRepository, Repository};
+use crate::schema::dataset_version_case::dsl::dataset_version_case;
+use diesel::prelude::*;
+
+const INSERT_BATCH_SIZE: usize = insert_batch_size(3);
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::dataset_version_case)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DatasetVersionCase {
+    pub id: i32,
+    pub dataset_version_id: i32,
+    pub eval_hash: String,
+    pub span_id: Option<i32>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::dataset_version_case)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableDatasetVersionCase {
+    pub dataset_version_id: i32,
+    pub eval_hash: String,
+    pub span_id: Option<i32>,
+}
+
+impl<'a> Repository for DieselRepository<'a, dataset_version_case> {
+    type Entity = DatasetVersionCase;
+    type InsertableEntity = InsertableDatasetVersionCase;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::dataset_version_case::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, dataset_version_case> {
+    pub fn create_many(&mut self, entities: &[InsertableDatasetVersionCase]) -> QueryResult<usize> {
+        let mut inserted = 0;
+        for chunk in entities.chunks(INSERT_BATCH_SIZE) {
+            inserted += diesel::insert_into(self.table)
+                .values(chunk)
+                .execute(self.connection)?;
+        }
+        Ok(inserted)
+    }
+
+    /// Hashes of the cases in a version, in sorted order
+    pub fn find_hashes(&mut self, dataset_version_id: i32) -> QueryResult<Vec<String>> {
+        use crate::schema::dataset_version_case::columns;
+
+        self.table
+            .filter(columns::dataset_version_id.eq(dataset_version_id))
+            .order(columns::eval_hash.asc())
+            .select(columns::eval_hash)
+            .load::<String>(self.connection)
+    }
+}
@@ -0,0 +1,77 @@
+use crate::models::repository::{insert_batch_size, DieselRepository, Repository};
+use crate::schema::eval_case::dsl::eval_case;
+use diesel::prelude::*;
+
+const INSERT_BATCH_SIZE: usize = insert_batch_size(4);
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::eval_case)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EvalCase {
+    pub eval_hash: String,
+    pub input: Option<serde_json::Value>,
+    pub expected_output: Option<serde_json::Value>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::eval_case)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableEvalCase {
+    pub eval_hash: String,
+    pub input: Option<serde_json::Value>,
+    pub expected_output: Option<serde_json::Value>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, eval_case> {
+    type Entity = EvalCase;
+    type InsertableEntity = InsertableEvalCase;
+    type Id = String;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::eval_case::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, eval_case> {
+    /// Store cases that are not known yet. A case is identified by its hash, so the first
+    /// recorded input and expected output are kept.
+    pub fn create_missing(&mut self, entities: &[InsertableEvalCase]) -> QueryResult<usize> {
+        let mut inserted = 0;
+        for chunk in entities.chunks(INSERT_BATCH_SIZE) {
+            inserted += diesel::insert_into(self.table)
+                .values(chunk)
+                .on_conflict_do_nothing()
+                .execute(self.connection)?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn find_by_hashes(&mut self, eval_hashes: &[String]) -> QueryResult<Vec<EvalCase>> {
+        use crate::schema::eval_case::columns;
+
+        self.table
+            .filter(columns::eval_hash.eq_any(eval_hashes))
+            .load::<EvalCase>(self.connection)
+    }
+}
@@ -0,0 +1,67 @@
+use crate::models::repository::{insert_batch_size, DieselRepository, Repository};
+use crate::schema::eval_output::dsl::eval_output;
+use diesel::prelude::*;
+
+const INSERT_BATCH_SIZE: usize = insert_batch_size(3);
+
+/// Output produced by a prompt for an eval case, shared by every trial that produced it
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::eval_output)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EvalOutput {
+    pub content_hash: String,
+    pub content: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::eval_output)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableEvalOutput {
+    pub content_hash: String,
+    pub content: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, eval_output> {
+    type Entity = EvalOutput;
+    type InsertableEntity = InsertableEvalOutput;
+    type Id = String;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::eval_output::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, eval_output> {
+    /// Store outputs whose content hash is not known yet
+    pub fn create_missing(&mut self, entities: &[InsertableEvalOutput]) -> QueryResult<usize> {
+        let mut inserted = 0;
+        for chunk in entities.chunks(INSERT_BATCH_SIZE) {
+            inserted += diesel::insert_into(self.table)
+                .values(chunk)
+                .on_conflict_do_nothing()
+                .execute(self.connection)?;
+        }
+        Ok(inserted)
+    }
+}
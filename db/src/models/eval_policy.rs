@@ -0,0 +1,63 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::eval_policy::dsl::eval_policy;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::eval_policy)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EvalPolicy {
+    pub id: i32,
+    pub eval_name: String,
+    pub individual_threshold: f32,
+    pub mean_threshold: f32,
+    pub consistency_threshold: f32,
+    pub higher_is_better: bool,
+    pub min_sample_count: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub metric: String,
+}
+
+#[derive(Insertable, Selectable, Queryable, AsChangeset)]
+#[diesel(table_name = crate::schema::eval_policy)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableEvalPolicy {
+    pub eval_name: String,
+    pub individual_threshold: f32,
+    pub mean_threshold: f32,
+    pub consistency_threshold: f32,
+    pub higher_is_better: bool,
+    pub min_sample_count: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub metric: String,
+}
+
+impl<'a> Repository for DieselRepository<'a, eval_policy> {
+    type Entity = EvalPolicy;
+    type InsertableEntity = InsertableEvalPolicy;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::eval_policy::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
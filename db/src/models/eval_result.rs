@@ -1,7 +1,11 @@
 use crate::models::repository::{DieselRepository, Repository};
 use crate::schema::eval_result::dsl::eval_result;
 use diesel::prelude::*;
-use serde::{Deserialize, Serialize};
+
+/// Run still receiving scores
+pub const STATUS_OPEN: &str = "open";
+/// Run with all of its scores, which other runs can be compared against
+pub const STATUS_FINISHED: &str = "finished";
 
 #[derive(Queryable, Selectable, Debug)]
 #[diesel(table_name = crate::schema::eval_result)]
@@ -9,8 +13,21 @@ use serde::{Deserialize, Serialize};
 pub struct EvalResult {
     pub id: i32,
     pub eval_id: i32,
-    pub scores: serde_json::Value,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub dataset_version_id: Option<i32>,
+    pub git_sha: Option<String>,
+    pub branch: Option<String>,
+    pub ci_run_url: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub attributes: serde_json::Value,
+    pub status: String,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub run_key: Option<String>,
+    pub requested_base_version: Option<String>,
+    pub requested_base_label: Option<String>,
+    pub requested_base_branch: Option<String>,
+    pub scorers: serde_json::Value,
 }
 
 #[derive(Insertable, Selectable, Queryable)]
@@ -18,18 +35,23 @@ pub struct EvalResult {
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct InsertableEvalResult {
     pub eval_id: i32,
-    pub scores: serde_json::Value,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub dataset_version_id: Option<i32>,
+    pub git_sha: Option<String>,
+    pub branch: Option<String>,
+    pub ci_run_url: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub attributes: serde_json::Value,
+    pub status: String,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub run_key: Option<String>,
+    pub requested_base_version: Option<String>,
+    pub requested_base_label: Option<String>,
+    pub requested_base_branch: Option<String>,
+    pub scorers: serde_json::Value,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct SingleEvalScore {
-    pub eval_hash: String,
-    pub score: f32,
-}
-
-pub type EvalRunScores = Vec<SingleEvalScore>;
-
 impl<'a> Repository for DieselRepository<'a, eval_result> {
     type Entity = EvalResult;
     type InsertableEntity = InsertableEvalResult;
@@ -58,3 +80,15 @@ impl<'a> Repository for DieselRepository<'a, eval_result> {
             .map(|_| ())
     }
 }
+
+impl<'a> DieselRepository<'a, eval_result> {
+    /// Run started with the given client-chosen key
+    pub fn find_by_run_key(&mut self, run_key: &str) -> QueryResult<Option<EvalResult>> {
+        use crate::schema::eval_result::columns;
+
+        self.table
+            .filter(columns::run_key.eq(run_key))
+            .first::<EvalResult>(self.connection)
+            .optional()
+    }
+}
@@ -0,0 +1,68 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::eval_run_batch::dsl::eval_run_batch;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::eval_run_batch)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EvalRunBatch {
+    pub id: i32,
+    pub eval_result_id: i32,
+    pub sequence: i32,
+    pub first_trial_index: i32,
+    pub trial_count: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::eval_run_batch)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableEvalRunBatch {
+    pub eval_result_id: i32,
+    pub sequence: i32,
+    pub first_trial_index: i32,
+    pub trial_count: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, eval_run_batch> {
+    type Entity = EvalRunBatch;
+    type InsertableEntity = InsertableEvalRunBatch;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::eval_run_batch::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, eval_run_batch> {
+    /// Most recently appended batch of a run
+    pub fn find_last(&mut self, eval_result_id: i32) -> QueryResult<Option<EvalRunBatch>> {
+        use crate::schema::eval_run_batch::columns;
+
+        self.table
+            .filter(columns::eval_result_id.eq(eval_result_id))
+            .order(columns::sequence.desc())
+            .first::<EvalRunBatch>(self.connection)
+            .optional()
+    }
+}
@@ -0,0 +1,201 @@
+use crate::models::repository::{insert_batch_size, DieselRepository, Repository};
+use crate::schema::eval_score::dsl::eval_score;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Double, Integer, Text};
+
+/// Metric that a plain `EvalScore.score` is stored under when no named metrics are given
+pub const DEFAULT_METRIC: &str = "score";
+
+const INSERT_BATCH_SIZE: usize = insert_batch_size(6);
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::eval_score)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EvalScore {
+    pub id: i32,
+    pub eval_result_id: i32,
+    pub eval_hash: String,
+    pub metric: String,
+    pub value: f32,
+    pub output_hash: Option<String>,
+    pub trial_index: i32,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::eval_score)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableEvalScore {
+    pub eval_result_id: i32,
+    pub eval_hash: String,
+    pub metric: String,
+    pub value: f32,
+    pub output_hash: Option<String>,
+    pub trial_index: i32,
+}
+
+/// Trials of a single eval case within a run, aggregated in the database
+#[derive(QueryableByName, Debug)]
+pub struct CaseAggregate {
+    #[diesel(sql_type = Text)]
+    pub eval_hash: String,
+    #[diesel(sql_type = BigInt)]
+    pub trials: i64,
+    #[diesel(sql_type = Double)]
+    pub mean: f64,
+    #[diesel(sql_type = Double)]
+    pub variance: f64,
+}
+
+/// Values of a metric within a run, aggregated in the database
+#[derive(Queryable, Debug)]
+pub struct MetricAggregate {
+    pub eval_result_id: i32,
+    pub metric: String,
+    pub count: i64,
+    pub mean: Option<f64>,
+}
+
+impl<'a> Repository for DieselRepository<'a, eval_score> {
+    type Entity = EvalScore;
+    type InsertableEntity = InsertableEvalScore;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::eval_score::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, eval_score> {
+    pub fn create_many(&mut self, entities: &[InsertableEvalScore]) -> QueryResult<usize> {
+        let mut inserted = 0;
+        for chunk in entities.chunks(INSERT_BATCH_SIZE) {
+            inserted += diesel::insert_into(self.table)
+                .values(chunk)
+                .execute(self.connection)?;
+        }
+        Ok(inserted)
+    }
+
+    /// Every score of a run, grouped by trial
+    pub fn find_trials(&mut self, eval_result_id: i32) -> QueryResult<Vec<EvalScore>> {
+        use crate::schema::eval_score::columns;
+
+        self.table
+            .filter(columns::eval_result_id.eq(eval_result_id))
+            .order((columns::trial_index.asc(), columns::id.asc()))
+            .load::<EvalScore>(self.connection)
+    }
+
+    /// Hashes of the cases scored within a run, in sorted order
+    pub fn find_hashes(&mut self, eval_result_id: i32) -> QueryResult<Vec<String>> {
+        use crate::schema::eval_score::columns;
+
+        self.table
+            .filter(columns::eval_result_id.eq(eval_result_id))
+            .select(columns::eval_hash)
+            .distinct()
+            .order(columns::eval_hash.asc())
+            .load::<String>(self.connection)
+    }
+
+    /// Names of the metrics recorded in any of the given runs, in sorted order
+    pub fn find_metrics(&mut self, eval_result_ids: &[i32]) -> QueryResult<Vec<String>> {
+        use crate::schema::eval_score::columns;
+
+        self.table
+            .filter(columns::eval_result_id.eq_any(eval_result_ids))
+            .select(columns::metric)
+            .distinct()
+            .order(columns::metric.asc())
+            .load::<String>(self.connection)
+    }
+
+    /// Number of trials, mean and sample variance of every case of a metric within a run
+    pub fn aggregate_by_case(
+        &mut self,
+        eval_result_id: i32,
+        metric: &str,
+    ) -> QueryResult<Vec<CaseAggregate>> {
+        diesel::sql_query(
+            "SELECT eval_hash, COUNT(*) AS trials, AVG(value)::FLOAT8 AS mean, \
+                    COALESCE(VAR_SAMP(value), 0)::FLOAT8 AS variance \
+             FROM eval_score \
+             WHERE eval_result_id = $1 AND metric = $2 \
+             GROUP BY eval_hash \
+             ORDER BY eval_hash",
+        )
+        .bind::<Integer, _>(eval_result_id)
+        .bind::<Text, _>(metric)
+        .load::<CaseAggregate>(self.connection)
+    }
+
+    /// Number of values and mean value of every metric recorded in each of the given runs
+    pub fn summarize_runs(&mut self, eval_result_ids: &[i32]) -> QueryResult<Vec<MetricAggregate>> {
+        use crate::schema::eval_score::columns;
+
+        self.table
+            .filter(columns::eval_result_id.eq_any(eval_result_ids))
+            .group_by((columns::eval_result_id, columns::metric))
+            .select((
+                columns::eval_result_id,
+                columns::metric,
+                diesel::dsl::count_star(),
+                diesel::dsl::avg(columns::value),
+            ))
+            .order(columns::metric.asc())
+            .load::<MetricAggregate>(self.connection)
+    }
+
+    /// Number of trials of each of the given runs
+    pub fn count_trials(&mut self, eval_result_ids: &[i32]) -> QueryResult<Vec<(i32, i64)>> {
+        use crate::schema::eval_score::columns;
+
+        self.table
+            .filter(columns::eval_result_id.eq_any(eval_result_ids))
+            .group_by(columns::eval_result_id)
+            .select((
+                columns::eval_result_id,
+                diesel::dsl::count(columns::trial_index).aggregate_distinct(),
+            ))
+            .load::<(i32, i64)>(self.connection)
+    }
+
+    /// Output recorded for each of the given cases within a run, taken from the first trial
+    /// that stored one
+    pub fn find_outputs(
+        &mut self,
+        eval_result_id: i32,
+        eval_hashes: &[String],
+    ) -> QueryResult<Vec<(String, serde_json::Value)>> {
+        use crate::schema::eval_output;
+        use crate::schema::eval_score::columns;
+
+        self.table
+            .inner_join(eval_output::table)
+            .filter(columns::eval_result_id.eq(eval_result_id))
+            .filter(columns::eval_hash.eq_any(eval_hashes))
+            .distinct_on(columns::eval_hash)
+            .order((columns::eval_hash, columns::id))
+            .select((columns::eval_hash, eval_output::content))
+            .load::<(String, serde_json::Value)>(self.connection)
+    }
+}
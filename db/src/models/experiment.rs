@@ -0,0 +1,63 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::experiment::dsl::experiment;
+use diesel::prelude::*;
+
+/// Online experiment splitting the traffic of a prompt between versions
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::experiment)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Experiment {
+    pub id: i32,
+    pub name: String,
+    pub prompt_name: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::experiment)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableExperiment {
+    pub name: String,
+    pub prompt_name: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, experiment> {
+    type Entity = Experiment;
+    type InsertableEntity = InsertableExperiment;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::experiment::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, experiment> {
+    pub fn find_by_name(&mut self, name: &str) -> QueryResult<Option<Experiment>> {
+        use crate::schema::experiment::columns;
+
+        self.table
+            .filter(columns::name.eq(name))
+            .first::<Experiment>(self.connection)
+            .optional()
+    }
+}
@@ -0,0 +1,52 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::experiment_arm::dsl::experiment_arm;
+use diesel::prelude::*;
+
+/// Prompt version served to a share of the subjects of an experiment, proportional to its weight
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::experiment_arm)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ExperimentArm {
+    pub id: i32,
+    pub experiment_id: i32,
+    pub prompt_version_id: i32,
+    pub weight: i32,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::experiment_arm)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableExperimentArm {
+    pub experiment_id: i32,
+    pub prompt_version_id: i32,
+    pub weight: i32,
+}
+
+impl<'a> Repository for DieselRepository<'a, experiment_arm> {
+    type Entity = ExperimentArm;
+    type InsertableEntity = InsertableExperimentArm;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::experiment_arm::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
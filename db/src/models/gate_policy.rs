@@ -0,0 +1,57 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::gate_policy::dsl::gate_policy;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::gate_policy)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct GatePolicy {
+    pub id: i32,
+    pub prompt_name: String,
+    pub fail_on_regression: bool,
+    pub max_case_regression: Option<f32>,
+    pub fail_on_unknown: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::gate_policy)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableGatePolicy {
+    pub prompt_name: String,
+    pub fail_on_regression: bool,
+    pub max_case_regression: Option<f32>,
+    pub fail_on_unknown: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, gate_policy> {
+    type Entity = GatePolicy;
+    type InsertableEntity = InsertableGatePolicy;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::gate_policy::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
@@ -1,11 +1,43 @@
 pub mod repository;
 
+pub mod annotation;
+pub mod annotation_item;
+pub mod annotation_queue;
+
 pub mod span;
+pub mod span_feedback;
+pub mod span_score;
+pub mod span_score_job;
 
 pub mod test_registration;
 pub mod test_version;
 
+pub mod dataset;
+pub mod dataset_case;
+pub mod dataset_version;
+pub mod dataset_version_case;
+
 pub mod eval;
+pub mod eval_case;
+pub mod eval_output;
+pub mod eval_policy;
 pub mod eval_result;
+pub mod eval_run_batch;
+pub mod eval_score;
+
+pub mod experiment;
+pub mod experiment_arm;
+
+pub mod gate_policy;
 
+pub mod online_eval_rule;
+
+pub mod prompt_label;
+pub mod prompt_label_history;
 pub mod prompt_version;
+
+pub mod scorer_plugin;
+
+pub mod webhook_delivery;
+pub mod webhook_delivery_attempt;
+pub mod webhook_endpoint;
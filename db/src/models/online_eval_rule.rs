@@ -0,0 +1,80 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::online_eval_rule::dsl::online_eval_rule;
+use diesel::prelude::*;
+
+/// Scorers applied to a sample of the ingested spans matching an operation name and attributes
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::online_eval_rule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OnlineEvalRule {
+    pub id: i32,
+    pub name: String,
+    pub operation_name: Option<String>,
+    pub attributes: serde_json::Value,
+    pub sample_rate: f64,
+    pub scorers: serde_json::Value,
+    pub enabled: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::online_eval_rule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableOnlineEvalRule {
+    pub name: String,
+    pub operation_name: Option<String>,
+    pub attributes: serde_json::Value,
+    pub sample_rate: f64,
+    pub scorers: serde_json::Value,
+    pub enabled: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, online_eval_rule> {
+    type Entity = OnlineEvalRule;
+    type InsertableEntity = InsertableOnlineEvalRule;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::online_eval_rule::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, online_eval_rule> {
+    pub fn find_by_name(&mut self, name: &str) -> QueryResult<Option<OnlineEvalRule>> {
+        use crate::schema::online_eval_rule::columns;
+
+        self.table
+            .filter(columns::name.eq(name))
+            .first::<OnlineEvalRule>(self.connection)
+            .optional()
+    }
+
+    pub fn find_enabled(&mut self) -> QueryResult<Vec<OnlineEvalRule>> {
+        use crate::schema::online_eval_rule::columns;
+
+        self.table
+            .filter(columns::enabled.eq(true))
+            .order(columns::id.asc())
+            .load::<OnlineEvalRule>(self.connection)
+    }
+}
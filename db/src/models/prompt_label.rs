@@ -0,0 +1,72 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::prompt_label::dsl::prompt_label;
+use diesel::prelude::*;
+
+/// Named pointer to a version of a prompt, e.g. the version deployed to production
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::prompt_label)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PromptLabel {
+    pub id: i32,
+    pub prompt_name: String,
+    pub label: String,
+    pub prompt_version_id: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::prompt_label)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertablePromptLabel {
+    pub prompt_name: String,
+    pub label: String,
+    pub prompt_version_id: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, prompt_label> {
+    type Entity = PromptLabel;
+    type InsertableEntity = InsertablePromptLabel;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::prompt_label::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, prompt_label> {
+    pub fn find_by_label(
+        &mut self,
+        prompt_name: &str,
+        label: &str,
+    ) -> QueryResult<Option<PromptLabel>> {
+        use crate::schema::prompt_label::columns;
+
+        self.table
+            .filter(columns::prompt_name.eq(prompt_name))
+            .filter(columns::label.eq(label))
+            .first::<PromptLabel>(self.connection)
+            .optional()
+    }
+}
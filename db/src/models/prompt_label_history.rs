@@ -0,0 +1,58 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::prompt_label_history::dsl::prompt_label_history;
+use diesel::prelude::*;
+
+/// Move of a label. A missing previous version marks its creation and a missing version its removal.
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::prompt_label_history)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PromptLabelHistory {
+    pub id: i32,
+    pub prompt_name: String,
+    pub label: String,
+    pub previous_prompt_version_id: Option<i32>,
+    pub prompt_version_id: Option<i32>,
+    pub actor: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::prompt_label_history)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertablePromptLabelHistory {
+    pub prompt_name: String,
+    pub label: String,
+    pub previous_prompt_version_id: Option<i32>,
+    pub prompt_version_id: Option<i32>,
+    pub actor: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, prompt_label_history> {
+    type Entity = PromptLabelHistory;
+    type InsertableEntity = InsertablePromptLabelHistory;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::prompt_label_history::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
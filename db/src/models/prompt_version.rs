@@ -11,6 +11,8 @@ pub struct PromptVersion {
     pub name: String,
     pub version: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub template: Option<serde_json::Value>,
+    pub content_hash: Option<String>,
 }
 
 #[derive(Insertable, Selectable, Queryable)]
@@ -20,6 +22,8 @@ pub struct InsertablePromptVersion {
     pub name: String,
     pub version: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub template: Option<serde_json::Value>,
+    pub content_hash: Option<String>,
 }
 
 impl<'a> Repository for DieselRepository<'a, prompt_version> {
@@ -50,3 +54,19 @@ impl<'a> Repository for DieselRepository<'a, prompt_version> {
             .map(|_| ())
     }
 }
+
+impl<'a> DieselRepository<'a, prompt_version> {
+    pub fn find_by_version(
+        &mut self,
+        name: &str,
+        version: &str,
+    ) -> QueryResult<Option<PromptVersion>> {
+        use crate::schema::prompt_version::columns;
+
+        self.table
+            .filter(columns::name.eq(name))
+            .filter(columns::version.eq(version))
+            .first::<PromptVersion>(self.connection)
+            .optional()
+    }
+}
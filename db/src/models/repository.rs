@@ -1,5 +1,14 @@
 use diesel::QueryResult;
 
+/// Largest number of bind parameters Postgres accepts in a single statement
+const MAX_BIND_PARAMETERS: usize = 65_535;
+
+/// Rows per insert statement of a table inserting the given number of columns per row, so that
+/// batched inserts stay within the bind parameter limit
+pub const fn insert_batch_size(columns: usize) -> usize {
+    MAX_BIND_PARAMETERS / columns
+}
+
 #[allow(dead_code)]
 pub trait Repository {
     type Entity;
@@ -0,0 +1,136 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::scorer_plugin::dsl::scorer_plugin;
+use diesel::prelude::*;
+
+/// WebAssembly module implementing a custom scorer
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::scorer_plugin)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ScorerPlugin {
+    pub id: i32,
+    pub name: String,
+    pub version: String,
+    pub module: Vec<u8>,
+    pub content_hash: String,
+    pub needs_expected: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Plugin version without its module, along with the size of the module
+#[derive(Queryable, Debug)]
+pub struct ScorerPluginSummary {
+    pub name: String,
+    pub version: String,
+    pub content_hash: String,
+    pub needs_expected: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub size_bytes: i32,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::scorer_plugin)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableScorerPlugin {
+    pub name: String,
+    pub version: String,
+    pub module: Vec<u8>,
+    pub content_hash: String,
+    pub needs_expected: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, scorer_plugin> {
+    type Entity = ScorerPlugin;
+    type InsertableEntity = InsertableScorerPlugin;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::scorer_plugin::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, scorer_plugin> {
+    pub fn find_by_version(
+        &mut self,
+        name: &str,
+        version: &str,
+    ) -> QueryResult<Option<ScorerPlugin>> {
+        use crate::schema::scorer_plugin::columns;
+
+        self.table
+            .filter(columns::name.eq(name))
+            .filter(columns::version.eq(version))
+            .first::<ScorerPlugin>(self.connection)
+            .optional()
+    }
+
+    /// Most recently registered version of a plugin
+    pub fn find_latest(&mut self, name: &str) -> QueryResult<Option<ScorerPlugin>> {
+        use crate::schema::scorer_plugin::columns;
+
+        self.table
+            .filter(columns::name.eq(name))
+            .order((columns::created_at.desc(), columns::id.desc()))
+            .first::<ScorerPlugin>(self.connection)
+            .optional()
+    }
+
+    /// Module with the given content hash, whichever plugin versions share it
+    pub fn find_module(&mut self, content_hash: &str) -> QueryResult<Option<Vec<u8>>> {
+        use crate::schema::scorer_plugin::columns;
+
+        self.table
+            .filter(columns::content_hash.eq(content_hash))
+            .select(columns::module)
+            .first::<Vec<u8>>(self.connection)
+            .optional()
+    }
+
+    /// Plugin versions by name and registration time, optionally of a single plugin, without
+    /// loading their modules
+    pub fn find_summaries(&mut self, name: Option<&str>) -> QueryResult<Vec<ScorerPluginSummary>> {
+        use crate::schema::scorer_plugin::columns;
+        use diesel::dsl::sql;
+        use diesel::sql_types::Int4;
+
+        let mut query = self
+            .table
+            .select((
+                columns::name,
+                columns::version,
+                columns::content_hash,
+                columns::needs_expected,
+                columns::created_at,
+                sql::<Int4>("octet_length(module)"),
+            ))
+            .order((
+                columns::name.asc(),
+                columns::created_at.asc(),
+                columns::id.asc(),
+            ))
+            .into_boxed();
+        if let Some(name) = name {
+            query = query.filter(columns::name.eq(name));
+        }
+        query.load::<ScorerPluginSummary>(self.connection)
+    }
+}
@@ -2,7 +2,7 @@ use crate::models::repository::{DieselRepository, Repository};
 use crate::schema::span::dsl::span;
 use diesel::prelude::*;
 
-#[derive(Queryable, Selectable)]
+#[derive(Queryable, Selectable, Clone)]
 #[diesel(table_name = crate::schema::span)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[allow(dead_code)]
@@ -13,6 +13,10 @@ pub struct Span {
     pub operation_name: String,
     pub parent_span_id: Option<i32>,
     pub external_uuid: Option<uuid::Uuid>,
+    pub attributes: serde_json::Value,
+    pub input: Option<serde_json::Value>,
+    pub output: Option<serde_json::Value>,
+    pub prompt_version_id: Option<i32>,
 }
 
 #[derive(Insertable, Selectable, Queryable)]
@@ -24,6 +28,10 @@ pub struct InsertableSpan {
     pub operation_name: String,
     pub parent_span_id: Option<i32>,
     pub external_uuid: Option<uuid::Uuid>,
+    pub attributes: serde_json::Value,
+    pub input: Option<serde_json::Value>,
+    pub output: Option<serde_json::Value>,
+    pub prompt_version_id: Option<i32>,
 }
 
 impl<'a> Repository for DieselRepository<'a, span> {
@@ -52,3 +60,24 @@ impl<'a> Repository for DieselRepository<'a, span> {
             .map(|_| ())
     }
 }
+
+impl<'a> DieselRepository<'a, span> {
+    /// Span reported with the given client side ID
+    pub fn find_by_external_uuid(&mut self, uuid: uuid::Uuid) -> QueryResult<Option<Span>> {
+        use crate::schema::span::columns;
+
+        self.table
+            .filter(columns::external_uuid.eq(uuid))
+            .first::<Span>(self.connection)
+            .optional()
+    }
+
+    /// Spans reported with any of the given client side IDs
+    pub fn find_by_external_uuids(&mut self, uuids: &[uuid::Uuid]) -> QueryResult<Vec<Span>> {
+        use crate::schema::span::columns;
+
+        self.table
+            .filter(columns::external_uuid.eq_any(uuids))
+            .load::<Span>(self.connection)
+    }
+}
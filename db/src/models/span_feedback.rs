@@ -0,0 +1,56 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::span_feedback::dsl::span_feedback;
+use diesel::prelude::*;
+
+/// Score given to a span after the fact, e.g. a user rating of an answer
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::span_feedback)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SpanFeedback {
+    pub id: i32,
+    pub span_id: i32,
+    pub name: String,
+    pub score: f64,
+    pub comment: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::span_feedback)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableSpanFeedback {
+    pub span_id: i32,
+    pub name: String,
+    pub score: f64,
+    pub comment: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, span_feedback> {
+    type Entity = SpanFeedback;
+    type InsertableEntity = InsertableSpanFeedback;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::span_feedback::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
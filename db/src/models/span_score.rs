@@ -0,0 +1,56 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::span_score::dsl::span_score;
+use diesel::prelude::*;
+
+/// Score of the output of a span, computed by a scorer of an online eval rule
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::span_score)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SpanScore {
+    pub id: i32,
+    pub span_id: i32,
+    pub online_eval_rule_id: i32,
+    pub metric: String,
+    pub score: f64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::span_score)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableSpanScore {
+    pub span_id: i32,
+    pub online_eval_rule_id: i32,
+    pub metric: String,
+    pub score: f64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, span_score> {
+    type Entity = SpanScore;
+    type InsertableEntity = InsertableSpanScore;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::span_score::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,63 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::span_score_job::dsl::span_score_job;
+use diesel::prelude::*;
+
+/// Span sampled by an online eval rule and waiting to be scored
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::span_score_job)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SpanScoreJob {
+    pub id: i32,
+    pub span_id: i32,
+    pub online_eval_rule_id: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::span_score_job)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableSpanScoreJob {
+    pub span_id: i32,
+    pub online_eval_rule_id: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, span_score_job> {
+    type Entity = SpanScoreJob;
+    type InsertableEntity = InsertableSpanScoreJob;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::span_score_job::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, span_score_job> {
+    /// Jobs waiting to be run, oldest first
+    pub fn find_pending(&mut self) -> QueryResult<Vec<SpanScoreJob>> {
+        use crate::schema::span_score_job as columns;
+
+        self.table
+            .order(columns::id.asc())
+            .load::<SpanScoreJob>(self.connection)
+    }
+}
@@ -0,0 +1,95 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::webhook_delivery::dsl::webhook_delivery;
+use diesel::prelude::*;
+
+/// Delivery still waiting for a successful attempt
+pub const STATUS_PENDING: &str = "pending";
+/// Delivery acknowledged by the endpoint
+pub const STATUS_DELIVERED: &str = "delivered";
+/// Delivery given up on after running out of attempts
+pub const STATUS_FAILED: &str = "failed";
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::webhook_delivery)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub webhook_endpoint_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempt_count: i32,
+    pub next_attempt_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::webhook_delivery)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableWebhookDelivery {
+    pub webhook_endpoint_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempt_count: i32,
+    pub next_attempt_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, webhook_delivery> {
+    type Entity = WebhookDelivery;
+    type InsertableEntity = InsertableWebhookDelivery;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::webhook_delivery::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, webhook_delivery> {
+    /// Deliveries still waiting for a successful attempt, oldest first
+    pub fn find_pending(&mut self) -> QueryResult<Vec<WebhookDelivery>> {
+        use crate::schema::webhook_delivery as columns;
+
+        self.table
+            .filter(columns::status.eq(STATUS_PENDING))
+            .order(columns::id.asc())
+            .load::<WebhookDelivery>(self.connection)
+    }
+
+    /// Most recent deliveries to an endpoint
+    pub fn find_by_endpoint(
+        &mut self,
+        webhook_endpoint_id: i32,
+        limit: i64,
+    ) -> QueryResult<Vec<WebhookDelivery>> {
+        use crate::schema::webhook_delivery as columns;
+
+        self.table
+            .filter(columns::webhook_endpoint_id.eq(webhook_endpoint_id))
+            .order(columns::id.desc())
+            .limit(limit)
+            .load::<WebhookDelivery>(self.connection)
+    }
+}
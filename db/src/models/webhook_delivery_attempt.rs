@@ -0,0 +1,72 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::webhook_delivery_attempt::dsl::webhook_delivery_attempt;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::webhook_delivery_attempt)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookDeliveryAttempt {
+    pub id: i32,
+    pub webhook_delivery_id: i32,
+    pub attempt: i32,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::webhook_delivery_attempt)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableWebhookDeliveryAttempt {
+    pub webhook_delivery_id: i32,
+    pub attempt: i32,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, webhook_delivery_attempt> {
+    type Entity = WebhookDeliveryAttempt;
+    type InsertableEntity = InsertableWebhookDeliveryAttempt;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::webhook_delivery_attempt::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, webhook_delivery_attempt> {
+    /// Attempts of the given deliveries, in order
+    pub fn find_by_deliveries(
+        &mut self,
+        webhook_delivery_ids: &[i32],
+    ) -> QueryResult<Vec<WebhookDeliveryAttempt>> {
+        use crate::schema::webhook_delivery_attempt as columns;
+
+        self.table
+            .filter(columns::webhook_delivery_id.eq_any(webhook_delivery_ids))
+            .order((columns::webhook_delivery_id.asc(), columns::attempt.asc()))
+            .load::<WebhookDeliveryAttempt>(self.connection)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::models::repository::{DieselRepository, Repository};
+use crate::schema::webhook_endpoint::dsl::webhook_endpoint;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::webhook_endpoint)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookEndpoint {
+    pub id: i32,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<String>,
+    pub eval_name: Option<String>,
+    pub prompt_name: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Insertable, Selectable, Queryable)]
+#[diesel(table_name = crate::schema::webhook_endpoint)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertableWebhookEndpoint {
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<String>,
+    pub eval_name: Option<String>,
+    pub prompt_name: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> Repository for DieselRepository<'a, webhook_endpoint> {
+    type Entity = WebhookEndpoint;
+    type InsertableEntity = InsertableWebhookEndpoint;
+    type Id = i32;
+
+    fn find_all(&mut self) -> QueryResult<Vec<Self::Entity>> {
+        self.table.load::<Self::Entity>(self.connection)
+    }
+
+    fn find_by_id(&mut self, id: Self::Id) -> QueryResult<Self::Entity> {
+        self.table
+            .find(id)
+            .get_result::<Self::Entity>(self.connection)
+    }
+
+    fn create(&mut self, entity: &Self::InsertableEntity) -> QueryResult<Self::Entity> {
+        diesel::insert_into(self.table)
+            .values(entity)
+            .returning(crate::schema::webhook_endpoint::all_columns)
+            .get_result(self.connection)
+    }
+
+    fn delete(&mut self, id: Self::Id) -> QueryResult<()> {
+        diesel::delete(self.table.find(id))
+            .execute(self.connection)
+            .map(|_| ())
+    }
+}
+
+impl<'a> DieselRepository<'a, webhook_endpoint> {
+    /// Endpoints subscribed to an event of the given eval and prompt
+    pub fn find_subscribed(
+        &mut self,
+        event: &str,
+        eval_name: &str,
+        prompt_name: &str,
+    ) -> QueryResult<Vec<WebhookEndpoint>> {
+        use crate::schema::webhook_endpoint as columns;
+
+        self.table
+            .filter(columns::events.contains(vec![event]))
+            .filter(
+                columns::eval_name
+                    .is_null()
+                    .or(columns::eval_name.eq(eval_name)),
+            )
+            .filter(
+                columns::prompt_name
+                    .is_null()
+                    .or(columns::prompt_name.eq(prompt_name)),
+            )
+            .load::<WebhookEndpoint>(self.connection)
+    }
+}
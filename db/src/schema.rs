@@ -1,5 +1,78 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    annotation (id) {
+        id -> Int4,
+        annotation_item_id -> Int4,
+        annotator -> Text,
+        answers -> Jsonb,
+        expected_output -> Nullable<Jsonb>,
+        comment -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    annotation_item (id) {
+        id -> Int4,
+        annotation_queue_id -> Int4,
+        span_id -> Int4,
+        claimed_by -> Nullable<Text>,
+        claimed_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    annotation_queue (id) {
+        id -> Int4,
+        name -> Text,
+        description -> Nullable<Text>,
+        rubric -> Jsonb,
+        annotations_per_item -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    dataset (id) {
+        id -> Int4,
+        name -> Text,
+        description -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    dataset_case (id) {
+        id -> Int4,
+        dataset_id -> Int4,
+        eval_hash -> Text,
+        created_at -> Timestamptz,
+        span_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    dataset_version (id) {
+        id -> Int4,
+        dataset_id -> Int4,
+        version -> Int4,
+        case_count -> Int4,
+        content_hash -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    dataset_version_case (id) {
+        id -> Int4,
+        dataset_version_id -> Int4,
+        eval_hash -> Text,
+        span_id -> Nullable<Int4>,
+    }
+}
+
 diesel::table! {
     eval (id) {
         id -> Int4,
@@ -9,12 +82,110 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    eval_case (eval_hash) {
+        eval_hash -> Text,
+        input -> Nullable<Jsonb>,
+        expected_output -> Nullable<Jsonb>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    eval_output (content_hash) {
+        content_hash -> Text,
+        content -> Jsonb,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    eval_policy (id) {
+        id -> Int4,
+        eval_name -> Text,
+        individual_threshold -> Float4,
+        mean_threshold -> Float4,
+        consistency_threshold -> Float4,
+        higher_is_better -> Bool,
+        min_sample_count -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        metric -> Text,
+    }
+}
+
 diesel::table! {
     eval_result (id) {
         id -> Int4,
         eval_id -> Int4,
-        scores -> Jsonb,
         created_at -> Timestamptz,
+        dataset_version_id -> Nullable<Int4>,
+        git_sha -> Nullable<Text>,
+        branch -> Nullable<Text>,
+        ci_run_url -> Nullable<Text>,
+        model -> Nullable<Text>,
+        temperature -> Nullable<Float4>,
+        attributes -> Jsonb,
+        status -> Text,
+        finished_at -> Nullable<Timestamptz>,
+        run_key -> Nullable<Text>,
+        requested_base_version -> Nullable<Text>,
+        requested_base_label -> Nullable<Text>,
+        requested_base_branch -> Nullable<Text>,
+        scorers -> Jsonb,
+    }
+}
+
+diesel::table! {
+    eval_run_batch (id) {
+        id -> Int4,
+        eval_result_id -> Int4,
+        sequence -> Int4,
+        first_trial_index -> Int4,
+        trial_count -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    eval_score (id) {
+        id -> Int4,
+        eval_result_id -> Int4,
+        eval_hash -> Text,
+        metric -> Text,
+        value -> Float4,
+        output_hash -> Nullable<Text>,
+        trial_index -> Int4,
+    }
+}
+
+diesel::table! {
+    experiment (id) {
+        id -> Int4,
+        name -> Text,
+        prompt_name -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    experiment_arm (id) {
+        id -> Int4,
+        experiment_id -> Int4,
+        prompt_version_id -> Int4,
+        weight -> Int4,
+    }
+}
+
+diesel::table! {
+    gate_policy (id) {
+        id -> Int4,
+        prompt_name -> Text,
+        fail_on_regression -> Bool,
+        max_case_regression -> Nullable<Float4>,
+        fail_on_unknown -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -27,12 +198,62 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    online_eval_rule (id) {
+        id -> Int4,
+        name -> Text,
+        operation_name -> Nullable<Text>,
+        attributes -> Jsonb,
+        sample_rate -> Float8,
+        scorers -> Jsonb,
+        enabled -> Bool,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    prompt_label (id) {
+        id -> Int4,
+        prompt_name -> Text,
+        label -> Text,
+        prompt_version_id -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    prompt_label_history (id) {
+        id -> Int4,
+        prompt_name -> Text,
+        label -> Text,
+        previous_prompt_version_id -> Nullable<Int4>,
+        prompt_version_id -> Nullable<Int4>,
+        actor -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     prompt_version (id) {
         id -> Int4,
         name -> Text,
         version -> Text,
         created_at -> Timestamptz,
+        template -> Nullable<Jsonb>,
+        content_hash -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    scorer_plugin (id) {
+        id -> Int4,
+        name -> Text,
+        version -> Text,
+        module -> Bytea,
+        content_hash -> Text,
+        needs_expected -> Bool,
+        created_at -> Timestamptz,
     }
 }
 
@@ -44,6 +265,41 @@ diesel::table! {
         operation_name -> Text,
         parent_span_id -> Nullable<Int4>,
         external_uuid -> Nullable<Uuid>,
+        attributes -> Jsonb,
+        input -> Nullable<Jsonb>,
+        output -> Nullable<Jsonb>,
+        prompt_version_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    span_feedback (id) {
+        id -> Int4,
+        span_id -> Int4,
+        name -> Text,
+        score -> Float8,
+        comment -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    span_score (id) {
+        id -> Int4,
+        span_id -> Int4,
+        online_eval_rule_id -> Int4,
+        metric -> Text,
+        score -> Float8,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    span_score_job (id) {
+        id -> Int4,
+        span_id -> Int4,
+        online_eval_rule_id -> Int4,
+        created_at -> Timestamptz,
     }
 }
 
@@ -68,17 +324,105 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    webhook_delivery (id) {
+        id -> Int4,
+        webhook_endpoint_id -> Int4,
+        event -> Text,
+        payload -> Jsonb,
+        status -> Text,
+        attempt_count -> Int4,
+        next_attempt_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webhook_delivery_attempt (id) {
+        id -> Int4,
+        webhook_delivery_id -> Int4,
+        attempt -> Int4,
+        response_status -> Nullable<Int4>,
+        error -> Nullable<Text>,
+        duration_ms -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webhook_endpoint (id) {
+        id -> Int4,
+        url -> Text,
+        secret -> Text,
+        events -> Array<Text>,
+        eval_name -> Nullable<Text>,
+        prompt_name -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::joinable!(annotation -> annotation_item (annotation_item_id));
+diesel::joinable!(annotation_item -> annotation_queue (annotation_queue_id));
+diesel::joinable!(annotation_item -> span (span_id));
+diesel::joinable!(dataset_case -> dataset (dataset_id));
+diesel::joinable!(dataset_case -> eval_case (eval_hash));
+diesel::joinable!(dataset_case -> span (span_id));
+diesel::joinable!(dataset_version -> dataset (dataset_id));
+diesel::joinable!(dataset_version_case -> dataset_version (dataset_version_id));
+diesel::joinable!(dataset_version_case -> eval_case (eval_hash));
+diesel::joinable!(dataset_version_case -> span (span_id));
 diesel::joinable!(eval -> prompt_version (prompt_version_id));
+diesel::joinable!(eval_result -> dataset_version (dataset_version_id));
 diesel::joinable!(eval_result -> eval (eval_id));
+diesel::joinable!(eval_run_batch -> eval_result (eval_result_id));
+diesel::joinable!(eval_score -> eval_output (output_hash));
+diesel::joinable!(eval_score -> eval_result (eval_result_id));
+diesel::joinable!(experiment_arm -> experiment (experiment_id));
+diesel::joinable!(experiment_arm -> prompt_version (prompt_version_id));
 diesel::joinable!(log -> span (span_id));
+diesel::joinable!(prompt_label -> prompt_version (prompt_version_id));
+diesel::joinable!(span -> prompt_version (prompt_version_id));
+diesel::joinable!(span_feedback -> span (span_id));
+diesel::joinable!(span_score -> online_eval_rule (online_eval_rule_id));
+diesel::joinable!(span_score -> span (span_id));
+diesel::joinable!(span_score_job -> online_eval_rule (online_eval_rule_id));
+diesel::joinable!(span_score_job -> span (span_id));
 diesel::joinable!(test_version -> test_registration (test_registration_id));
+diesel::joinable!(webhook_delivery -> webhook_endpoint (webhook_endpoint_id));
+diesel::joinable!(webhook_delivery_attempt -> webhook_delivery (webhook_delivery_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    annotation,
+    annotation_item,
+    annotation_queue,
+    dataset,
+    dataset_case,
+    dataset_version,
+    dataset_version_case,
     eval,
+    eval_case,
+    eval_output,
+    eval_policy,
     eval_result,
+    eval_run_batch,
+    eval_score,
+    experiment,
+    experiment_arm,
+    gate_policy,
     log,
+    online_eval_rule,
+    prompt_label,
+    prompt_label_history,
     prompt_version,
+    scorer_plugin,
     span,
+    span_feedback,
+    span_score,
+    span_score_job,
     test_registration,
     test_version,
+    webhook_delivery,
+    webhook_delivery_attempt,
+    webhook_endpoint,
 );
@@ -7,7 +7,34 @@ use tonic::transport;
 
 use crate::ellmo::ellmo_service_server::{EllmoService, EllmoServiceServer};
 use crate::ellmo::{
-    EvalOutcome, RecordEvalRequest, RecordEvalResponse, ReportSpanRequest, TestExecutionRequest,
+    AddDatasetCasesFromSpansRequest, AddDatasetCasesFromSpansResponse, AddDatasetCasesRequest,
+    AddDatasetCasesResponse, AppendEvalScoresRequest, AppendEvalScoresResponse,
+    AssignExperimentRequest, AssignExperimentResponse, ClaimAnnotationItemRequest,
+    ClaimAnnotationItemResponse, CompareEvalRunsRequest, CompareEvalRunsResponse,
+    CreateAnnotationQueueRequest, CreateAnnotationQueueResponse, CreateDatasetRequest,
+    CreateDatasetResponse, CreateExperimentRequest, CreateExperimentResponse,
+    CreateOnlineEvalRuleRequest, CreateOnlineEvalRuleResponse, CreateWebhookRequest,
+    CreateWebhookResponse, DeletePromptLabelRequest, DeleteWebhookRequest,
+    DiffPromptVersionsRequest, DiffPromptVersionsResponse, EnqueueAnnotationItemsRequest,
+    EnqueueAnnotationItemsResponse, EvalOutcome, ExportAnnotationsRequest,
+    ExportAnnotationsResponse, FinishEvalRunRequest, GetAnnotationAgreementRequest,
+    GetAnnotationAgreementResponse, GetEvalPolicyRequest, GetEvalPolicyResponse, GetEvalRunRequest,
+    GetEvalRunResponse, GetExperimentResultsRequest, GetExperimentResultsResponse,
+    GetGatePolicyRequest, GetGatePolicyResponse, GetOnlineEvalScoresRequest,
+    GetOnlineEvalScoresResponse, GetPromptLabelHistoryRequest, GetPromptLabelHistoryResponse,
+    GetPromptRequest, GetPromptResponse, ListDatasetCasesRequest, ListDatasetCasesResponse,
+    ListEvalRunsRequest, ListEvalRunsResponse, ListEvalsRequest, ListEvalsResponse,
+    ListOnlineEvalRulesRequest, ListOnlineEvalRulesResponse, ListPromptLabelsRequest,
+    ListPromptLabelsResponse, ListScorerPluginsRequest, ListScorerPluginsResponse,
+    ListWebhookDeliveriesRequest, ListWebhookDeliveriesResponse, ListWebhooksRequest,
+    ListWebhooksResponse, RecordEvalRequest, RecordEvalResponse, RecordSpanFeedbackRequest,
+    RegisterPromptRequest, RegisterPromptResponse, RegisterScorerPluginRequest,
+    RegisterScorerPluginResponse, RemoveDatasetCasesRequest, RemoveDatasetCasesResponse,
+    RenderPromptRequest, RenderPromptResponse, ReportSpanRequest, SetEvalPolicyRequest,
+    SetEvalPolicyResponse, SetGatePolicyRequest, SetGatePolicyResponse,
+    SetOnlineEvalRuleEnabledRequest, SetOnlineEvalRuleEnabledResponse, SetPromptLabelRequest,
+    SetPromptLabelResponse, SnapshotDatasetRequest, SnapshotDatasetResponse, StartEvalRunRequest,
+    StartEvalRunResponse, SubmitAnnotationRequest, SubmitAnnotationResponse, TestExecutionRequest,
 };
 
 #[derive(Default)]
@@ -39,8 +66,345 @@ impl EllmoService for EllmoRpcDefinition {
             previous_eval_scores: [].to_vec(),
             meaningful_eval_scores: [].to_vec(),
             message: "".to_string(),
+            statistics: None,
+            base_version: None,
+            eval_run_id: 0,
+            dataset_drift: false,
+            missing_case_count: 0,
+            metric_outcomes: [].to_vec(),
+            gate: None,
+            prompt_diff: None,
         }))
     }
+    async fn start_eval_run(
+        &self,
+        _request: tonic::Request<StartEvalRunRequest>,
+    ) -> Result<tonic::Response<StartEvalRunResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(StartEvalRunResponse::default()))
+    }
+    async fn append_eval_scores(
+        &self,
+        _request: tonic::Request<AppendEvalScoresRequest>,
+    ) -> Result<tonic::Response<AppendEvalScoresResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(AppendEvalScoresResponse::default()))
+    }
+    async fn finish_eval_run(
+        &self,
+        _request: tonic::Request<FinishEvalRunRequest>,
+    ) -> Result<tonic::Response<RecordEvalResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(RecordEvalResponse::default()))
+    }
+    async fn set_eval_policy(
+        &self,
+        _request: tonic::Request<SetEvalPolicyRequest>,
+    ) -> Result<tonic::Response<SetEvalPolicyResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(SetEvalPolicyResponse::default()))
+    }
+    async fn get_eval_policy(
+        &self,
+        _request: tonic::Request<GetEvalPolicyRequest>,
+    ) -> Result<tonic::Response<GetEvalPolicyResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(GetEvalPolicyResponse::default()))
+    }
+    async fn set_gate_policy(
+        &self,
+        _request: tonic::Request<SetGatePolicyRequest>,
+    ) -> Result<tonic::Response<SetGatePolicyResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(SetGatePolicyResponse::default()))
+    }
+    async fn get_gate_policy(
+        &self,
+        _request: tonic::Request<GetGatePolicyRequest>,
+    ) -> Result<tonic::Response<GetGatePolicyResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(GetGatePolicyResponse::default()))
+    }
+    async fn list_evals(
+        &self,
+        _request: tonic::Request<ListEvalsRequest>,
+    ) -> Result<tonic::Response<ListEvalsResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(ListEvalsResponse::default()))
+    }
+    async fn list_eval_runs(
+        &self,
+        _request: tonic::Request<ListEvalRunsRequest>,
+    ) -> Result<tonic::Response<ListEvalRunsResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(ListEvalRunsResponse::default()))
+    }
+    async fn get_eval_run(
+        &self,
+        _request: tonic::Request<GetEvalRunRequest>,
+    ) -> Result<tonic::Response<GetEvalRunResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(GetEvalRunResponse::default()))
+    }
+    async fn compare_eval_runs(
+        &self,
+        _request: tonic::Request<CompareEvalRunsRequest>,
+    ) -> Result<tonic::Response<CompareEvalRunsResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(CompareEvalRunsResponse::default()))
+    }
+    async fn create_dataset(
+        &self,
+        _request: tonic::Request<CreateDatasetRequest>,
+    ) -> Result<tonic::Response<CreateDatasetResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(CreateDatasetResponse::default()))
+    }
+    async fn add_dataset_cases(
+        &self,
+        _request: tonic::Request<AddDatasetCasesRequest>,
+    ) -> Result<tonic::Response<AddDatasetCasesResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(AddDatasetCasesResponse::default()))
+    }
+    async fn add_dataset_cases_from_spans(
+        &self,
+        _request: tonic::Request<AddDatasetCasesFromSpansRequest>,
+    ) -> Result<tonic::Response<AddDatasetCasesFromSpansResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(
+            AddDatasetCasesFromSpansResponse::default(),
+        ))
+    }
+    async fn remove_dataset_cases(
+        &self,
+        _request: tonic::Request<RemoveDatasetCasesRequest>,
+    ) -> Result<tonic::Response<RemoveDatasetCasesResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(RemoveDatasetCasesResponse::default()))
+    }
+    async fn snapshot_dataset(
+        &self,
+        _request: tonic::Request<SnapshotDatasetRequest>,
+    ) -> Result<tonic::Response<SnapshotDatasetResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(SnapshotDatasetResponse::default()))
+    }
+    async fn list_dataset_cases(
+        &self,
+        _request: tonic::Request<ListDatasetCasesRequest>,
+    ) -> Result<tonic::Response<ListDatasetCasesResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(ListDatasetCasesResponse::default()))
+    }
+    async fn set_prompt_label(
+        &self,
+        _request: tonic::Request<SetPromptLabelRequest>,
+    ) -> Result<tonic::Response<SetPromptLabelResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(SetPromptLabelResponse::default()))
+    }
+    async fn list_prompt_labels(
+        &self,
+        _request: tonic::Request<ListPromptLabelsRequest>,
+    ) -> Result<tonic::Response<ListPromptLabelsResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(ListPromptLabelsResponse::default()))
+    }
+    async fn get_prompt_label_history(
+        &self,
+        _request: tonic::Request<GetPromptLabelHistoryRequest>,
+    ) -> Result<tonic::Response<GetPromptLabelHistoryResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(
+            GetPromptLabelHistoryResponse::default(),
+        ))
+    }
+    async fn create_webhook(
+        &self,
+        _request: tonic::Request<CreateWebhookRequest>,
+    ) -> Result<tonic::Response<CreateWebhookResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(CreateWebhookResponse::default()))
+    }
+    async fn delete_webhook(
+        &self,
+        _request: tonic::Request<DeleteWebhookRequest>,
+    ) -> Result<tonic::Response<()>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(()))
+    }
+    async fn list_webhooks(
+        &self,
+        _request: tonic::Request<ListWebhooksRequest>,
+    ) -> Result<tonic::Response<ListWebhooksResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(ListWebhooksResponse::default()))
+    }
+    async fn list_webhook_deliveries(
+        &self,
+        _request: tonic::Request<ListWebhookDeliveriesRequest>,
+    ) -> Result<tonic::Response<ListWebhookDeliveriesResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(
+            ListWebhookDeliveriesResponse::default(),
+        ))
+    }
+    async fn register_scorer_plugin(
+        &self,
+        _request: tonic::Request<RegisterScorerPluginRequest>,
+    ) -> Result<tonic::Response<RegisterScorerPluginResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(RegisterScorerPluginResponse::default()))
+    }
+    async fn list_scorer_plugins(
+        &self,
+        _request: tonic::Request<ListScorerPluginsRequest>,
+    ) -> Result<tonic::Response<ListScorerPluginsResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(ListScorerPluginsResponse::default()))
+    }
+    async fn register_prompt(
+        &self,
+        _request: tonic::Request<RegisterPromptRequest>,
+    ) -> Result<tonic::Response<RegisterPromptResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(RegisterPromptResponse::default()))
+    }
+    async fn get_prompt(
+        &self,
+        _request: tonic::Request<GetPromptRequest>,
+    ) -> Result<tonic::Response<GetPromptResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(GetPromptResponse::default()))
+    }
+    async fn diff_prompt_versions(
+        &self,
+        _request: tonic::Request<DiffPromptVersionsRequest>,
+    ) -> Result<tonic::Response<DiffPromptVersionsResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(DiffPromptVersionsResponse::default()))
+    }
+    async fn render_prompt(
+        &self,
+        _request: tonic::Request<RenderPromptRequest>,
+    ) -> Result<tonic::Response<RenderPromptResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(RenderPromptResponse::default()))
+    }
+    async fn record_span_feedback(
+        &self,
+        _request: tonic::Request<RecordSpanFeedbackRequest>,
+    ) -> Result<tonic::Response<()>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(()))
+    }
+    async fn create_experiment(
+        &self,
+        _request: tonic::Request<CreateExperimentRequest>,
+    ) -> Result<tonic::Response<CreateExperimentResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(CreateExperimentResponse::default()))
+    }
+    async fn assign_experiment(
+        &self,
+        _request: tonic::Request<AssignExperimentRequest>,
+    ) -> Result<tonic::Response<AssignExperimentResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(AssignExperimentResponse::default()))
+    }
+    async fn get_experiment_results(
+        &self,
+        _request: tonic::Request<GetExperimentResultsRequest>,
+    ) -> Result<tonic::Response<GetExperimentResultsResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(GetExperimentResultsResponse::default()))
+    }
+    async fn create_online_eval_rule(
+        &self,
+        _request: tonic::Request<CreateOnlineEvalRuleRequest>,
+    ) -> Result<tonic::Response<CreateOnlineEvalRuleResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(CreateOnlineEvalRuleResponse::default()))
+    }
+    async fn list_online_eval_rules(
+        &self,
+        _request: tonic::Request<ListOnlineEvalRulesRequest>,
+    ) -> Result<tonic::Response<ListOnlineEvalRulesResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(ListOnlineEvalRulesResponse::default()))
+    }
+    async fn set_online_eval_rule_enabled(
+        &self,
+        _request: tonic::Request<SetOnlineEvalRuleEnabledRequest>,
+    ) -> Result<tonic::Response<SetOnlineEvalRuleEnabledResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(
+            SetOnlineEvalRuleEnabledResponse::default(),
+        ))
+    }
+    async fn get_online_eval_scores(
+        &self,
+        _request: tonic::Request<GetOnlineEvalScoresRequest>,
+    ) -> Result<tonic::Response<GetOnlineEvalScoresResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(GetOnlineEvalScoresResponse::default()))
+    }
+    async fn create_annotation_queue(
+        &self,
+        _request: tonic::Request<CreateAnnotationQueueRequest>,
+    ) -> Result<tonic::Response<CreateAnnotationQueueResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(
+            CreateAnnotationQueueResponse::default(),
+        ))
+    }
+    async fn enqueue_annotation_items(
+        &self,
+        _request: tonic::Request<EnqueueAnnotationItemsRequest>,
+    ) -> Result<tonic::Response<EnqueueAnnotationItemsResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(
+            EnqueueAnnotationItemsResponse::default(),
+        ))
+    }
+    async fn claim_annotation_item(
+        &self,
+        _request: tonic::Request<ClaimAnnotationItemRequest>,
+    ) -> Result<tonic::Response<ClaimAnnotationItemResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(ClaimAnnotationItemResponse::default()))
+    }
+    async fn submit_annotation(
+        &self,
+        _request: tonic::Request<SubmitAnnotationRequest>,
+    ) -> Result<tonic::Response<SubmitAnnotationResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(SubmitAnnotationResponse::default()))
+    }
+    async fn get_annotation_agreement(
+        &self,
+        _request: tonic::Request<GetAnnotationAgreementRequest>,
+    ) -> Result<tonic::Response<GetAnnotationAgreementResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(
+            GetAnnotationAgreementResponse::default(),
+        ))
+    }
+    async fn export_annotations(
+        &self,
+        _request: tonic::Request<ExportAnnotationsRequest>,
+    ) -> Result<tonic::Response<ExportAnnotationsResponse>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(ExportAnnotationsResponse::default()))
+    }
+    async fn delete_prompt_label(
+        &self,
+        _request: tonic::Request<DeletePromptLabelRequest>,
+    ) -> Result<tonic::Response<()>, tonic::Status> {
+        println!("Received!");
+        Ok(tonic::Response::new(()))
+    }
 }
 
 pub struct DummyRpcServer {
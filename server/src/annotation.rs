@@ -0,0 +1,328 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Question of a rubric, answered with one of its labels or, when it has none, with a score
+/// within its bounds
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Criterion {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub min_score: f64,
+    #[serde(default)]
+    pub max_score: f64,
+}
+
+impl Criterion {
+    pub fn is_labeled(&self) -> bool {
+        !self.labels.is_empty()
+    }
+}
+
+/// Answer of a reviewer to a criterion
+#[derive(Debug, Clone, PartialEq)]
+pub enum Answer {
+    Label(String),
+    Score(f64),
+}
+
+pub fn validate_rubric(rubric: &[Criterion]) -> anyhow::Result<()> {
+    if rubric.is_empty() {
+        bail!("A rubric needs at least one criterion");
+    }
+
+    let mut names = HashSet::new();
+    for criterion in rubric {
+        if criterion.name.is_empty() {
+            bail!("Missing criterion name");
+        }
+        if !names.insert(criterion.name.as_str()) {
+            bail!("Duplicate criterion {}", criterion.name);
+        }
+
+        if criterion.is_labeled() {
+            let mut labels = HashSet::new();
+            for label in &criterion.labels {
+                if label.is_empty() {
+                    bail!("Empty label in criterion {}", criterion.name);
+                }
+                if !labels.insert(label) {
+                    bail!("Duplicate label {} in criterion {}", label, criterion.name);
+                }
+            }
+        } else if !(criterion.min_score.is_finite()
+            && criterion.max_score.is_finite()
+            && criterion.min_score < criterion.max_score)
+        {
+            bail!(
+                "Criterion {} needs labels or a minimum score below its maximum score",
+                criterion.name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that answers cover every criterion of a rubric exactly once, returning them as stored:
+/// an object of labels and scores by criterion
+pub fn validate_answers(
+    rubric: &[Criterion],
+    answers: Vec<(String, Answer)>,
+) -> anyhow::Result<Map<String, Value>> {
+    let mut stored = Map::new();
+    for (name, answer) in answers {
+        let criterion = rubric
+            .iter()
+            .find(|criterion| criterion.name == name)
+            .ok_or_else(|| anyhow!("Unknown criterion {}", name))?;
+
+        let value = match answer {
+            Answer::Label(label) if criterion.labels.contains(&label) => Value::from(label),
+            Answer::Label(label) if criterion.is_labeled() => {
+                bail!("Unknown label {} for criterion {}", label, name)
+            }
+            Answer::Score(score)
+                if !criterion.is_labeled()
+                    && score >= criterion.min_score
+                    && score <= criterion.max_score =>
+            {
+                Value::from(score)
+            }
+            Answer::Score(_) if !criterion.is_labeled() => bail!(
+                "Score for criterion {} must be between {} and {}",
+                name,
+                criterion.min_score,
+                criterion.max_score
+            ),
+            _ if criterion.is_labeled() => bail!("Criterion {} needs a label", name),
+            _ => bail!("Criterion {} needs a score", name),
+        };
+        if stored.insert(name.clone(), value).is_some() {
+            bail!("Duplicate answer to criterion {}", name);
+        }
+    }
+
+    if let Some(missing) = rubric
+        .iter()
+        .find(|criterion| !stored.contains_key(&criterion.name))
+    {
+        bail!("Missing answer to criterion {}", missing.name);
+    }
+
+    Ok(stored)
+}
+
+/// Agreement between reviewers on a criterion, over the items annotated more than once
+#[derive(Debug, Clone, PartialEq)]
+pub struct Agreement {
+    pub item_count: usize,
+    /// Share of pairs of annotations of an item choosing the same label, for labeled criteria
+    pub observed_agreement: Option<f64>,
+    /// Krippendorff's alpha, absent when every answer is the same and no disagreement is expected
+    pub alpha: Option<f64>,
+}
+
+/// Agreement on a criterion, given the stored answers of every item
+pub fn agreement(criterion: &Criterion, items: &[Vec<&Map<String, Value>>]) -> Agreement {
+    if criterion.is_labeled() {
+        let labels: Vec<Vec<&str>> = pairable(items, criterion, |value| value.as_str());
+        let (agreeing, pairs) = labels.iter().fold((0, 0), |(agreeing, pairs), values| {
+            let m = values.len();
+            let same: usize = label_counts(values).values().map(|&n| n * (n - 1)).sum();
+            (agreeing + same, pairs + m * (m - 1))
+        });
+
+        Agreement {
+            item_count: labels.len(),
+            observed_agreement: (pairs > 0).then(|| agreeing as f64 / pairs as f64),
+            alpha: nominal_alpha(&labels),
+        }
+    } else {
+        let scores = pairable(items, criterion, |value| value.as_f64());
+
+        Agreement {
+            item_count: scores.len(),
+            observed_agreement: None,
+            alpha: interval_alpha(&scores),
+        }
+    }
+}
+
+/// Answers to a criterion of the items with at least two of them
+fn pairable<'a, T>(
+    items: &[Vec<&'a Map<String, Value>>],
+    criterion: &Criterion,
+    extract: impl Fn(&'a Value) -> Option<T>,
+) -> Vec<Vec<T>> {
+    items
+        .iter()
+        .map(|answers| {
+            answers
+                .iter()
+                .filter_map(|answers| answers.get(&criterion.name).and_then(&extract))
+                .collect::<Vec<T>>()
+        })
+        .filter(|values| values.len() > 1)
+        .collect()
+}
+
+fn label_counts<'a>(values: &[&'a str]) -> HashMap<&'a str, usize> {
+    let mut counts = HashMap::new();
+    for value in values {
+        *counts.entry(*value).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Krippendorff's alpha of labels, where two different labels disagree by 1
+fn nominal_alpha(items: &[Vec<&str>]) -> Option<f64> {
+    // Sum over ordered pairs of different values of the disagreement between them
+    let disagreement = |values: &[&str]| {
+        let m = values.len() as f64;
+        m * m
+            - label_counts(values)
+                .values()
+                .map(|&n| (n * n) as f64)
+                .sum::<f64>()
+    };
+    alpha(items, disagreement)
+}
+
+/// Krippendorff's alpha of scores, where two scores disagree by their squared difference
+fn interval_alpha(items: &[Vec<f64>]) -> Option<f64> {
+    let disagreement = |values: &[f64]| {
+        let m = values.len() as f64;
+        let sum: f64 = values.iter().sum();
+        let sum_of_squares: f64 = values.iter().map(|value| value * value).sum();
+        2.0 * m * sum_of_squares - 2.0 * sum * sum
+    };
+    alpha(items, disagreement)
+}
+
+/// 1 minus the observed disagreement within items over the disagreement expected between any
+/// two values
+fn alpha<T: Copy>(items: &[Vec<T>], disagreement: impl Fn(&[T]) -> f64) -> Option<f64> {
+    let all: Vec<T> = items.iter().flatten().copied().collect();
+    let n = all.len() as f64;
+    if n < 2.0 {
+        return None;
+    }
+
+    let observed = items
+        .iter()
+        .map(|values| disagreement(values) / (values.len() - 1) as f64)
+        .sum::<f64>()
+        / n;
+    let expected = disagreement(&all) / (n * (n - 1.0));
+    if expected <= f64::EPSILON {
+        return None;
+    }
+
+    Some(1.0 - observed / expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn labeled(name: &str, labels: &[&str]) -> Criterion {
+        Criterion {
+            name: name.to_string(),
+            description: None,
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            min_score: 0.0,
+            max_score: 0.0,
+        }
+    }
+
+    fn scored(name: &str, min_score: f64, max_score: f64) -> Criterion {
+        Criterion {
+            name: name.to_string(),
+            description: None,
+            labels: Vec::new(),
+            min_score,
+            max_score,
+        }
+    }
+
+    #[test]
+    fn test_validate_answers_against_rubric() {
+        let rubric = vec![
+            labeled("verdict", &["good", "bad"]),
+            scored("helpfulness", 1.0, 5.0),
+        ];
+        assert!(validate_rubric(&rubric).is_ok());
+        assert!(validate_rubric(&[scored("x", 1.0, 1.0)]).is_err());
+        assert!(validate_rubric(&[labeled("x", &["a", "a"])]).is_err());
+
+        let answer = |verdict: Answer, helpfulness: Answer| {
+            validate_answers(
+                &rubric,
+                vec![
+                    ("verdict".to_string(), verdict),
+                    ("helpfulness".to_string(), helpfulness),
+                ],
+            )
+        };
+        let stored = answer(Answer::Label("bad".into()), Answer::Score(2.0)).unwrap();
+        assert_eq!(
+            Value::Object(stored),
+            json!({"verdict": "bad", "helpfulness": 2.0})
+        );
+
+        assert!(answer(Answer::Label("ugly".into()), Answer::Score(2.0)).is_err());
+        assert!(answer(Answer::Label("bad".into()), Answer::Score(6.0)).is_err());
+        assert!(answer(Answer::Score(1.0), Answer::Score(2.0)).is_err());
+        assert!(answer(Answer::Label("bad".into()), Answer::Label("good".into())).is_err());
+        assert!(validate_answers(
+            &rubric,
+            vec![("verdict".to_string(), Answer::Label("bad".into()))]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_agreement_computes_krippendorff_alpha() {
+        let verdict = labeled("verdict", &["good", "bad"]);
+        let answers: Vec<Map<String, Value>> = ["good", "good", "bad", "bad", "good", "bad", "bad"]
+            .iter()
+            .map(|label| json!({ "verdict": label }).as_object().unwrap().clone())
+            .collect();
+        // The last item only has one annotation, so it is not pairable
+        let items = vec![
+            vec![&answers[0], &answers[1]],
+            vec![&answers[2], &answers[3]],
+            vec![&answers[4], &answers[5]],
+            vec![&answers[6]],
+        ];
+        let result = agreement(&verdict, &items);
+        assert_eq!(result.item_count, 3);
+        assert!((result.observed_agreement.unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert!((result.alpha.unwrap() - 4.0 / 9.0).abs() < 1e-9);
+
+        let helpfulness = scored("helpfulness", 1.0, 5.0);
+        let answers: Vec<Map<String, Value>> = [1, 3, 3, 1]
+            .iter()
+            .map(|score| json!({ "helpfulness": score }).as_object().unwrap().clone())
+            .collect();
+        let items = vec![
+            vec![&answers[0], &answers[1]],
+            vec![&answers[2], &answers[3]],
+        ];
+        let result = agreement(&helpfulness, &items);
+        assert_eq!(result.observed_agreement, None);
+        assert!((result.alpha.unwrap() + 0.5).abs() < 1e-9);
+
+        // Without any disagreement to expect, alpha is undefined
+        let items = vec![vec![&answers[0], &answers[3]]];
+        assert_eq!(agreement(&helpfulness, &items).alpha, None);
+    }
+}
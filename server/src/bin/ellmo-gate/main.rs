@@ -0,0 +1,302 @@
+//! Records an eval run and fails when it does not pass the gate policy of its prompt.
+//!
+//! ```text
+//! ellmo-gate --eval <name> --prompt <name> --version <version> --scores <file.json>
+//!            [--base-version <version> | --base-label <label>] [--base-branch <branch>]
+//!            [--dataset <name> --dataset-version <version>]
+//!            [--git-sha <sha>] [--branch <branch>] [--ci-url <url>] [--model <model>]
+//!            [--temperature <value>] [--attribute <key>=<value>]...
+//!            [--format markdown|junit] [--output <file>] [--server <url>]
+//! ```
+//!
+//! The scores file holds a JSON array of
+//! `{"evalHash", "score", "metrics": {name: value}, "input", "expectedOutput", "actualOutput"}`.
+//! Exits with 0 when the gate passes, 1 when it fails and 2 on errors, including a run recorded
+//! without a gate verdict.
+
+mod report;
+
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use serde::Deserialize;
+
+use ellmo_proto::ellmo::ellmo_service_client::EllmoServiceClient;
+use ellmo_proto::ellmo::{
+    DatasetReference, Eval, EvalScore, Metric, RecordEvalRequest, RunMetadata, VersionedPrompt,
+};
+
+use report::{Format, Report};
+
+const DEFAULT_SERVER: &str = "http://127.0.0.1:50051";
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    server: Option<String>,
+    eval: String,
+    prompt: String,
+    version: String,
+    scores: String,
+    base_version: Option<String>,
+    base_label: Option<String>,
+    base_branch: Option<String>,
+    dataset: Option<String>,
+    dataset_version: Option<u32>,
+    metadata: RunMetadata,
+    format: Format,
+    output: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ScoreEntry {
+    eval_hash: String,
+    #[serde(default)]
+    score: f32,
+    #[serde(default)]
+    metrics: BTreeMap<String, f32>,
+    input: Option<serde_json::Value>,
+    expected_output: Option<serde_json::Value>,
+    actual_output: Option<serde_json::Value>,
+}
+
+impl From<ScoreEntry> for EvalScore {
+    fn from(entry: ScoreEntry) -> Self {
+        EvalScore {
+            eval_hash: entry.eval_hash,
+            score: entry.score,
+            input: entry.input.map(|input| input.to_string()),
+            expected_output: entry.expected_output.map(|output| output.to_string()),
+            actual_output: entry.actual_output.map(|output| output.to_string()),
+            metrics: entry
+                .metrics
+                .into_iter()
+                .map(|(name, value)| Metric { name, value })
+                .collect(),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(error) => {
+            eprintln!("ellmo-gate: {}", error);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Record the run and write the report, returning whether the gate passed
+async fn run() -> Result<bool, String> {
+    let args = parse_args(std::env::args().skip(1))?;
+
+    let contents = std::fs::read_to_string(&args.scores)
+        .map_err(|e| format!("Failed to read {}: {}", args.scores, e))?;
+    let entries: Vec<ScoreEntry> = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse {}: {}", args.scores, e))?;
+
+    let dataset = match (&args.dataset, args.dataset_version) {
+        (Some(name), Some(version)) => Some(DatasetReference {
+            name: name.clone(),
+            version,
+        }),
+        (None, None) => None,
+        _ => return Err("--dataset and --dataset-version must be given together".to_string()),
+    };
+
+    let server = args
+        .server
+        .clone()
+        .or_else(|| std::env::var("ELLMO_SERVER").ok())
+        .unwrap_or_else(|| DEFAULT_SERVER.to_string());
+    let mut client = EllmoServiceClient::connect(server.clone())
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", server, e))?;
+
+    let response = client
+        .record_eval(RecordEvalRequest {
+            eval: Some(Eval {
+                name: args.eval.clone(),
+            }),
+            prompt: Some(VersionedPrompt {
+                name: args.prompt.clone(),
+                version: args.version.clone(),
+            }),
+            base_version: args.base_version.clone(),
+            eval_scores: entries.into_iter().map(EvalScore::from).collect(),
+            dataset,
+            base_label: args.base_label.clone(),
+            metadata: Some(args.metadata.clone()),
+            base_branch: args.base_branch.clone(),
+            scorers: Vec::new(),
+        })
+        .await
+        .map_err(|status| format!("Failed to record eval: {}", status.message()))?
+        .into_inner();
+
+    let report = Report {
+        eval_name: &args.eval,
+        prompt_name: &args.prompt,
+        version: &args.version,
+        response: &response,
+    };
+    let rendered = match args.format {
+        Format::Markdown => report::markdown(&report),
+        Format::Junit => report::junit(&report),
+    };
+    match &args.output {
+        Some(path) => std::fs::write(path, rendered)
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?,
+        None => print!("{}", rendered),
+    }
+
+    // Checked once the report is written, so that a missing verdict still leaves one to inspect
+    report.passed()
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+
+    while let Some(flag) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", flag))
+        };
+        match flag.as_str() {
+            "--server" => parsed.server = Some(value()?),
+            "--eval" => parsed.eval = value()?,
+            "--prompt" => parsed.prompt = value()?,
+            "--version" => parsed.version = value()?,
+            "--scores" => parsed.scores = value()?,
+            "--base-version" => parsed.base_version = Some(value()?),
+            "--base-label" => parsed.base_label = Some(value()?),
+            "--base-branch" => parsed.base_branch = Some(value()?),
+            "--git-sha" => parsed.metadata.git_sha = Some(value()?),
+            "--branch" => parsed.metadata.branch = Some(value()?),
+            "--ci-url" => parsed.metadata.ci_run_url = Some(value()?),
+            "--model" => parsed.metadata.model = Some(value()?),
+            "--temperature" => {
+                let temperature = value()?;
+                parsed.metadata.temperature = Some(
+                    temperature
+                        .parse()
+                        .map_err(|_| format!("Invalid temperature {}", temperature))?,
+                );
+            }
+            "--attribute" => {
+                let attribute = value()?;
+                let (key, value) = attribute.split_once('=').ok_or_else(|| {
+                    format!("Attributes must be given as key=value: {}", attribute)
+                })?;
+                parsed
+                    .metadata
+                    .attributes
+                    .insert(key.to_string(), value.to_string());
+            }
+            "--dataset" => parsed.dataset = Some(value()?),
+            "--dataset-version" => {
+                let version = value()?;
+                parsed.dataset_version = Some(
+                    version
+                        .parse()
+                        .map_err(|_| format!("Invalid dataset version {}", version))?,
+                );
+            }
+            "--format" => {
+                parsed.format = match value()?.as_str() {
+                    "markdown" => Format::Markdown,
+                    "junit" => Format::Junit,
+                    other => return Err(format!("Unknown format {}", other)),
+                }
+            }
+            "--output" => parsed.output = Some(value()?),
+            other => return Err(format!("Unknown argument {}", other)),
+        }
+    }
+
+    for (flag, value) in [
+        ("--eval", &parsed.eval),
+        ("--prompt", &parsed.prompt),
+        ("--version", &parsed.version),
+        ("--scores", &parsed.scores),
+    ] {
+        if value.is_empty() {
+            return Err(format!("Missing required argument {}", flag));
+        }
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> impl Iterator<Item = String> {
+        values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = parse_args(args(&[
+            "--eval",
+            "accuracy",
+            "--prompt",
+            "summarizer",
+            "--version",
+            "1.2.0",
+            "--scores",
+            "scores.json",
+            "--base-label",
+            "production",
+            "--format",
+            "junit",
+            "--branch",
+            "feature/x",
+            "--attribute",
+            "top_p=0.9",
+        ]))
+        .unwrap();
+
+        assert_eq!(parsed.eval, "accuracy");
+        assert_eq!(parsed.base_label.as_deref(), Some("production"));
+        assert_eq!(parsed.metadata.branch.as_deref(), Some("feature/x"));
+        assert_eq!(parsed.metadata.attributes["top_p"], "0.9");
+        assert_eq!(parsed.format, Format::Junit);
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert_eq!(
+            parse_args(args(&["--eval", "accuracy"])),
+            Err("Missing required argument --prompt".to_string())
+        );
+        assert_eq!(
+            parse_args(args(&["--format"])),
+            Err("Missing value for --format".to_string())
+        );
+        assert!(parse_args(args(&["--verbose"])).is_err());
+        assert!(parse_args(args(&["--attribute", "top_p"])).is_err());
+    }
+
+    #[test]
+    fn test_score_entry_conversion() {
+        let entry: ScoreEntry = serde_json::from_str(
+            r#"{"evalHash": "a", "metrics": {"latency": 120}, "input": {"q": "hi"}}"#,
+        )
+        .unwrap();
+
+        let score = EvalScore::from(entry);
+
+        assert_eq!(score.input.as_deref(), Some(r#"{"q":"hi"}"#));
+        assert_eq!(score.metrics[0].name, "latency");
+        assert_eq!(score.metrics[0].value, 120.0);
+    }
+}
@@ -0,0 +1,338 @@
+use std::fmt::Write;
+
+use ellmo_proto::ellmo::{EvalOutcome, MetricOutcome, RecordEvalResponse};
+
+/// Maximum number of changed cases listed per metric in the Markdown report
+const MAX_CASE_ROWS: usize = 20;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Format {
+    #[default]
+    Markdown,
+    Junit,
+}
+
+/// Outcome of a recorded run, as rendered in the report
+pub struct Report<'a> {
+    pub eval_name: &'a str,
+    pub prompt_name: &'a str,
+    pub version: &'a str,
+    pub response: &'a RecordEvalResponse,
+}
+
+impl Report<'_> {
+    /// Whether the run passed the gate. A missing verdict is an error, so that the gate never
+    /// passes by default.
+    pub fn passed(&self) -> Result<bool, String> {
+        self.response
+            .gate
+            .as_ref()
+            .map(|gate| gate.passed)
+            .ok_or_else(|| "The server returned no gate verdict".to_string())
+    }
+
+    fn reasons(&self) -> &[String] {
+        self.response
+            .gate
+            .as_ref()
+            .map(|gate| gate.reasons.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Render the report as Markdown, e.g. for a pull request comment
+pub fn markdown(report: &Report) -> String {
+    let response = report.response;
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "## Eval `{}` of `{}@{}`\n",
+        report.eval_name, report.prompt_name, report.version
+    );
+    let _ = writeln!(
+        out,
+        "**Gate: {}**",
+        match report.passed() {
+            Ok(true) => "passed",
+            Ok(false) => "failed",
+            Err(_) => "missing",
+        }
+    );
+    for reason in report.reasons() {
+        let _ = writeln!(out, "- {}", reason);
+    }
+    out.push('\n');
+
+    match &response.base_version {
+        Some(base_version) => {
+            let _ = writeln!(
+                out,
+                "Compared against `{}`. Outcome: **{}** (run #{})\n",
+                base_version,
+                response.outcome().as_str_name(),
+                response.eval_run_id
+            );
+        }
+        None => {
+            let _ = writeln!(
+                out,
+                "No baseline to compare against (run #{})\n",
+                response.eval_run_id
+            );
+        }
+    }
+    if let Some(diff) = response.prompt_diff.as_ref().filter(|diff| !diff.identical) {
+        let _ = writeln!(out, "Prompt changes: {}\n", diff.description);
+    }
+    if !response.message.is_empty() {
+        let _ = writeln!(out, "> {}\n", response.message);
+    }
+    if response.metric_outcomes.is_empty() {
+        return out;
+    }
+
+    out.push_str("| Metric | Outcome | Mean delta | 95% CI | p-value |\n");
+    out.push_str("|---|---|---|---|---|\n");
+    for metric in &response.metric_outcomes {
+        let _ = match &metric.statistics {
+            Some(stats) => writeln!(
+                out,
+                "| {} | {} | {:+.4} | [{:+.4}, {:+.4}] | {:.4} |",
+                metric.metric,
+                metric.outcome().as_str_name(),
+                stats.mean_delta,
+                stats.ci_lower,
+                stats.ci_upper,
+                stats.wilcoxon_p_value
+            ),
+            None => writeln!(
+                out,
+                "| {} | {} | - | - | - |",
+                metric.metric,
+                metric.outcome().as_str_name()
+            ),
+        };
+    }
+
+    for metric in &response.metric_outcomes {
+        if metric.meaningful_eval_scores.is_empty() {
+            continue;
+        }
+
+        let _ = writeln!(out, "\n### Changed cases of `{}`\n", metric.metric);
+        out.push_str("| Case | Previous | Current | Outcome |\n");
+        out.push_str("|---|---|---|---|\n");
+        for score in metric.meaningful_eval_scores.iter().take(MAX_CASE_ROWS) {
+            let _ = writeln!(
+                out,
+                "| `{}` | {:.4} | {:.4} | {} |",
+                score.eval_hash,
+                score.previous_score,
+                score.current_score,
+                score.outcome().as_str_name()
+            );
+        }
+        let hidden = metric
+            .meaningful_eval_scores
+            .len()
+            .saturating_sub(MAX_CASE_ROWS);
+        if hidden > 0 {
+            let _ = writeln!(out, "\n_{} more cases not shown_", hidden);
+        }
+    }
+
+    out
+}
+
+/// Render the report as JUnit XML, with a test case for the gate and one per metric
+pub fn junit(report: &Report) -> String {
+    let classname = escape(&format!("{}.{}", report.eval_name, report.prompt_name));
+    let metrics = &report.response.metric_outcomes;
+
+    let mut cases = String::new();
+    let mut failures = 0;
+
+    let _ = write!(
+        cases,
+        "    <testcase classname=\"{}\" name=\"gate\"",
+        classname
+    );
+    match report.passed() {
+        Ok(true) => cases.push_str("/>\n"),
+        Ok(false) => {
+            failures += 1;
+            let _ = writeln!(
+                cases,
+                ">\n      <failure message=\"Gate failed\">{}</failure>\n    </testcase>",
+                escape(&report.reasons().join("\n"))
+            );
+        }
+        Err(error) => {
+            failures += 1;
+            let _ = writeln!(
+                cases,
+                ">\n      <error message=\"{}\"/>\n    </testcase>",
+                escape(&error)
+            );
+        }
+    }
+
+    for metric in metrics {
+        let _ = write!(
+            cases,
+            "    <testcase classname=\"{}\" name=\"metric:{}\"",
+            classname,
+            escape(&metric.metric)
+        );
+        if metric.outcome() == EvalOutcome::Regression {
+            failures += 1;
+            let _ = writeln!(
+                cases,
+                ">\n      <failure message=\"Regression\">{}</failure>\n    </testcase>",
+                escape(&regressed_cases(metric))
+            );
+        } else {
+            cases.push_str("/>\n");
+        }
+    }
+
+    let tests = metrics.len() + 1;
+    let suite = escape(report.eval_name);
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuites name=\"ellmo\" tests=\"{tests}\" failures=\"{failures}\">\n  \
+         <testsuite name=\"{suite}\" tests=\"{tests}\" failures=\"{failures}\">\n\
+         {cases}  </testsuite>\n\
+         </testsuites>\n"
+    )
+}
+
+fn regressed_cases(metric: &MetricOutcome) -> String {
+    metric
+        .meaningful_eval_scores
+        .iter()
+        .filter(|score| score.outcome() == EvalOutcome::Regression)
+        .map(|score| {
+            format!(
+                "{}: {:.4} -> {:.4}",
+                score.eval_hash, score.previous_score, score.current_score
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ellmo_proto::ellmo::{GateVerdict, MeaningfulEvalScore, PromptDiffSummary};
+
+    fn response(passed: bool) -> RecordEvalResponse {
+        RecordEvalResponse {
+            outcome: EvalOutcome::Regression.into(),
+            base_version: Some("1.0.0".to_string()),
+            eval_run_id: 7,
+            metric_outcomes: vec![
+                MetricOutcome {
+                    metric: "score".to_string(),
+                    outcome: EvalOutcome::Regression.into(),
+                    meaningful_eval_scores: vec![MeaningfulEvalScore {
+                        eval_hash: "a<b".to_string(),
+                        previous_score: 1.0,
+                        current_score: 0.5,
+                        outcome: EvalOutcome::Regression.into(),
+                        ..Default::default()
+                    }],
+                    statistics: None,
+                },
+                MetricOutcome {
+                    metric: "latency".to_string(),
+                    outcome: EvalOutcome::NoChange.into(),
+                    ..Default::default()
+                },
+            ],
+            gate: Some(GateVerdict {
+                passed,
+                reasons: if passed {
+                    vec![]
+                } else {
+                    vec!["Overall outcome is a regression".to_string()]
+                },
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn report(response: &RecordEvalResponse) -> Report<'_> {
+        Report {
+            eval_name: "accuracy",
+            prompt_name: "summarizer",
+            version: "1.1.0",
+            response,
+        }
+    }
+
+    #[test]
+    fn test_markdown_lists_verdict_and_changes() {
+        let response = response(false);
+
+        let rendered = markdown(&report(&response));
+
+        assert!(rendered.contains("**Gate: failed**\n- Overall outcome is a regression"));
+        assert!(rendered.contains("| score | REGRESSION | - | - | - |"));
+        assert!(rendered.contains("| `a<b` | 1.0000 | 0.5000 | REGRESSION |"));
+        assert!(!rendered.contains("Changed cases of `latency`"));
+    }
+
+    #[test]
+    fn test_markdown_describes_prompt_changes() {
+        let mut response = response(false);
+        response.prompt_diff = Some(PromptDiffSummary {
+            description: "+1 -1 lines in message 1; temperature: 0.2 -> 0.7".to_string(),
+            ..Default::default()
+        });
+
+        let rendered = markdown(&report(&response));
+
+        assert!(rendered
+            .contains("Prompt changes: +1 -1 lines in message 1; temperature: 0.2 -> 0.7\n"));
+    }
+
+    #[test]
+    fn test_junit_counts_failures_and_escapes() {
+        let response = response(false);
+
+        let rendered = junit(&report(&response));
+
+        assert!(rendered.contains("<testsuite name=\"accuracy\" tests=\"3\" failures=\"2\">"));
+        assert!(rendered.contains("a&lt;b: 1.0000 -&gt; 0.5000"));
+        assert!(rendered.contains("name=\"metric:latency\"/>"));
+    }
+
+    #[test]
+    fn test_passed_follows_verdict() {
+        assert_eq!(report(&response(true)).passed(), Ok(true));
+        assert_eq!(report(&response(false)).passed(), Ok(false));
+    }
+
+    #[test]
+    fn test_missing_verdict_is_an_error() {
+        let response = RecordEvalResponse::default();
+
+        assert!(report(&response).passed().is_err());
+        assert!(markdown(&report(&response)).contains("**Gate: missing**"));
+        let rendered = junit(&report(&response));
+        assert!(rendered.contains("tests=\"1\" failures=\"1\""));
+        assert!(rendered.contains("<error message=\"The server returned no gate verdict\"/>"));
+    }
+}
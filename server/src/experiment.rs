@@ -0,0 +1,193 @@
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::stats::{self, Summary};
+
+/// Index of the arm a subject is assigned to, with a probability proportional to the weight of
+/// the arm. Subjects are hashed along with the experiment name, so that a subject always gets the
+/// same arm within an experiment while assignments are independent between experiments.
+pub fn assign(experiment: &str, subject_key: &str, weights: &[u32]) -> usize {
+    let digest = Sha256::digest(format!("{}\n{}", experiment, subject_key));
+    let bucket = u64::from_be_bytes(digest[..8].try_into().unwrap());
+
+    let total: u64 = weights.iter().map(|&weight| u64::from(weight)).sum();
+    let mut point = bucket % total.max(1);
+    for (i, &weight) in weights.iter().enumerate() {
+        if point < u64::from(weight) {
+            return i;
+        }
+        point -= u64::from(weight);
+    }
+    weights.len().saturating_sub(1)
+}
+
+/// Exposure of a subject to an arm, with the outcome values recorded against it
+#[derive(Debug, Clone)]
+pub struct Exposure {
+    pub arm: usize,
+    pub values: Vec<f64>,
+}
+
+/// Exposures of each subject, pooling the values recorded against every exposure of a subject, so
+/// that a subject assigned several times is a single observation. Subjects keep the arm of their
+/// first exposure.
+pub fn by_subject(
+    exposures: impl IntoIterator<Item = (String, usize, Option<f64>)>,
+) -> Vec<Exposure> {
+    let mut subjects: BTreeMap<String, Exposure> = BTreeMap::new();
+    for (subject, arm, value) in exposures {
+        subjects
+            .entry(subject)
+            .or_insert(Exposure {
+                arm,
+                values: Vec::new(),
+            })
+            .values
+            .extend(value);
+    }
+    subjects.into_values().collect()
+}
+
+/// Outcome of an arm, compared to the first arm of the experiment
+#[derive(Debug, Clone)]
+pub struct ArmOutcome {
+    pub exposures: usize,
+    /// Outcomes of the exposures with at least one value, each the mean of its values
+    pub summary: Summary,
+    pub difference: Option<f64>,
+    pub relative_lift: Option<f64>,
+    pub p_value: Option<f64>,
+    pub significant: bool,
+}
+
+/// Outcome of every arm. Exposures without values count as exposures, but not as observations.
+pub fn analyze(arm_count: usize, exposures: &[Exposure]) -> Vec<ArmOutcome> {
+    let mut counts = vec![0; arm_count];
+    let mut outcomes = vec![Vec::new(); arm_count];
+    for exposure in exposures {
+        let Some(count) = counts.get_mut(exposure.arm) else {
+            continue;
+        };
+        *count += 1;
+        if !exposure.values.is_empty() {
+            outcomes[exposure.arm].push(stats::mean(&exposure.values));
+        }
+    }
+
+    let summaries: Vec<Summary> = outcomes
+        .iter()
+        .map(|values| Summary::from_values(values))
+        .collect();
+    let Some(control) = summaries.first().copied() else {
+        return Vec::new();
+    };
+
+    summaries
+        .into_iter()
+        .zip(counts)
+        .enumerate()
+        .map(|(i, (summary, exposures))| {
+            let compared = i > 0 && control.count > 0 && summary.count > 0;
+            let difference = compared.then_some(summary.mean - control.mean);
+            let p_value = if compared {
+                stats::welch_t_test(&control, &summary)
+            } else {
+                None
+            };
+
+            ArmOutcome {
+                exposures,
+                summary,
+                difference,
+                relative_lift: difference
+                    .filter(|_| control.mean != 0.0)
+                    .map(|difference| difference / control.mean.abs()),
+                p_value,
+                significant: p_value.is_some_and(|p| p < stats::SIGNIFICANCE_LEVEL),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assign_is_deterministic_and_follows_weights() {
+        let weights = [80, 20];
+        let assignments: Vec<usize> = (0..10_000)
+            .map(|i| assign("checkout", &format!("user-{}", i), &weights))
+            .collect();
+
+        for (i, &arm) in assignments.iter().enumerate().take(100) {
+            assert_eq!(assign("checkout", &format!("user-{}", i), &weights), arm);
+        }
+        let share = assignments.iter().filter(|&&arm| arm == 1).count() as f64 / 10_000.0;
+        assert!((share - 0.2).abs() < 0.02, "share of arm 1 was {}", share);
+
+        // Another experiment splits the same subjects independently
+        let other: Vec<usize> = (0..10_000)
+            .map(|i| assign("onboarding", &format!("user-{}", i), &[50, 50]))
+            .collect();
+        let both = assignments
+            .iter()
+            .zip(&other)
+            .filter(|(&a, &b)| a == 1 && b == 1)
+            .count() as f64
+            / 10_000.0;
+        assert!((both - 0.1).abs() < 0.02, "share in both was {}", both);
+
+        assert_eq!(assign("single", "user", &[1]), 0);
+    }
+
+    #[test]
+    fn test_analyze_compares_arms_to_the_first() {
+        let mut exposures = Vec::new();
+        for i in 0..40 {
+            let noise = (i % 5) as f64 * 0.1;
+            exposures.push(Exposure {
+                arm: 0,
+                values: vec![0.3 + noise],
+            });
+            exposures.push(Exposure {
+                arm: 1,
+                values: vec![0.6 + noise, 0.6 + noise],
+            });
+            exposures.push(Exposure {
+                arm: 2,
+                values: vec![0.3 + noise],
+            });
+        }
+        exposures.push(Exposure {
+            arm: 1,
+            values: vec![],
+        });
+
+        let outcomes = analyze(3, &exposures);
+        assert_eq!(outcomes[0].difference, None);
+        assert!(!outcomes[0].significant);
+
+        assert_eq!(outcomes[1].exposures, 41);
+        assert_eq!(outcomes[1].summary.count, 40);
+        assert!((outcomes[1].difference.unwrap() - 0.3).abs() < 1e-9);
+        assert!((outcomes[1].relative_lift.unwrap() - 0.6).abs() < 1e-9);
+        assert!(outcomes[1].significant);
+
+        assert!((outcomes[2].difference.unwrap()).abs() < 1e-9);
+        assert!(!outcomes[2].significant);
+    }
+
+    #[test]
+    fn test_repeated_assignments_count_once() {
+        let subject = |value| ("user-1".to_string(), 1, value);
+        let exposures = by_subject([subject(Some(1.0)), subject(None), subject(Some(0.0))]);
+        assert_eq!(exposures.len(), 1);
+        assert_eq!(exposures[0].values, vec![1.0, 0.0]);
+
+        let outcomes = analyze(2, &exposures);
+        assert_eq!(outcomes[1].exposures, 1);
+        assert_eq!(outcomes[1].summary.count, 1);
+        assert!((outcomes[1].summary.mean - 0.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,94 @@
+use axum::extract::{Path, Query};
+use axum::response::IntoResponse;
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use ellmo_proto::ellmo::{
+    CompareEvalRunsResponse, MeaningfulEvalScore, MetricOutcome, StatisticalSummary,
+};
+
+use super::error_response;
+use crate::rpc::eval::compare::{self, RunBase};
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareQuery {
+    pub base_run_id: Option<i32>,
+    pub base_version: Option<String>,
+    pub base_label: Option<String>,
+}
+
+pub async fn compare_get(
+    Path(id): Path<i32>,
+    Query(query): Query<CompareQuery>,
+) -> impl IntoResponse {
+    let base = match RunBase::from_parts(query.base_run_id, query.base_version, query.base_label) {
+        Ok(base) => base,
+        Err(status) => return error_response(&status),
+    };
+
+    let mut conn = ellmo_db::establish_connection();
+    match compare::compare_runs(&mut conn, id, base) {
+        Ok(comparison) => (StatusCode::OK, Json(comparison_json(&comparison))),
+        Err(status) => error_response(&status),
+    }
+}
+
+fn comparison_json(comparison: &CompareEvalRunsResponse) -> Value {
+    json!({
+        "outcome": comparison.outcome().as_str_name(),
+        "baseRunId": comparison.base_run_id,
+        "currentRunId": comparison.current_run_id,
+        "datasetDrift": comparison.dataset_drift,
+        "metrics": comparison
+            .metric_outcomes
+            .iter()
+            .map(metric_json)
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn metric_json(metric: &MetricOutcome) -> Value {
+    json!({
+        "metric": metric.metric,
+        "outcome": metric.outcome().as_str_name(),
+        "statistics": metric.statistics.as_ref().map(statistics_json),
+        "meaningfulScores": metric
+            .meaningful_eval_scores
+            .iter()
+            .map(score_json)
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn statistics_json(statistics: &StatisticalSummary) -> Value {
+    json!({
+        "sampleSize": statistics.sample_size,
+        "meanDelta": statistics.mean_delta,
+        "ciLower": statistics.ci_lower,
+        "ciUpper": statistics.ci_upper,
+        "confidenceLevel": statistics.confidence_level,
+        "wilcoxonPValue": statistics.wilcoxon_p_value,
+        "bootstrapPValue": statistics.bootstrap_p_value,
+        "significant": statistics.significant,
+    })
+}
+
+fn score_json(score: &MeaningfulEvalScore) -> Value {
+    json!({
+        "evalHash": score.eval_hash,
+        "outcome": score.outcome().as_str_name(),
+        "previousScore": score.previous_score,
+        "currentScore": score.current_score,
+        "previousTrials": score.previous_trials,
+        "currentTrials": score.current_trials,
+        "previousStdDev": score.previous_std_dev,
+        "currentStdDev": score.current_std_dev,
+        "pValue": score.p_value,
+        "input": score.input,
+        "expectedOutput": score.expected_output,
+        "previousOutput": score.previous_output,
+        "currentOutput": score.current_output,
+    })
+}
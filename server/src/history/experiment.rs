@@ -0,0 +1,61 @@
+use axum::extract::{Path, Query};
+use axum::response::IntoResponse;
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use ellmo_proto::ellmo::GetExperimentResultsResponse;
+
+use super::error_response;
+use crate::rpc::experiment;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ResultsQuery {
+    #[serde(default)]
+    pub metric: String,
+}
+
+pub async fn results_get(
+    Path(name): Path<String>,
+    Query(query): Query<ResultsQuery>,
+) -> impl IntoResponse {
+    let mut conn = ellmo_db::establish_connection();
+
+    match experiment::experiment_results(&mut conn, &name, &query.metric) {
+        Ok(results) => (StatusCode::OK, Json(results_json(&results))),
+        Err(status) => error_response(&status),
+    }
+}
+
+fn results_json(results: &GetExperimentResultsResponse) -> Value {
+    json!({
+        "experiment": results.experiment.as_ref().map(|experiment| json!({
+            "name": experiment.name,
+            "promptName": experiment.prompt_name,
+            "arms": experiment
+                .arms
+                .iter()
+                .map(|arm| json!({ "version": arm.version, "weight": arm.weight }))
+                .collect::<Vec<_>>(),
+        })),
+        "metric": results.metric,
+        "arms": results
+            .arms
+            .iter()
+            .map(|arm| {
+                json!({
+                    "version": arm.version,
+                    "exposures": arm.exposures,
+                    "observations": arm.observations,
+                    "mean": arm.mean,
+                    "stdDev": arm.std_dev,
+                    "difference": arm.difference,
+                    "relativeLift": arm.relative_lift,
+                    "pValue": arm.p_value,
+                    "significant": arm.significant,
+                })
+            })
+            .collect::<Vec<_>>(),
+        "readout": results.readout,
+    })
+}
@@ -0,0 +1,434 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::extract::{Path, Query};
+use axum::response::IntoResponse;
+use axum::{http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Nullable, Text, Timestamptz};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use ellmo_db::{
+    models::{
+        eval_result::{EvalResult, STATUS_FINISHED},
+        eval_score::DEFAULT_METRIC,
+        prompt_version::PromptVersion,
+        repository::DieselRepository,
+    },
+    schema::{eval, eval_result, eval_score, prompt_version},
+};
+
+use crate::rpc::eval::cases;
+use crate::version;
+
+mod compare;
+mod experiment;
+mod online_eval;
+mod prompt;
+
+pub use compare::compare_get;
+pub use experiment::results_get;
+pub use online_eval::online_scores_get;
+pub use prompt::{diff_get, render_post};
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalSummary {
+    pub name: String,
+    pub prompt_name: String,
+    pub run_count: i64,
+    pub last_run_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSummary {
+    pub id: i32,
+    pub eval_name: String,
+    pub prompt_name: String,
+    pub prompt_version: String,
+    pub created_at: DateTime<Utc>,
+    pub score_count: i64,
+    pub mean_score: f32,
+    pub metadata: RunMetadata,
+    pub metrics: Vec<MetricSummary>,
+}
+
+/// Count and mean of a metric recorded within a run
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricSummary {
+    pub name: String,
+    pub count: i64,
+    pub mean: f32,
+}
+
+/// Context a run was produced in
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMetadata {
+    pub git_sha: Option<String>,
+    pub branch: Option<String>,
+    pub ci_run_url: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl From<&EvalResult> for RunMetadata {
+    fn from(result: &EvalResult) -> Self {
+        let attributes = match &result.attributes {
+            serde_json::Value::Object(map) => map
+                .iter()
+                .map(|(key, value)| match value {
+                    serde_json::Value::String(value) => (key.clone(), value.clone()),
+                    other => (key.clone(), other.to_string()),
+                })
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+
+        RunMetadata {
+            git_sha: result.git_sha.clone(),
+            branch: result.branch.clone(),
+            ci_run_url: result.ci_run_url.clone(),
+            model: result.model.clone(),
+            temperature: result.temperature,
+            attributes,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RunScore {
+    pub eval_hash: String,
+    pub score: f32,
+    pub metrics: Vec<RunMetric>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMetric {
+    pub name: String,
+    pub value: f32,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RunDetail {
+    #[serde(flatten)]
+    pub run: RunSummary,
+    pub scores: Vec<RunScore>,
+}
+
+#[derive(QueryableByName)]
+struct EvalSummaryRow {
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Text)]
+    prompt_name: String,
+    #[diesel(sql_type = BigInt)]
+    run_count: i64,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    last_run_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListEvalsQuery {
+    pub prompt_name: Option<String>,
+}
+
+/// Filters applied when listing the runs of an eval. Version bounds are inclusive.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RunFilter {
+    pub prompt_name: Option<String>,
+    pub min_version: Option<String>,
+    pub max_version: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub branch: Option<String>,
+}
+
+/// List evals along with how often and how recently they ran. Open runs are not counted.
+pub fn list_evals(
+    conn: &mut PgConnection,
+    prompt_name: Option<&str>,
+) -> anyhow::Result<Vec<EvalSummary>> {
+    let rows = diesel::sql_query(
+        "SELECT e.name, pv.name AS prompt_name, COUNT(er.id) AS run_count, \
+                MAX(er.created_at) AS last_run_at \
+         FROM eval e \
+         JOIN prompt_version pv ON pv.id = e.prompt_version_id \
+         LEFT JOIN eval_result er ON er.eval_id = e.id AND er.status = 'finished' \
+         WHERE $1::text IS NULL OR pv.name = $1 \
+         GROUP BY e.name, pv.name \
+         ORDER BY e.name, pv.name",
+    )
+    .bind::<Nullable<Text>, _>(prompt_name)
+    .load::<EvalSummaryRow>(conn)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| EvalSummary {
+            name: row.name,
+            prompt_name: row.prompt_name,
+            run_count: row.run_count,
+            last_run_at: row.last_run_at,
+        })
+        .collect())
+}
+
+/// List the finished runs of an eval in chronological order
+pub fn list_runs(
+    conn: &mut PgConnection,
+    eval_name: &str,
+    filter: &RunFilter,
+) -> anyhow::Result<Vec<RunSummary>> {
+    let mut query = eval_result::table
+        .inner_join(eval::table.inner_join(prompt_version::table))
+        .filter(eval::name.eq(eval_name))
+        .filter(eval_result::status.eq(STATUS_FINISHED))
+        .select((EvalResult::as_select(), PromptVersion::as_select()))
+        .order(eval_result::created_at.asc())
+        .into_boxed();
+
+    if let Some(prompt_name) = &filter.prompt_name {
+        query = query.filter(prompt_version::name.eq(prompt_name));
+    }
+    if let Some(created_after) = filter.created_after {
+        query = query.filter(eval_result::created_at.ge(created_after));
+    }
+    if let Some(created_before) = filter.created_before {
+        query = query.filter(eval_result::created_at.lt(created_before));
+    }
+    if let Some(branch) = &filter.branch {
+        query = query.filter(eval_result::branch.eq(branch));
+    }
+
+    if filter.min_version.is_some() || filter.max_version.is_some() {
+        let version_ids = prompt_versions_in_range(conn, eval_name, filter)?;
+        query = query.filter(prompt_version::id.eq_any(version_ids));
+    }
+
+    let rows: Vec<(EvalResult, PromptVersion)> = query.load::<(EvalResult, PromptVersion)>(conn)?;
+
+    let ids: Vec<i32> = rows.iter().map(|(result, _)| result.id).collect();
+    let mut totals = summarize_runs(conn, &ids)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(result, prompt)| {
+            let totals = totals.remove(&result.id).unwrap_or_default();
+            let metadata = RunMetadata::from(&result);
+            RunSummary {
+                id: result.id,
+                eval_name: eval_name.to_string(),
+                prompt_name: prompt.name,
+                prompt_version: prompt.version,
+                created_at: result.created_at,
+                score_count: totals.trial_count,
+                mean_score: totals.default_mean(),
+                metadata,
+                metrics: totals.metrics,
+            }
+        })
+        .collect())
+}
+
+/// IDs of the prompt versions evaluated by an eval that lie within the version bounds of the
+/// filter. Versions are compared by semver precedence, which SQL cannot do, so only the versions
+/// are loaded and compared here rather than every run.
+fn prompt_versions_in_range(
+    conn: &mut PgConnection,
+    eval_name: &str,
+    filter: &RunFilter,
+) -> anyhow::Result<Vec<i32>> {
+    let mut query = eval::table
+        .inner_join(prompt_version::table)
+        .filter(eval::name.eq(eval_name))
+        .select((prompt_version::id, prompt_version::version))
+        .distinct()
+        .into_boxed();
+
+    if let Some(prompt_name) = &filter.prompt_name {
+        query = query.filter(prompt_version::name.eq(prompt_name));
+    }
+
+    Ok(query
+        .load::<(i32, String)>(conn)?
+        .into_iter()
+        .filter(|(_, version)| {
+            version::in_range(
+                version,
+                filter.min_version.as_deref(),
+                filter.max_version.as_deref(),
+            )
+        })
+        .map(|(id, _)| id)
+        .collect())
+}
+
+/// Fetch a single run with all of its scores
+pub fn get_run(conn: &mut PgConnection, id: i32) -> anyhow::Result<Option<RunDetail>> {
+    let row = eval_result::table
+        .inner_join(eval::table.inner_join(prompt_version::table))
+        .filter(eval_result::id.eq(id))
+        .select((
+            EvalResult::as_select(),
+            eval::name,
+            PromptVersion::as_select(),
+        ))
+        .first::<(EvalResult, String, PromptVersion)>(conn)
+        .optional()?;
+
+    let Some((result, eval_name, prompt)) = row else {
+        return Ok(None);
+    };
+
+    let totals = summarize_runs(conn, &[result.id])?
+        .remove(&result.id)
+        .unwrap_or_default();
+    let trials = cases::load_trials(conn, result.id)?;
+    let metadata = RunMetadata::from(&result);
+
+    Ok(Some(RunDetail {
+        run: RunSummary {
+            id: result.id,
+            eval_name,
+            prompt_name: prompt.name,
+            prompt_version: prompt.version,
+            created_at: result.created_at,
+            score_count: totals.trial_count,
+            mean_score: totals.default_mean(),
+            metadata,
+            metrics: totals.metrics,
+        },
+        scores: trials
+            .into_iter()
+            .map(|trial| RunScore {
+                eval_hash: trial.eval_hash,
+                score: trial.score,
+                metrics: trial
+                    .metrics
+                    .into_iter()
+                    .map(|metric| RunMetric {
+                        name: metric.name,
+                        value: metric.value,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }))
+}
+
+/// Trials and metrics of a run, aggregated in the database
+#[derive(Debug, Default)]
+struct RunTotals {
+    trial_count: i64,
+    metrics: Vec<MetricSummary>,
+}
+
+impl RunTotals {
+    /// Mean of the plain score, which keeps being reported at the top level for existing clients
+    fn default_mean(&self) -> f32 {
+        self.metrics
+            .iter()
+            .find(|metric| metric.name == DEFAULT_METRIC)
+            .map(|metric| metric.mean)
+            .unwrap_or_default()
+    }
+}
+
+/// Trial count and per-metric count and mean of each run, computed in the database
+fn summarize_runs(conn: &mut PgConnection, ids: &[i32]) -> anyhow::Result<HashMap<i32, RunTotals>> {
+    let mut repository = DieselRepository::new(conn, eval_score::table);
+    let trial_counts = repository.count_trials(ids)?;
+    let metrics = repository.summarize_runs(ids)?;
+
+    let mut totals: HashMap<i32, RunTotals> = trial_counts
+        .into_iter()
+        .map(|(id, trial_count)| {
+            (
+                id,
+                RunTotals {
+                    trial_count,
+                    metrics: Vec::new(),
+                },
+            )
+        })
+        .collect();
+    for metric in metrics {
+        totals
+            .entry(metric.eval_result_id)
+            .or_default()
+            .metrics
+            .push(MetricSummary {
+                name: metric.metric,
+                count: metric.count,
+                mean: metric.mean.unwrap_or_default() as f32,
+            });
+    }
+
+    Ok(totals)
+}
+
+pub async fn evals_get(Query(query): Query<ListEvalsQuery>) -> impl IntoResponse {
+    let mut conn = ellmo_db::establish_connection();
+
+    match list_evals(&mut conn, query.prompt_name.as_deref()) {
+        Ok(evals) => (StatusCode::OK, Json(json!({ "evals": evals }))),
+        Err(e) => internal_error("Failed to list evals", e),
+    }
+}
+
+pub async fn runs_get(
+    Path(eval_name): Path<String>,
+    Query(filter): Query<RunFilter>,
+) -> impl IntoResponse {
+    let mut conn = ellmo_db::establish_connection();
+
+    match list_runs(&mut conn, &eval_name, &filter) {
+        Ok(runs) => (StatusCode::OK, Json(json!({ "runs": runs }))),
+        Err(e) => internal_error("Failed to list eval runs", e),
+    }
+}
+
+pub async fn run_get(Path(id): Path<i32>) -> impl IntoResponse {
+    let mut conn = ellmo_db::establish_connection();
+
+    match get_run(&mut conn, id) {
+        Ok(Some(run)) => (StatusCode::OK, Json(json!(run))),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Eval run not found" })),
+        ),
+        Err(e) => internal_error("Failed to fetch eval run", e),
+    }
+}
+
+/// HTTP response for a status returned by the shared gRPC logic
+fn error_response(status: &tonic::Status) -> (StatusCode, Json<serde_json::Value>) {
+    let code = match status.code() {
+        tonic::Code::NotFound => StatusCode::NOT_FOUND,
+        tonic::Code::InvalidArgument => StatusCode::BAD_REQUEST,
+        tonic::Code::FailedPrecondition | tonic::Code::AlreadyExists => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (code, Json(json!({ "error": status.message() })))
+}
+
+/// Log the cause of a failure but only expose the fixed message to the client
+fn internal_error(
+    error_message: &str,
+    cause: anyhow::Error,
+) -> (StatusCode, Json<serde_json::Value>) {
+    println!("{}: {}", error_message, cause);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": error_message })),
+    )
+}
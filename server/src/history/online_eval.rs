@@ -0,0 +1,64 @@
+use axum::extract::{Path, Query};
+use axum::response::IntoResponse;
+use axum::{http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+
+use ellmo_proto::ellmo::OnlineEvalInterval;
+
+use super::error_response;
+use crate::rpc::online_eval;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ScoresQuery {
+    /// Either hour or day, day by default
+    pub interval: Option<String>,
+    pub rule: Option<String>,
+    pub metric: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+pub async fn online_scores_get(
+    Path(name): Path<String>,
+    Query(query): Query<ScoresQuery>,
+) -> impl IntoResponse {
+    let interval = match query.interval.as_deref() {
+        None | Some("day") => OnlineEvalInterval::OnlineEvalDay,
+        Some("hour") => OnlineEvalInterval::OnlineEvalHour,
+        Some(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "Interval must be hour or day" })),
+            )
+        }
+    };
+    let filter = online_eval::ScoreFilter {
+        rule: query.rule,
+        metric: query.metric,
+        interval,
+        since: query.since,
+        until: query.until,
+    };
+
+    let mut conn = ellmo_db::establish_connection();
+    match online_eval::online_scores(&mut conn, &name, &filter) {
+        Ok(buckets) => (
+            StatusCode::OK,
+            Json(json!({
+                "buckets": buckets
+                    .iter()
+                    .map(|bucket| json!({
+                        "version": bucket.version,
+                        "metric": bucket.metric,
+                        "bucketStart": bucket.bucket_start.as_ref().map(ToString::to_string),
+                        "count": bucket.count,
+                        "mean": bucket.mean,
+                    }))
+                    .collect::<Vec<_>>(),
+            })),
+        ),
+        Err(status) => error_response(&status),
+    }
+}
@@ -0,0 +1,130 @@
+use axum::extract::{Path, Query};
+use axum::response::IntoResponse;
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use ellmo_proto::ellmo::{PromptDiff, PromptDiffSummary, PromptSectionDiff, RenderPromptResponse};
+
+use super::error_response;
+use crate::rpc::prompt;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffQuery {
+    pub base_version: String,
+    pub version: String,
+}
+
+pub async fn diff_get(
+    Path(name): Path<String>,
+    Query(query): Query<DiffQuery>,
+) -> impl IntoResponse {
+    let mut conn = ellmo_db::establish_connection();
+
+    match prompt::diff_versions(&mut conn, &name, &query.base_version, &query.version) {
+        Ok(diff) => (StatusCode::OK, Json(diff_json(&diff))),
+        Err(status) => error_response(&status),
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderBody {
+    pub version: Option<String>,
+    pub label: Option<String>,
+    #[serde(default)]
+    pub variables: Map<String, Value>,
+    pub parent_span_id: Option<String>,
+}
+
+pub async fn render_post(
+    Path(name): Path<String>,
+    Json(body): Json<RenderBody>,
+) -> impl IntoResponse {
+    let mut conn = ellmo_db::establish_connection();
+
+    match prompt::render_version(
+        &mut conn,
+        &name,
+        body.version,
+        body.label,
+        &body.variables,
+        body.parent_span_id.as_deref(),
+    ) {
+        Ok(rendered) => (StatusCode::OK, Json(rendered_json(&rendered))),
+        Err(status) => error_response(&status),
+    }
+}
+
+fn rendered_json(rendered: &RenderPromptResponse) -> Value {
+    json!({
+        "name": rendered.prompt.as_ref().map(|prompt| &prompt.name),
+        "version": rendered.prompt.as_ref().map(|prompt| &prompt.version),
+        "text": rendered.text,
+        "messages": rendered
+            .messages
+            .iter()
+            .map(|message| json!({ "role": message.role, "content": message.content }))
+            .collect::<Vec<_>>(),
+        "model": rendered.model,
+        "parameters": rendered.parameters.as_deref().map(parse_value),
+        "spanId": rendered.span_id,
+    })
+}
+
+fn diff_json(diff: &PromptDiff) -> Value {
+    json!({
+        "summary": diff.summary.as_ref().map(summary_json),
+        "sections": diff.sections.iter().map(section_json).collect::<Vec<_>>(),
+    })
+}
+
+fn summary_json(summary: &PromptDiffSummary) -> Value {
+    json!({
+        "baseVersion": summary.base_version,
+        "version": summary.version,
+        "identical": summary.identical,
+        "linesAdded": summary.lines_added,
+        "linesRemoved": summary.lines_removed,
+        "changedSections": summary.changed_sections,
+        "settingChanges": summary
+            .setting_changes
+            .iter()
+            .map(|change| {
+                json!({
+                    "name": change.name,
+                    "oldValue": change.old_value.as_deref().map(parse_value),
+                    "newValue": change.new_value.as_deref().map(parse_value),
+                })
+            })
+            .collect::<Vec<_>>(),
+        "description": summary.description,
+    })
+}
+
+fn section_json(section: &PromptSectionDiff) -> Value {
+    json!({
+        "section": section.section,
+        "lines": section
+            .lines
+            .iter()
+            .map(|line| {
+                json!({
+                    "change": line.change().as_str_name(),
+                    "text": line.text,
+                    "spans": line
+                        .spans
+                        .iter()
+                        .map(|span| json!({ "text": span.text, "changed": span.changed }))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Setting values are JSON-encoded in the API, and embedded as JSON here
+fn parse_value(encoded: &str) -> Value {
+    serde_json::from_str(encoded).unwrap_or_else(|_| Value::String(encoded.to_string()))
+}
@@ -1,7 +1,16 @@
+mod annotation;
+mod experiment;
+mod history;
+mod online_eval;
+mod prompt;
 mod queue;
 mod register;
 mod rpc;
+mod scorer;
+mod stats;
 mod tracing;
+mod version;
+mod webhook;
 
 use axum::{
     routing::{get, post},
@@ -13,11 +22,36 @@ use rpc::RpcServer;
 
 #[tokio::main]
 async fn main() {
+    match webhook::resume_pending() {
+        Ok(0) => {}
+        Ok(count) => println!("Resuming {} pending webhook deliveries", count),
+        Err(e) => println!("Failed to resume pending webhook deliveries: {}", e),
+    }
+    match online_eval::resume_pending() {
+        Ok(0) => {}
+        Ok(count) => println!("Resuming {} pending online eval scoring jobs", count),
+        Err(e) => println!("Failed to resume pending online eval scoring jobs: {}", e),
+    }
+
     tokio::task::spawn(async {
         let app = Router::new()
             .route("/", get(root))
             .route("/api/v1/tracing", post(tracing::post))
             .route("/api/v1/test/register", post(register::test_post))
+            .route("/api/v1/evals", get(history::evals_get))
+            .route("/api/v1/evals/:name/runs", get(history::runs_get))
+            .route("/api/v1/eval-runs/:id", get(history::run_get))
+            .route("/api/v1/eval-runs/:id/compare", get(history::compare_get))
+            .route("/api/v1/prompts/:name/diff", get(history::diff_get))
+            .route("/api/v1/prompts/:name/render", post(history::render_post))
+            .route(
+                "/api/v1/prompts/:name/online-scores",
+                get(history::online_scores_get),
+            )
+            .route(
+                "/api/v1/experiments/:name/results",
+                get(history::results_get),
+            )
             .layer(CorsLayer::permissive());
 
         let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
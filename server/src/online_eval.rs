@@ -0,0 +1,244 @@
+use anyhow::anyhow;
+use chrono::Utc;
+use diesel::prelude::*;
+use sha2::{Digest, Sha256};
+
+use ellmo_db::{
+    models::{
+        online_eval_rule::OnlineEvalRule,
+        repository::{DieselRepository, Repository},
+        span::Span,
+        span_score::InsertableSpanScore,
+        span_score_job::{InsertableSpanScoreJob, SpanScoreJob},
+    },
+    schema::{online_eval_rule, span, span_score, span_score_job},
+    try_establish_connection,
+};
+
+use crate::queue::{Job, JOB_QUEUE};
+use crate::rpc::eval::scoring::RunScorers;
+
+/// Record and queue scoring jobs for the ingested spans that enabled rules match and sample,
+/// returning the number of jobs queued
+pub fn schedule(conn: &mut PgConnection, spans: &[Span]) -> QueryResult<usize> {
+    if spans.is_empty() {
+        return Ok(0);
+    }
+
+    let rules = DieselRepository::new(conn, online_eval_rule::table).find_enabled()?;
+    let now = Utc::now();
+    let jobs: Vec<InsertableSpanScoreJob> = spans
+        .iter()
+        .flat_map(|span| {
+            rules
+                .iter()
+                .filter(|rule| matches(rule, span) && sampled(rule, span))
+                .map(|rule| InsertableSpanScoreJob {
+                    span_id: span.id,
+                    online_eval_rule_id: rule.id,
+                    created_at: now,
+                })
+        })
+        .collect();
+    if jobs.is_empty() {
+        return Ok(0);
+    }
+
+    let jobs = diesel::insert_into(span_score_job::table)
+        .values(&jobs)
+        .on_conflict_do_nothing()
+        .returning(span_score_job::all_columns)
+        .get_results::<SpanScoreJob>(conn)?;
+    for job in &jobs {
+        enqueue(job);
+    }
+
+    Ok(jobs.len())
+}
+
+/// Queue the scoring jobs left over, e.g. by a restart
+pub fn resume_pending() -> anyhow::Result<usize> {
+    let mut conn = try_establish_connection()?;
+    let pending = DieselRepository::new(&mut conn, span_score_job::table).find_pending()?;
+    for job in &pending {
+        enqueue(job);
+    }
+
+    Ok(pending.len())
+}
+
+fn enqueue(job: &SpanScoreJob) {
+    JOB_QUEUE
+        .lock()
+        .expect("Job queue lock poisoned")
+        .add_job(Box::new(ScoreSpan {
+            job_id: job.id,
+            span_id: job.span_id,
+            rule_id: job.online_eval_rule_id,
+        }));
+}
+
+/// Whether a span has the operation name and every attribute value required by a rule
+fn matches(rule: &OnlineEvalRule, span: &Span) -> bool {
+    if rule
+        .operation_name
+        .as_ref()
+        .is_some_and(|operation_name| *operation_name != span.operation_name)
+    {
+        return false;
+    }
+
+    let Some(required) = rule.attributes.as_object() else {
+        return true;
+    };
+    required
+        .iter()
+        .all(|(name, value)| span.attributes.get(name) == Some(value))
+}
+
+/// Whether a span is part of the sample of a rule. Spans are hashed along with the rule name, so
+/// that a span is either always or never sampled by a rule, independently of other rules.
+fn sampled(rule: &OnlineEvalRule, span: &Span) -> bool {
+    if rule.sample_rate >= 1.0 {
+        return true;
+    }
+
+    let key = match span.external_uuid {
+        Some(uuid) => uuid.to_string(),
+        None => span.id.to_string(),
+    };
+    let digest = Sha256::digest(format!("{}\n{}", rule.name, key));
+    let bucket = u64::from_be_bytes(digest[..8].try_into().unwrap());
+
+    (bucket as f64 / u64::MAX as f64) < rule.sample_rate
+}
+
+/// Application of the scorers of a rule to the output of a span
+struct ScoreSpan {
+    job_id: i32,
+    span_id: i32,
+    rule_id: i32,
+}
+
+#[async_trait::async_trait]
+impl Job for ScoreSpan {
+    async fn execute(&self) {
+        // Without the database, the job is kept to run again after a restart
+        let mut conn = match try_establish_connection() {
+            Ok(conn) => conn,
+            Err(e) => {
+                println!("Failed to run scoring job {}: {}", self.job_id, e);
+                return;
+            }
+        };
+
+        if let Err(e) = self.score(&mut conn) {
+            println!(
+                "Failed to score span {} for online eval rule {}: {}",
+                self.span_id, self.rule_id, e
+            );
+        }
+
+        // Scored or failing for good, so the job is done either way
+        if let Err(e) = DieselRepository::new(&mut conn, span_score_job::table).delete(self.job_id)
+        {
+            println!("Failed to complete scoring job {}: {}", self.job_id, e);
+        }
+    }
+}
+
+impl ScoreSpan {
+    fn score(&self, conn: &mut PgConnection) -> anyhow::Result<()> {
+        let rule = DieselRepository::new(conn, online_eval_rule::table).find_by_id(self.rule_id)?;
+        if !rule.enabled {
+            return Ok(());
+        }
+        let span = DieselRepository::new(conn, span::table).find_by_id(self.span_id)?;
+        let output = span
+            .output
+            .ok_or_else(|| anyhow!("Span has no output to score"))?;
+
+        let scorers = RunScorers::load(conn, &rule.scorers)
+            .map_err(|status| anyhow!("{}", status.message()))?;
+        let now = Utc::now();
+        let mut scores = Vec::new();
+        for (metric, score) in scorers.score_output(&output) {
+            match score {
+                Ok(score) => scores.push(InsertableSpanScore {
+                    span_id: span.id,
+                    online_eval_rule_id: rule.id,
+                    metric: metric.to_string(),
+                    score: f64::from(score),
+                    created_at: now,
+                }),
+                Err(e) => println!("Failed to compute {} of span {}: {}", metric, span.id, e),
+            }
+        }
+
+        diesel::insert_into(span_score::table)
+            .values(&scores)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::span::sample_span;
+    use serde_json::{json, Value};
+
+    fn rule(operation_name: Option<&str>, attributes: Value, sample_rate: f64) -> OnlineEvalRule {
+        OnlineEvalRule {
+            id: 1,
+            name: "answers".to_string(),
+            operation_name: operation_name.map(str::to_string),
+            attributes,
+            sample_rate,
+            scorers: json!([]),
+            enabled: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn span(id: i32, operation_name: &str, attributes: Value) -> Span {
+        Span {
+            id,
+            ..sample_span(operation_name, attributes)
+        }
+    }
+
+    #[test]
+    fn test_matches_operation_name_and_attributes() {
+        let answers = rule(Some("llm.call"), json!({"env": "production"}), 1.0);
+        let production = json!({"env": "production", "model": "gpt-4o"});
+
+        assert!(matches(&answers, &span(1, "llm.call", production.clone())));
+        assert!(!matches(
+            &answers,
+            &span(1, "llm.call", json!({"env": "staging"}))
+        ));
+        assert!(!matches(&answers, &span(1, "llm.call", json!({}))));
+        assert!(!matches(&answers, &span(1, "prompt.render", production)));
+        assert!(matches(
+            &rule(None, json!({}), 1.0),
+            &span(1, "anything", json!({}))
+        ));
+    }
+
+    #[test]
+    fn test_sampled_follows_sample_rate() {
+        let all = rule(None, json!({}), 1.0);
+        let tenth = rule(None, json!({}), 0.1);
+        let spans: Vec<Span> = (0..10_000)
+            .map(|id| span(id, "llm.call", json!({})))
+            .collect();
+
+        assert!(spans.iter().all(|span| sampled(&all, span)));
+        let share = spans.iter().filter(|span| sampled(&tenth, span)).count() as f64 / 10_000.0;
+        assert!((share - 0.1).abs() < 0.01, "sampled share was {}", share);
+        assert_eq!(sampled(&tenth, &spans[7]), sampled(&tenth, &spans[7]));
+    }
+}
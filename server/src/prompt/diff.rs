@@ -0,0 +1,319 @@
+use serde_json::Value;
+use similar::{ChangeTag, TextDiff};
+use std::collections::BTreeSet;
+
+use super::Template;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// Part of a line, `changed` when the words differ from the other version
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub change: Change,
+    pub spans: Vec<Span>,
+}
+
+impl Line {
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+}
+
+/// Line diff of a text of the templates, the text of a completion prompt or a chat message
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionDiff {
+    pub section: String,
+    pub lines: Vec<Line>,
+}
+
+impl SectionDiff {
+    pub fn is_changed(&self) -> bool {
+        self.lines.iter().any(|line| line.change != Change::Equal)
+    }
+}
+
+/// Change of a setting of the templates, with values encoded as JSON. A value is absent when the
+/// setting is only part of one of the templates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingChange {
+    pub name: String,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateDiff {
+    pub sections: Vec<SectionDiff>,
+    pub setting_changes: Vec<SettingChange>,
+}
+
+impl TemplateDiff {
+    pub fn is_identical(&self) -> bool {
+        self.setting_changes.is_empty() && !self.sections.iter().any(SectionDiff::is_changed)
+    }
+
+    pub fn lines_added(&self) -> usize {
+        self.count_lines(Change::Insert)
+    }
+
+    pub fn lines_removed(&self) -> usize {
+        self.count_lines(Change::Delete)
+    }
+
+    pub fn changed_sections(&self) -> Vec<String> {
+        self.sections
+            .iter()
+            .filter(|section| section.is_changed())
+            .map(|section| section.section.clone())
+            .collect()
+    }
+
+    /// One line description of the changes, e.g. "+2 -1 lines in message 1; temperature: 0.2 -> 0.5"
+    pub fn summary(&self) -> String {
+        if self.is_identical() {
+            return "No changes".to_string();
+        }
+
+        let mut parts = Vec::new();
+        let changed_sections = self.changed_sections();
+        if !changed_sections.is_empty() {
+            parts.push(format!(
+                "+{} -{} lines in {}",
+                self.lines_added(),
+                self.lines_removed(),
+                changed_sections.join(", ")
+            ));
+        }
+        for change in &self.setting_changes {
+            parts.push(format!(
+                "{}: {} -> {}",
+                change.name,
+                display_value(change.old_value.as_ref()),
+                display_value(change.new_value.as_ref())
+            ));
+        }
+
+        parts.join("; ")
+    }
+
+    fn count_lines(&self, change: Change) -> usize {
+        self.sections
+            .iter()
+            .flat_map(|section| &section.lines)
+            .filter(|line| line.change == change)
+            .count()
+    }
+}
+
+fn display_value(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+        None => "unset".to_string(),
+    }
+}
+
+/// Differences between two templates. Chat messages are compared by position.
+pub fn diff(old: &Template, new: &Template) -> TemplateDiff {
+    let old_sections = sections(old);
+    let new_sections = sections(new);
+
+    let mut sections = Vec::new();
+    for i in 0..old_sections.len().max(new_sections.len()) {
+        let old_section = old_sections.get(i);
+        let new_section = new_sections.get(i);
+        sections.push(SectionDiff {
+            section: new_section
+                .or(old_section)
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default(),
+            lines: diff_lines(
+                old_section.map_or("", |(_, text)| text.as_str()),
+                new_section.map_or("", |(_, text)| text.as_str()),
+            ),
+        });
+    }
+
+    TemplateDiff {
+        sections,
+        setting_changes: settings_diff(old, new),
+    }
+}
+
+/// Texts of a template by section name
+fn sections(template: &Template) -> Vec<(String, String)> {
+    match &template.text {
+        Some(text) => vec![("text".to_string(), text.clone())],
+        None => template
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(i, message)| (format!("message {}", i + 1), message.content.clone()))
+            .collect(),
+    }
+}
+
+fn diff_lines(old: &str, new: &str) -> Vec<Line> {
+    let diff = TextDiff::from_lines(old, new);
+
+    let mut lines = Vec::new();
+    for op in diff.ops() {
+        for change in diff.iter_inline_changes(op) {
+            let change_kind = match change.tag() {
+                ChangeTag::Equal => Change::Equal,
+                ChangeTag::Insert => Change::Insert,
+                ChangeTag::Delete => Change::Delete,
+            };
+            let spans = change
+                .iter_strings_lossy()
+                .map(|(emphasized, text)| Span {
+                    text: text.trim_end_matches('\n').to_string(),
+                    changed: emphasized,
+                })
+                .filter(|span| !span.text.is_empty())
+                .collect();
+
+            lines.push(Line {
+                change: change_kind,
+                spans,
+            });
+        }
+    }
+
+    lines
+}
+
+/// Changes of the model, variables, message roles and model parameters
+fn settings_diff(old: &Template, new: &Template) -> Vec<SettingChange> {
+    let mut changes = Vec::new();
+    let mut compare = |name: String, old_value: Option<Value>, new_value: Option<Value>| {
+        if old_value != new_value {
+            changes.push(SettingChange {
+                name,
+                old_value,
+                new_value,
+            });
+        }
+    };
+
+    compare(
+        "model".to_string(),
+        old.model.clone().map(Value::String),
+        new.model.clone().map(Value::String),
+    );
+    compare(
+        "variables".to_string(),
+        Some(Value::from(old.variables.clone())),
+        Some(Value::from(new.variables.clone())),
+    );
+    // Added and removed messages already show as changed sections
+    for (i, (old_message, new_message)) in old.messages.iter().zip(&new.messages).enumerate() {
+        compare(
+            format!("message {} role", i + 1),
+            Some(Value::String(old_message.role.clone())),
+            Some(Value::String(new_message.role.clone())),
+        );
+    }
+
+    let empty = serde_json::Map::new();
+    let old_parameters = old
+        .parameters
+        .as_ref()
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let new_parameters = new
+        .parameters
+        .as_ref()
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let names: BTreeSet<&String> = old_parameters.keys().chain(new_parameters.keys()).collect();
+    for name in names {
+        compare(
+            name.clone(),
+            old_parameters.get(name).cloned(),
+            new_parameters.get(name).cloned(),
+        );
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::chat;
+    use serde_json::json;
+
+    fn summarizer(system: &str, parameters: Value) -> Template {
+        chat(system, "{{ document }}", &["document"], Some(parameters))
+    }
+
+    #[test]
+    fn test_diff_marks_changed_lines_and_words() {
+        let old = summarizer(
+            "You are a helpful assistant.\nSummarize in three sentences.",
+            json!({"temperature": 0.2}),
+        );
+        let new = summarizer(
+            "You are a helpful assistant.\nSummarize in two sentences.\nBe concise.",
+            json!({"temperature": 0.2}),
+        );
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.changed_sections(), vec!["message 1"]);
+        assert_eq!((diff.lines_added(), diff.lines_removed()), (2, 1));
+        assert!(diff.setting_changes.is_empty());
+
+        let lines = &diff.sections[0].lines;
+        assert_eq!(lines[0].change, Change::Equal);
+        let removed = lines
+            .iter()
+            .find(|line| line.change == Change::Delete)
+            .unwrap();
+        assert_eq!(removed.text(), "Summarize in three sentences.");
+        let changed_words: Vec<&str> = removed
+            .spans
+            .iter()
+            .filter(|span| span.changed)
+            .map(|span| span.text.as_str())
+            .collect();
+        assert_eq!(changed_words, vec!["three"]);
+        assert_eq!(diff.summary(), "+2 -1 lines in message 1");
+    }
+
+    #[test]
+    fn test_diff_reports_setting_changes() {
+        let old = summarizer("Summarize.", json!({"temperature": 0.2, "top_p": 1}));
+        let mut new = summarizer("Summarize.", json!({"temperature": 0.5, "max_tokens": 100}));
+        new.messages[1].role = "assistant".to_string();
+
+        let diff = diff(&old, &new);
+        let names: Vec<&str> = diff
+            .setting_changes
+            .iter()
+            .map(|change| change.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["message 2 role", "max_tokens", "temperature", "top_p"]
+        );
+        assert_eq!(
+            diff.summary(),
+            "message 2 role: user -> assistant; max_tokens: unset -> 100; \
+             temperature: 0.2 -> 0.5; top_p: 1 -> unset"
+        );
+        assert!(!diff.is_identical());
+        assert!(super::diff(&old, &old).is_identical());
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+pub mod diff;
+pub mod render;
+
+/// Content of a prompt version, as stored with it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    pub text: Option<String>,
+    pub messages: Vec<Message>,
+    pub variables: Vec<String>,
+    pub model: Option<String>,
+    pub parameters: Option<Value>,
+}
+
+/// Single message of a chat prompt
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+}
+
+impl Template {
+    /// Check that a template is either a text or a list of messages, and that variables are
+    /// declared once with valid names
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match (&self.text, self.messages.is_empty()) {
+            (Some(_), false) => bail!("Template must have either a text or messages, not both"),
+            (None, true) => bail!("Template must have a text or messages"),
+            _ => {}
+        }
+        if self.messages.iter().any(|message| message.role.is_empty()) {
+            bail!("Every message must have a role");
+        }
+
+        for (i, variable) in self.variables.iter().enumerate() {
+            if !is_identifier(variable) {
+                bail!("Invalid variable name {:?}", variable);
+            }
+            if self.variables[..i].contains(variable) {
+                bail!("Duplicate variable {}", variable);
+            }
+        }
+
+        if let Some(parameters) = &self.parameters {
+            if !parameters.is_object() {
+                bail!("Parameters must be a JSON object");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Identity of the content of a template. Objects are encoded with sorted keys, so the hash
+    /// does not depend on the order parameters were given in.
+    pub fn content_hash(&self) -> String {
+        let encoded = serde_json::to_vec(self).expect("Templates are always encodable");
+        format!("{:x}", Sha256::digest(encoded))
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Template of a system and a user message, shared by the tests of the prompt modules
+#[cfg(test)]
+pub(crate) fn chat(
+    system: &str,
+    user: &str,
+    variables: &[&str],
+    parameters: Option<Value>,
+) -> Template {
+    Template {
+        text: None,
+        messages: vec![
+            Message {
+                role: "system".to_string(),
+                content: system.to_string(),
+            },
+            Message {
+                role: "user".to_string(),
+                content: user.to_string(),
+            },
+        ],
+        variables: variables.iter().map(|name| name.to_string()).collect(),
+        model: Some("gpt-4o".to_string()),
+        parameters,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summarizer(parameters: Value) -> Template {
+        chat(
+            "You summarize {{ document }}",
+            "{{ question }}",
+            &["document", "question"],
+            Some(parameters),
+        )
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(summarizer(json!({"temperature": 0.2})).validate().is_ok());
+
+        let mut both = summarizer(json!({}));
+        both.text = Some("Summarize {{ document }}".to_string());
+        assert!(both.validate().is_err());
+
+        let mut neither = summarizer(json!({}));
+        neither.messages.clear();
+        assert!(neither.validate().is_err());
+
+        let mut duplicate = summarizer(json!({}));
+        duplicate.variables.push("document".to_string());
+        assert!(duplicate.validate().is_err());
+
+        let mut invalid_name = summarizer(json!({}));
+        invalid_name.variables = vec!["1st".to_string()];
+        assert!(invalid_name.validate().is_err());
+
+        assert!(summarizer(json!([0.2])).validate().is_err());
+    }
+
+    #[test]
+    fn test_content_hash_ignores_parameter_order() {
+        let a: Value = serde_json::from_str(r#"{"temperature": 0.2, "max_tokens": 100}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"max_tokens": 100, "temperature": 0.2}"#).unwrap();
+
+        assert_eq!(summarizer(a).content_hash(), summarizer(b).content_hash());
+        assert_ne!(
+            summarizer(json!({"temperature": 0.2})).content_hash(),
+            summarizer(json!({"temperature": 0.3})).content_hash()
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::{anyhow, bail};
+use minijinja::{Environment, UndefinedBehavior};
+use serde_json::{Map, Value};
+
+use super::{Message, Template};
+
+/// Template rendered with a set of variables
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rendered {
+    pub text: Option<String>,
+    pub messages: Vec<Message>,
+}
+
+/// Render a template with Jinja syntax: `{{ name }}` substitutes a variable, `{% if %}` and
+/// `{% for %}` blocks allow conditionals and loops over lists. Every declared variable must be
+/// given, and referencing a value that does not exist is an error rather than an empty string.
+pub fn render(template: &Template, variables: &Map<String, Value>) -> anyhow::Result<Rendered> {
+    let missing: Vec<&str> = template
+        .variables
+        .iter()
+        .filter(|variable| !variables.contains_key(*variable))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        bail!("Missing variables: {}", missing.join(", "));
+    }
+
+    let environment = environment();
+    let render = |section: &str, source: &str| {
+        environment
+            .render_str(source, variables)
+            .map_err(|e| anyhow!("Failed to render {}: {}", section, describe(&e)))
+    };
+
+    Ok(Rendered {
+        text: template
+            .text
+            .as_deref()
+            .map(|text| render("text", text))
+            .transpose()?,
+        messages: template
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(i, message)| {
+                Ok(Message {
+                    role: message.role.clone(),
+                    content: render(&format!("message {}", i + 1), &message.content)?,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+    })
+}
+
+fn environment() -> Environment<'static> {
+    let mut environment = Environment::new();
+    environment.set_undefined_behavior(UndefinedBehavior::Strict);
+    // Block tags on their own line do not leave blank lines behind
+    environment.set_trim_blocks(true);
+    environment.set_lstrip_blocks(true);
+    environment.set_keep_trailing_newline(true);
+    environment
+}
+
+/// Error message with the line it occurred on, without the name of the inline template
+fn describe(error: &minijinja::Error) -> String {
+    let detail = match error.detail() {
+        Some(detail) => format!("{}: {}", error.kind(), detail),
+        None => error.kind().to_string(),
+    };
+    match error.line() {
+        Some(line) => format!("{} (line {})", detail, line),
+        None => detail,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::chat;
+    use serde_json::json;
+
+    fn variables(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_render_conditionals_and_loops() {
+        let template = chat(
+            "You answer questions about {{ product }}.\n\
+             {% if examples %}\n\
+             Examples:\n\
+             {% for example in examples %}\n\
+             - {{ example.question }}: {{ example.answer }}\n\
+             {% endfor %}\n\
+             {% endif %}",
+            "{{ question }}",
+            &["product", "examples", "question"],
+            None,
+        );
+
+        let rendered = render(
+            &template,
+            &variables(json!({
+                "product": "Ellmo",
+                "examples": [
+                    {"question": "Is it free?", "answer": "Yes"},
+                    {"question": "Is it fast?", "answer": "Very"},
+                ],
+                "question": "<b>How do I start?</b>",
+                "unused": true,
+            })),
+        )
+        .unwrap();
+        assert_eq!(rendered.text, None);
+        assert_eq!(
+            rendered.messages[0].content,
+            "You answer questions about Ellmo.\n\
+             Examples:\n\
+             - Is it free?: Yes\n\
+             - Is it fast?: Very\n"
+        );
+        assert_eq!(rendered.messages[1].role, "user");
+        assert_eq!(rendered.messages[1].content, "<b>How do I start?</b>");
+
+        let without_examples = render(
+            &template,
+            &variables(json!({"product": "Ellmo", "examples": [], "question": "Hi"})),
+        )
+        .unwrap();
+        assert_eq!(
+            without_examples.messages[0].content,
+            "You answer questions about Ellmo.\n"
+        );
+    }
+
+    #[test]
+    fn test_render_rejects_missing_and_undefined_values() {
+        let template = chat(
+            "Summarize {{ document }}",
+            "{{ question }}",
+            &["document"],
+            None,
+        );
+
+        let missing = render(&template, &Map::new()).unwrap_err();
+        assert_eq!(missing.to_string(), "Missing variables: document");
+
+        // Undeclared variables are not required, but may not be silently left out
+        let undefined = render(&template, &variables(json!({"document": "x"}))).unwrap_err();
+        assert!(undefined
+            .to_string()
+            .starts_with("Failed to render message 2"));
+
+        let invalid = Template {
+            text: Some("{% if document %}unclosed".to_string()),
+            messages: vec![],
+            variables: vec!["document".to_string()],
+            model: None,
+            parameters: None,
+        };
+        let error = render(&invalid, &variables(json!({"document": "x"}))).unwrap_err();
+        assert!(error.to_string().starts_with("Failed to render text"));
+    }
+}
@@ -26,9 +26,11 @@ impl JobQueue {
     }
 
     fn start_worker(&self, receiver: Receiver<Box<dyn Job + Send>>) {
-        tokio::spawn(async move {
+        let runtime = tokio::runtime::Handle::current();
+        // Waiting for jobs blocks, so it must not happen on one of the async worker threads
+        tokio::task::spawn_blocking(move || {
             while let Ok(job) = receiver.recv() {
-                job.execute().await;
+                runtime.block_on(job.execute());
             }
         });
     }
@@ -0,0 +1,425 @@
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{Int4, Text, Timestamptz};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        annotation::InsertableAnnotation,
+        annotation_item::{AnnotationItem, InsertableAnnotationItem},
+        annotation_queue::{AnnotationQueue, InsertableAnnotationQueue},
+        eval_case::InsertableEvalCase,
+        repository::{DieselRepository, Repository},
+        span::Span,
+    },
+    schema::{annotation, annotation_item, annotation_queue, span},
+};
+use ellmo_proto::ellmo::{
+    AnnotationItem as ItemMessage, AnnotationQueue as QueueMessage, ClaimAnnotationItemRequest,
+    ClaimAnnotationItemResponse, CreateAnnotationQueueRequest, CreateAnnotationQueueResponse,
+    CriterionAgreement, EnqueueAnnotationItemsRequest, EnqueueAnnotationItemsResponse,
+    ExportAnnotationsRequest, ExportAnnotationsResponse, GetAnnotationAgreementRequest,
+    GetAnnotationAgreementResponse, RubricCriterion, SubmitAnnotationRequest,
+    SubmitAnnotationResponse,
+};
+
+use super::dataset::append_cases;
+use super::eval::cases::case_hash;
+use super::eval::metadata::non_empty;
+use super::span::select_spans;
+use super::to_timestamp;
+use crate::annotation::{self as rubric, Answer, Criterion};
+
+/// Time after which an item claimed but not annotated can be claimed by another reviewer
+const CLAIM_TIMEOUT_MINUTES: i64 = 30;
+
+/// Create an annotation queue with a rubric
+pub async fn create_annotation_queue(
+    request: Request<CreateAnnotationQueueRequest>,
+) -> Result<Response<CreateAnnotationQueueResponse>, Status> {
+    let queue = request
+        .into_inner()
+        .queue
+        .ok_or_else(|| Status::invalid_argument("Missing queue"))?;
+    if queue.name.is_empty() {
+        return Err(Status::invalid_argument("Missing queue name"));
+    }
+    let criteria: Vec<Criterion> = queue.rubric.into_iter().map(convert_criterion).collect();
+    rubric::validate_rubric(&criteria).map_err(|e| Status::invalid_argument(e.to_string()))?;
+    let annotations_per_item = i32::try_from(queue.annotations_per_item.max(1))
+        .map_err(|_| Status::invalid_argument("Too many annotations per item"))?;
+    let stored_rubric =
+        serde_json::to_value(&criteria).map_err(|_| Status::internal("Failed to encode rubric"))?;
+
+    let mut conn = establish_connection();
+    let created = diesel::insert_into(annotation_queue::table)
+        .values(&InsertableAnnotationQueue {
+            name: queue.name.clone(),
+            description: non_empty(queue.description),
+            rubric: stored_rubric,
+            annotations_per_item,
+            created_at: Utc::now(),
+        })
+        .on_conflict(annotation_queue::name)
+        .do_nothing()
+        .returning(annotation_queue::all_columns)
+        .get_result::<AnnotationQueue>(&mut conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to create annotation queue"))?
+        .ok_or_else(|| {
+            Status::already_exists(format!("Annotation queue {} already exists", queue.name))
+        })?;
+
+    Ok(Response::new(CreateAnnotationQueueResponse {
+        queue: Some(convert_queue(created)?),
+    }))
+}
+
+/// Push spans into a queue, by ID or by filter. Spans already in the queue are skipped.
+pub async fn enqueue_annotation_items(
+    request: Request<EnqueueAnnotationItemsRequest>,
+) -> Result<Response<EnqueueAnnotationItemsResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let queue = find_queue(&mut conn, &message.queue)?;
+    let spans = select_spans(&mut conn, &message.span_ids, message.filter.as_ref())?;
+
+    let now = Utc::now();
+    let items: Vec<InsertableAnnotationItem> = spans
+        .iter()
+        .map(|span| InsertableAnnotationItem {
+            annotation_queue_id: queue.id,
+            span_id: span.id,
+            claimed_by: None,
+            claimed_at: None,
+            created_at: now,
+        })
+        .collect();
+    let added_count = DieselRepository::new(&mut conn, annotation_item::table)
+        .create_missing(&items)
+        .map_err(|_| Status::internal("Failed to enqueue annotation items"))?;
+
+    Ok(Response::new(EnqueueAnnotationItemsResponse {
+        added_count: added_count as u32,
+    }))
+}
+
+#[derive(QueryableByName)]
+struct ClaimedRow {
+    #[diesel(sql_type = Int4)]
+    id: i32,
+    #[diesel(sql_type = Int4)]
+    span_id: i32,
+    #[diesel(sql_type = Timestamptz)]
+    claimed_at: DateTime<Utc>,
+}
+
+/// Reserve the oldest item of a queue the reviewer has not annotated yet, that still needs
+/// annotations and is not claimed by someone else. An item the reviewer already holds is
+/// returned again.
+pub async fn claim_annotation_item(
+    request: Request<ClaimAnnotationItemRequest>,
+) -> Result<Response<ClaimAnnotationItemResponse>, Status> {
+    let message = request.into_inner();
+    if message.annotator.is_empty() {
+        return Err(Status::invalid_argument("Missing annotator"));
+    }
+
+    let mut conn = establish_connection();
+    let queue = find_queue(&mut conn, &message.queue)?;
+
+    let now = Utc::now();
+    let claimed = diesel::sql_query(
+        "UPDATE annotation_item SET claimed_by = $2, claimed_at = $3 \
+         WHERE id = ( \
+             SELECT i.id FROM annotation_item i \
+             WHERE i.annotation_queue_id = $1 \
+               AND (i.claimed_by IS NULL OR i.claimed_by = $2 OR i.claimed_at < $4) \
+               AND NOT EXISTS ( \
+                   SELECT 1 FROM annotation a \
+                   WHERE a.annotation_item_id = i.id AND a.annotator = $2) \
+               AND (SELECT COUNT(*) FROM annotation a WHERE a.annotation_item_id = i.id) < $5 \
+             ORDER BY i.claimed_by IS NOT DISTINCT FROM $2 DESC, i.id \
+             LIMIT 1 \
+             FOR UPDATE SKIP LOCKED) \
+         RETURNING id, span_id, claimed_at",
+    )
+    .bind::<Int4, _>(queue.id)
+    .bind::<Text, _>(&message.annotator)
+    .bind::<Timestamptz, _>(now)
+    .bind::<Timestamptz, _>(now - Duration::minutes(CLAIM_TIMEOUT_MINUTES))
+    .bind::<Int4, _>(queue.annotations_per_item)
+    .get_result::<ClaimedRow>(&mut conn)
+    .optional()
+    .map_err(|_| Status::internal("Failed to claim annotation item"))?;
+
+    let item = match claimed {
+        Some(claimed) => {
+            let span = DieselRepository::new(&mut conn, span::table)
+                .find_by_id(claimed.span_id)
+                .map_err(|_| Status::internal("Failed to fetch span"))?;
+            Some(convert_item(claimed.id, &span, claimed.claimed_at))
+        }
+        None => None,
+    };
+
+    Ok(Response::new(ClaimAnnotationItemResponse { item }))
+}
+
+enum Submission {
+    Submitted { annotation_count: i64 },
+    ItemNotFound,
+    NotClaimed,
+}
+
+/// Annotate an item claimed by the reviewer, releasing the claim
+pub async fn submit_annotation(
+    request: Request<SubmitAnnotationRequest>,
+) -> Result<Response<SubmitAnnotationResponse>, Status> {
+    let message = request.into_inner();
+    if message.annotator.is_empty() {
+        return Err(Status::invalid_argument("Missing annotator"));
+    }
+    let answers = message
+        .answers
+        .into_iter()
+        .map(|answer| match (answer.label, answer.score) {
+            (Some(label), None) => Ok((answer.criterion, Answer::Label(label))),
+            (None, Some(score)) => Ok((answer.criterion, Answer::Score(score))),
+            _ => Err(Status::invalid_argument(format!(
+                "Answer to criterion {} needs either a label or a score",
+                answer.criterion
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let expected_output = message
+        .expected_output
+        .as_deref()
+        .map(serde_json::from_str::<Value>)
+        .transpose()
+        .map_err(|_| Status::invalid_argument("Invalid JSON in expected output"))?;
+
+    let mut conn = establish_connection();
+    let queue = find_queue(&mut conn, &message.queue)?;
+    let criteria = load_rubric(&queue)?;
+    let stored_answers = rubric::validate_answers(&criteria, answers)
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+    let submission = conn
+        .transaction(|conn| {
+            let Some(item) = annotation_item::table
+                .filter(annotation_item::id.eq(message.item_id))
+                .filter(annotation_item::annotation_queue_id.eq(queue.id))
+                .for_update()
+                .first::<AnnotationItem>(conn)
+                .optional()?
+            else {
+                return Ok(Submission::ItemNotFound);
+            };
+            if item.claimed_by.as_deref() != Some(message.annotator.as_str()) {
+                return Ok(Submission::NotClaimed);
+            }
+
+            DieselRepository::new(conn, annotation::table).create(&InsertableAnnotation {
+                annotation_item_id: item.id,
+                annotator: message.annotator.clone(),
+                answers: Value::Object(stored_answers),
+                expected_output,
+                comment: non_empty(message.comment),
+                created_at: Utc::now(),
+            })?;
+            diesel::update(annotation_item::table.find(item.id))
+                .set((
+                    annotation_item::claimed_by.eq(None::<String>),
+                    annotation_item::claimed_at.eq(None::<DateTime<Utc>>),
+                ))
+                .execute(conn)?;
+            let annotation_count = annotation::table
+                .filter(annotation::annotation_item_id.eq(item.id))
+                .count()
+                .get_result::<i64>(conn)?;
+
+            Ok(Submission::Submitted { annotation_count })
+        })
+        .map_err(|_: diesel::result::Error| Status::internal("Failed to submit annotation"))?;
+
+    match submission {
+        Submission::Submitted { annotation_count } => Ok(Response::new(SubmitAnnotationResponse {
+            annotation_count: annotation_count as u32,
+            complete: annotation_count >= i64::from(queue.annotations_per_item),
+        })),
+        Submission::ItemNotFound => Err(Status::not_found("Annotation item not found")),
+        Submission::NotClaimed => Err(Status::failed_precondition(format!(
+            "Item is not claimed by {}",
+            message.annotator
+        ))),
+    }
+}
+
+/// Agreement between reviewers on every criterion of the rubric of a queue
+pub async fn get_annotation_agreement(
+    request: Request<GetAnnotationAgreementRequest>,
+) -> Result<Response<GetAnnotationAgreementResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let queue = find_queue(&mut conn, &message.queue)?;
+    let criteria = load_rubric(&queue)?;
+    let annotations = DieselRepository::new(&mut conn, annotation::table)
+        .find_by_queue(queue.id)
+        .map_err(|_| Status::internal("Failed to fetch annotations"))?;
+
+    let mut by_item: BTreeMap<i32, Vec<&Map<String, Value>>> = BTreeMap::new();
+    for annotation in &annotations {
+        if let Some(answers) = annotation.answers.as_object() {
+            by_item
+                .entry(annotation.annotation_item_id)
+                .or_default()
+                .push(answers);
+        }
+    }
+    let items: Vec<Vec<&Map<String, Value>>> = by_item.into_values().collect();
+
+    Ok(Response::new(GetAnnotationAgreementResponse {
+        criteria: criteria
+            .iter()
+            .map(|criterion| {
+                let agreement = rubric::agreement(criterion, &items);
+                CriterionAgreement {
+                    criterion: criterion.name.clone(),
+                    item_count: agreement.item_count as u32,
+                    observed_agreement: agreement.observed_agreement,
+                    alpha: agreement.alpha,
+                }
+            })
+            .collect(),
+    }))
+}
+
+/// Add the items of a queue that have all of their annotations to the working set of a dataset
+pub async fn export_annotations(
+    request: Request<ExportAnnotationsRequest>,
+) -> Result<Response<ExportAnnotationsResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let queue = find_queue(&mut conn, &message.queue)?;
+    let annotations = DieselRepository::new(&mut conn, annotation::table)
+        .find_by_queue(queue.id)
+        .map_err(|_| Status::internal("Failed to fetch annotations"))?;
+
+    // Annotation count and most recently submitted expected output of every item
+    let mut by_item: BTreeMap<i32, (i32, Option<Value>)> = BTreeMap::new();
+    for annotation in annotations {
+        let (count, expected_output) = by_item.entry(annotation.annotation_item_id).or_default();
+        *count += 1;
+        if annotation.expected_output.is_some() {
+            *expected_output = annotation.expected_output;
+        }
+    }
+    let complete: Vec<i32> = by_item
+        .iter()
+        .filter(|(_, (count, _))| *count >= queue.annotations_per_item)
+        .map(|(&item_id, _)| item_id)
+        .collect();
+
+    let inputs: Vec<(i32, i32, Option<Value>)> = annotation_item::table
+        .inner_join(span::table)
+        .filter(annotation_item::id.eq_any(&complete))
+        .select((annotation_item::id, span::id, span::input))
+        .load(&mut conn)
+        .map_err(|_| Status::internal("Failed to fetch annotated spans"))?;
+
+    let now = Utc::now();
+    let mut cases = BTreeMap::new();
+    let mut skipped_count = 0;
+    for (item_id, span_id, input) in inputs {
+        let expected_output = by_item.remove(&item_id).and_then(|(_, output)| output);
+        if input.is_none() && expected_output.is_none() {
+            skipped_count += 1;
+            continue;
+        }
+        let eval_hash = case_hash(input.as_ref(), expected_output.as_ref());
+        cases.entry(eval_hash.clone()).or_insert_with(|| {
+            let case = InsertableEvalCase {
+                eval_hash,
+                input,
+                expected_output,
+                created_at: now,
+            };
+            (case, Some(span_id))
+        });
+    }
+    let added_count = append_cases(
+        &mut conn,
+        &message.dataset_name,
+        cases.into_values().collect(),
+    )?;
+
+    Ok(Response::new(ExportAnnotationsResponse {
+        added_count: added_count as u32,
+        skipped_count,
+    }))
+}
+
+fn find_queue(conn: &mut PgConnection, name: &str) -> Result<AnnotationQueue, Status> {
+    DieselRepository::new(conn, annotation_queue::table)
+        .find_by_name(name)
+        .map_err(|_| Status::internal("Failed to fetch annotation queue"))?
+        .ok_or_else(|| Status::not_found("Annotation queue not found"))
+}
+
+fn load_rubric(queue: &AnnotationQueue) -> Result<Vec<Criterion>, Status> {
+    serde_json::from_value(queue.rubric.clone())
+        .map_err(|_| Status::internal("Failed to load rubric of annotation queue"))
+}
+
+fn convert_criterion(criterion: RubricCriterion) -> Criterion {
+    Criterion {
+        name: criterion.name,
+        description: non_empty(criterion.description),
+        labels: criterion.labels,
+        min_score: criterion.min_score,
+        max_score: criterion.max_score,
+    }
+}
+
+fn convert_queue(queue: AnnotationQueue) -> Result<QueueMessage, Status> {
+    let criteria = load_rubric(&queue)?;
+
+    Ok(QueueMessage {
+        name: queue.name,
+        description: queue.description,
+        rubric: criteria
+            .into_iter()
+            .map(|criterion| RubricCriterion {
+                name: criterion.name,
+                description: criterion.description,
+                labels: criterion.labels,
+                min_score: criterion.min_score,
+                max_score: criterion.max_score,
+            })
+            .collect(),
+        annotations_per_item: queue.annotations_per_item as u32,
+        created_at: Some(to_timestamp(queue.created_at)),
+    })
+}
+
+fn convert_item(id: i32, span: &Span, claimed_at: DateTime<Utc>) -> ItemMessage {
+    ItemMessage {
+        id,
+        span_id: span
+            .external_uuid
+            .map(|uuid| uuid.to_string())
+            .unwrap_or_default(),
+        operation_name: span.operation_name.clone(),
+        attributes: span.attributes.to_string(),
+        input: span.input.as_ref().map(Value::to_string),
+        output: span.output.as_ref().map(Value::to_string),
+        claimed_at: Some(to_timestamp(claimed_at)),
+    }
+}
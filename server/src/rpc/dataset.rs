@@ -0,0 +1,477 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use sha2::{Digest, Sha256};
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        dataset::{Dataset, InsertableDataset},
+        dataset_case::InsertableDatasetCase,
+        dataset_version::{DatasetVersion, InsertableDatasetVersion},
+        dataset_version_case::InsertableDatasetVersionCase,
+        eval_case::InsertableEvalCase,
+        repository::{DieselRepository, Repository},
+        span::Span,
+    },
+    schema::{dataset, dataset_case, dataset_version, dataset_version_case, eval_case, span},
+};
+use ellmo_proto::ellmo::{
+    AddDatasetCasesFromSpansRequest, AddDatasetCasesFromSpansResponse, AddDatasetCasesRequest,
+    AddDatasetCasesResponse, CreateDatasetRequest, CreateDatasetResponse, DatasetCase,
+    DatasetReference, ListDatasetCasesRequest, ListDatasetCasesResponse, RemoveDatasetCasesRequest,
+    RemoveDatasetCasesResponse, SnapshotDatasetRequest, SnapshotDatasetResponse,
+};
+
+use super::eval::cases::{case_hash, parse_payload};
+use super::eval::metadata::non_empty;
+use super::span::select_spans;
+use super::to_timestamp;
+
+type CaseRow = (
+    String,
+    Option<serde_json::Value>,
+    Option<serde_json::Value>,
+    Option<uuid::Uuid>,
+);
+
+/// Attribute holding the input of spans that captured none, by default
+const DEFAULT_INPUT_ATTRIBUTE: &str = "input";
+/// Attribute holding the output of spans that captured none, by default
+const DEFAULT_OUTPUT_ATTRIBUTE: &str = "output";
+
+/// Create an empty dataset
+pub async fn create_dataset(
+    request: Request<CreateDatasetRequest>,
+) -> Result<Response<CreateDatasetResponse>, Status> {
+    let message = request.into_inner();
+    if message.name.is_empty() {
+        return Err(Status::invalid_argument("Missing dataset name"));
+    }
+
+    let mut conn = establish_connection();
+    let created = diesel::insert_into(dataset::table)
+        .values(&InsertableDataset {
+            name: message.name,
+            description: message.description,
+            created_at: Utc::now(),
+        })
+        .on_conflict(dataset::name)
+        .do_nothing()
+        .returning(dataset::all_columns)
+        .get_result::<Dataset>(&mut conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to create dataset"))?
+        .ok_or_else(|| Status::already_exists("Dataset already exists"))?;
+
+    Ok(Response::new(CreateDatasetResponse {
+        dataset: Some(ellmo_proto::ellmo::Dataset {
+            id: created.id,
+            name: created.name,
+            description: created.description,
+            created_at: Some(to_timestamp(created.created_at)),
+            latest_version: None,
+        }),
+    }))
+}
+
+/// Add cases to the working set of a dataset
+pub async fn add_dataset_cases(
+    request: Request<AddDatasetCasesRequest>,
+) -> Result<Response<AddDatasetCasesResponse>, Status> {
+    let message = request.into_inner();
+
+    let now = Utc::now();
+    let mut cases = Vec::with_capacity(message.cases.len());
+    for case in message.cases {
+        if case.eval_hash.is_empty() {
+            return Err(Status::invalid_argument("Missing eval hash"));
+        }
+        let eval_case = InsertableEvalCase {
+            input: parse_payload(case.input.as_deref(), "input", &case.eval_hash)?,
+            expected_output: parse_payload(
+                case.expected_output.as_deref(),
+                "expected output",
+                &case.eval_hash,
+            )?,
+            eval_hash: case.eval_hash,
+            created_at: now,
+        };
+        cases.push((eval_case, None));
+    }
+
+    let mut conn = establish_connection();
+    let added_count = append_cases(&mut conn, &message.dataset_name, cases)?;
+
+    Ok(Response::new(AddDatasetCasesResponse {
+        added_count: added_count as u32,
+    }))
+}
+
+/// Add cases to the working set of a dataset, each with the span it was built from if any,
+/// returning the number of cases that were not part of it yet
+pub fn append_cases(
+    conn: &mut PgConnection,
+    dataset_name: &str,
+    cases: Vec<(InsertableEvalCase, Option<i32>)>,
+) -> Result<usize, Status> {
+    let dataset = find_dataset(conn, dataset_name)?;
+
+    let now = Utc::now();
+    let dataset_cases: Vec<InsertableDatasetCase> = cases
+        .iter()
+        .map(|(case, span_id)| InsertableDatasetCase {
+            dataset_id: dataset.id,
+            eval_hash: case.eval_hash.clone(),
+            created_at: now,
+            span_id: *span_id,
+        })
+        .collect();
+    let cases: Vec<InsertableEvalCase> = cases.into_iter().map(|(case, _)| case).collect();
+
+    conn.transaction(|conn| {
+        DieselRepository::new(conn, eval_case::table).create_missing(&cases)?;
+        DieselRepository::new(conn, dataset_case::table).create_missing(&dataset_cases)
+    })
+    .map_err(|_: diesel::result::Error| Status::internal("Failed to add dataset cases"))
+}
+
+/// Turn spans into cases of the working set of a dataset, e.g. to keep a bad production answer
+/// as a regression case. Each case records the span it was built from. Outputs only become
+/// expected outputs on request, since the answers of such spans are usually the ones to fix.
+pub async fn add_dataset_cases_from_spans(
+    request: Request<AddDatasetCasesFromSpansRequest>,
+) -> Result<Response<AddDatasetCasesFromSpansResponse>, Status> {
+    let message = request.into_inner();
+    let input_attribute =
+        non_empty(message.input_attribute).unwrap_or_else(|| DEFAULT_INPUT_ATTRIBUTE.to_string());
+    let output_attribute = message.output_as_expected.then(|| {
+        non_empty(message.output_attribute).unwrap_or_else(|| DEFAULT_OUTPUT_ATTRIBUTE.to_string())
+    });
+
+    let mut conn = establish_connection();
+    // Fail on an unknown dataset before searching spans
+    find_dataset(&mut conn, &message.dataset_name)?;
+    let spans = select_spans(&mut conn, &message.span_ids, message.filter.as_ref())?;
+
+    let now = Utc::now();
+    let mut cases = Vec::with_capacity(spans.len());
+    let mut skipped_span_ids = Vec::new();
+    for span in &spans {
+        match span_case(span, &input_attribute, output_attribute.as_deref(), now) {
+            Some(case) => cases.push((case, Some(span.id))),
+            None => skipped_span_ids.push(match span.external_uuid {
+                Some(uuid) => uuid.to_string(),
+                None => span.id.to_string(),
+            }),
+        }
+    }
+    let added_count = append_cases(&mut conn, &message.dataset_name, cases)?;
+
+    Ok(Response::new(AddDatasetCasesFromSpansResponse {
+        added_count: added_count as u32,
+        skipped_span_ids,
+    }))
+}
+
+/// Case of the captured input and output of a span, falling back to the given attributes. Spans
+/// without an input make no case. The expected output is left empty without an output attribute.
+fn span_case(
+    span: &Span,
+    input_attribute: &str,
+    output_attribute: Option<&str>,
+    now: DateTime<Utc>,
+) -> Option<InsertableEvalCase> {
+    let input = span
+        .input
+        .clone()
+        .or_else(|| span.attributes.get(input_attribute).cloned())?;
+    let expected_output = output_attribute.and_then(|attribute| {
+        span.output
+            .clone()
+            .or_else(|| span.attributes.get(attribute).cloned())
+    });
+
+    Some(InsertableEvalCase {
+        eval_hash: case_hash(Some(&input), expected_output.as_ref()),
+        input: Some(input),
+        expected_output,
+        created_at: now,
+    })
+}
+
+/// Remove cases from the working set of a dataset. Snapshotted versions are not affected.
+pub async fn remove_dataset_cases(
+    request: Request<RemoveDatasetCasesRequest>,
+) -> Result<Response<RemoveDatasetCasesResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let dataset = find_dataset(&mut conn, &message.dataset_name)?;
+
+    let removed_count = DieselRepository::new(&mut conn, dataset_case::table)
+        .delete_by_hashes(dataset.id, &message.eval_hashes)
+        .map_err(|_| Status::internal("Failed to remove dataset cases"))?;
+
+    Ok(Response::new(RemoveDatasetCasesResponse {
+        removed_count: removed_count as u32,
+    }))
+}
+
+/// Snapshot the working set of a dataset into a new immutable version
+pub async fn snapshot_dataset(
+    request: Request<SnapshotDatasetRequest>,
+) -> Result<Response<SnapshotDatasetResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let snapshot = conn
+        .transaction(|conn| snapshot_working_set(conn, &message.dataset_name))
+        .map_err(|_| Status::internal("Failed to snapshot dataset"))?;
+    let (version, created) = match snapshot {
+        Snapshot::Created(version) => (version, true),
+        Snapshot::Unchanged(version) => (version, false),
+        Snapshot::DatasetNotFound => return Err(Status::not_found("Dataset not found")),
+        Snapshot::Empty => return Err(Status::failed_precondition("Dataset has no cases")),
+    };
+
+    Ok(Response::new(SnapshotDatasetResponse {
+        version: Some(ellmo_proto::ellmo::DatasetVersion {
+            dataset_name: message.dataset_name,
+            version: version.version as u32,
+            case_count: version.case_count as u32,
+            created_at: Some(to_timestamp(version.created_at)),
+        }),
+        created,
+    }))
+}
+
+enum Snapshot {
+    Created(DatasetVersion),
+    /// The working set matches the latest version
+    Unchanged(DatasetVersion),
+    DatasetNotFound,
+    Empty,
+}
+
+fn snapshot_working_set(conn: &mut PgConnection, dataset_name: &str) -> QueryResult<Snapshot> {
+    // Lock the dataset so concurrent snapshots get consecutive version numbers
+    let Some(dataset) = dataset::table
+        .filter(dataset::name.eq(dataset_name))
+        .for_update()
+        .first::<Dataset>(conn)
+        .optional()?
+    else {
+        return Ok(Snapshot::DatasetNotFound);
+    };
+
+    let provenance =
+        DieselRepository::new(conn, dataset_case::table).find_provenance(dataset.id)?;
+    let eval_hashes: Vec<String> = provenance
+        .iter()
+        .map(|(eval_hash, _)| eval_hash.clone())
+        .collect();
+    if eval_hashes.is_empty() {
+        return Ok(Snapshot::Empty);
+    }
+
+    let content_hash = hash_case_set(&eval_hashes);
+    let next_version =
+        match DieselRepository::new(conn, dataset_version::table).find_latest(dataset.id)? {
+            Some(latest) if latest.content_hash == content_hash => {
+                return Ok(Snapshot::Unchanged(latest))
+            }
+            Some(latest) => latest.version + 1,
+            None => 1,
+        };
+
+    let version =
+        DieselRepository::new(conn, dataset_version::table).create(&InsertableDatasetVersion {
+            dataset_id: dataset.id,
+            version: next_version,
+            case_count: eval_hashes.len() as i32,
+            content_hash,
+            created_at: Utc::now(),
+        })?;
+
+    let version_cases: Vec<InsertableDatasetVersionCase> = provenance
+        .into_iter()
+        .map(|(eval_hash, span_id)| InsertableDatasetVersionCase {
+            dataset_version_id: version.id,
+            eval_hash,
+            span_id,
+        })
+        .collect();
+    DieselRepository::new(conn, dataset_version_case::table).create_many(&version_cases)?;
+
+    Ok(Snapshot::Created(version))
+}
+
+/// List the cases of a dataset version, or of the working set if no version is given
+pub async fn list_dataset_cases(
+    request: Request<ListDatasetCasesRequest>,
+) -> Result<Response<ListDatasetCasesResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let rows = match message.version {
+        Some(version) => {
+            let version = find_version(
+                &mut conn,
+                &DatasetReference {
+                    name: message.dataset_name,
+                    version,
+                },
+            )?;
+
+            dataset_version_case::table
+                .inner_join(eval_case::table)
+                .left_join(span::table)
+                .filter(dataset_version_case::dataset_version_id.eq(version.id))
+                .order(eval_case::eval_hash.asc())
+                .select((
+                    eval_case::eval_hash,
+                    eval_case::input,
+                    eval_case::expected_output,
+                    span::external_uuid.nullable(),
+                ))
+                .load::<CaseRow>(&mut conn)
+        }
+        None => {
+            let dataset = find_dataset(&mut conn, &message.dataset_name)?;
+
+            dataset_case::table
+                .inner_join(eval_case::table)
+                .left_join(span::table)
+                .filter(dataset_case::dataset_id.eq(dataset.id))
+                .order(eval_case::eval_hash.asc())
+                .select((
+                    eval_case::eval_hash,
+                    eval_case::input,
+                    eval_case::expected_output,
+                    span::external_uuid.nullable(),
+                ))
+                .load::<CaseRow>(&mut conn)
+        }
+    }
+    .map_err(|_| Status::internal("Failed to fetch dataset cases"))?;
+
+    Ok(Response::new(ListDatasetCasesResponse {
+        cases: rows
+            .into_iter()
+            .map(
+                |(eval_hash, input, expected_output, span_uuid)| DatasetCase {
+                    eval_hash,
+                    input: input.map(|input| input.to_string()),
+                    expected_output: expected_output.map(|output| output.to_string()),
+                    span_id: span_uuid.map(|uuid| uuid.to_string()),
+                },
+            )
+            .collect(),
+    }))
+}
+
+/// Resolve a reference to a snapshotted dataset version
+pub fn find_version(
+    conn: &mut PgConnection,
+    reference: &DatasetReference,
+) -> Result<DatasetVersion, Status> {
+    let dataset = find_dataset(conn, &reference.name)?;
+
+    DieselRepository::new(conn, dataset_version::table)
+        .find_by_version(dataset.id, reference.version as i32)
+        .map_err(|_| Status::internal("Failed to fetch dataset version"))?
+        .ok_or_else(|| Status::not_found("Dataset version not found"))
+}
+
+/// Hashes of the cases in a dataset version, in sorted order
+pub fn find_version_hashes(
+    conn: &mut PgConnection,
+    version: &DatasetVersion,
+) -> Result<Vec<String>, Status> {
+    DieselRepository::new(conn, dataset_version_case::table)
+        .find_hashes(version.id)
+        .map_err(|_| Status::internal("Failed to fetch dataset cases"))
+}
+
+fn find_dataset(conn: &mut PgConnection, name: &str) -> Result<Dataset, Status> {
+    if name.is_empty() {
+        return Err(Status::invalid_argument("Missing dataset name"));
+    }
+
+    DieselRepository::new(conn, dataset::table)
+        .find_by_name(name)
+        .map_err(|_| Status::internal("Failed to fetch dataset"))?
+        .ok_or_else(|| Status::not_found("Dataset not found"))
+}
+
+/// Identity of a set of cases, used to avoid snapshotting an unchanged working set
+fn hash_case_set(sorted_hashes: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    for eval_hash in sorted_hashes {
+        hasher.update(eval_hash.as_bytes());
+        hasher.update(b"\n");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::span::sample_span;
+    use serde_json::json;
+
+    #[test]
+    fn test_hash_case_set_separates_hashes() {
+        let joined = hash_case_set(&["ab".to_string(), "c".to_string()]);
+        let split = hash_case_set(&["a".to_string(), "bc".to_string()]);
+
+        assert_ne!(joined, split);
+        assert_eq!(joined, hash_case_set(&["ab".to_string(), "c".to_string()]));
+    }
+
+    fn span(
+        input: Option<serde_json::Value>,
+        output: Option<serde_json::Value>,
+        attributes: serde_json::Value,
+    ) -> Span {
+        Span {
+            input,
+            output,
+            ..sample_span("llm.call", attributes)
+        }
+    }
+
+    #[test]
+    fn test_span_case_extracts_payloads_and_attributes() {
+        let now = Utc::now();
+        let captured = span(
+            Some(json!({"q": "why?"})),
+            Some(json!("because")),
+            json!({"input": "ignored", "output": "ignored"}),
+        );
+        let case = span_case(&captured, "input", Some("output"), now).unwrap();
+        assert_eq!(case.input, Some(json!({"q": "why?"})));
+        assert_eq!(case.expected_output, Some(json!("because")));
+        assert_eq!(
+            case.eval_hash,
+            case_hash(Some(&json!({"q": "why?"})), Some(&json!("because")))
+        );
+
+        let attributed = span(None, None, json!({"question": "why?", "answer": "because"}));
+        let case = span_case(&attributed, "question", Some("answer"), now).unwrap();
+        assert_eq!(case.input, Some(json!("why?")));
+        assert_eq!(case.expected_output, Some(json!("because")));
+
+        let case = span_case(&captured, "input", None, now).unwrap();
+        assert_eq!(case.expected_output, None);
+        assert_ne!(
+            case.eval_hash,
+            span_case(&captured, "input", Some("output"), now)
+                .unwrap()
+                .eval_hash
+        );
+
+        assert!(span_case(&span(None, Some(json!("x")), json!({})), "input", None, now).is_none());
+    }
+}
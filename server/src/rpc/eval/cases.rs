@@ -0,0 +1,375 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use tonic::Status;
+
+use ellmo_db::{
+    models::{
+        eval_case::InsertableEvalCase, eval_output::InsertableEvalOutput,
+        eval_score::InsertableEvalScore, eval_score::DEFAULT_METRIC, repository::DieselRepository,
+    },
+    schema::{eval_case, eval_output, eval_score},
+};
+use ellmo_proto::ellmo::{EvalScore, MeaningfulEvalScore, Metric};
+
+/// Scores of a run along with the case data and outputs they reference, validated and ready to
+/// be stored
+pub struct PreparedScores {
+    cases: Vec<InsertableEvalCase>,
+    outputs: Vec<InsertableEvalOutput>,
+    trials: Vec<PreparedTrial>,
+}
+
+struct PreparedTrial {
+    eval_hash: String,
+    metrics: Vec<(String, f32)>,
+    output_hash: Option<String>,
+}
+
+impl PreparedScores {
+    /// Parse the JSON payloads of the scores. Cases are keyed by their eval hash and outputs by
+    /// the hash of their content, so each is only stored once.
+    pub fn prepare(eval_scores: Vec<EvalScore>, now: DateTime<Utc>) -> Result<Self, Status> {
+        let mut cases = BTreeMap::new();
+        let mut outputs = BTreeMap::new();
+        let mut trials = Vec::with_capacity(eval_scores.len());
+
+        for score in eval_scores {
+            let metrics = trial_metrics(&score)?;
+            let input = parse_payload(score.input.as_deref(), "input", &score.eval_hash)?;
+            let expected_output = parse_payload(
+                score.expected_output.as_deref(),
+                "expected output",
+                &score.eval_hash,
+            )?;
+            let actual_output = parse_payload(
+                score.actual_output.as_deref(),
+                "actual output",
+                &score.eval_hash,
+            )?;
+
+            if input.is_some() || expected_output.is_some() {
+                cases
+                    .entry(score.eval_hash.clone())
+                    .or_insert_with(|| InsertableEvalCase {
+                        eval_hash: score.eval_hash.clone(),
+                        input,
+                        expected_output,
+                        created_at: now,
+                    });
+            }
+
+            let output_hash = actual_output.map(|content| {
+                let content_hash = content_hash(&content);
+                outputs
+                    .entry(content_hash.clone())
+                    .or_insert_with(|| InsertableEvalOutput {
+                        content_hash: content_hash.clone(),
+                        content,
+                        created_at: now,
+                    });
+                content_hash
+            });
+
+            trials.push(PreparedTrial {
+                eval_hash: score.eval_hash,
+                metrics,
+                output_hash,
+            });
+        }
+
+        Ok(PreparedScores {
+            cases: cases.into_values().collect(),
+            outputs: outputs.into_values().collect(),
+            trials,
+        })
+    }
+
+    /// Number of trials among the scores
+    pub fn trial_count(&self) -> usize {
+        self.trials.len()
+    }
+
+    /// Store the scores under a run, numbering trials from `first_trial_index`. Expected to run
+    /// inside a transaction.
+    pub fn store(
+        self,
+        conn: &mut PgConnection,
+        eval_result_id: i32,
+        first_trial_index: i32,
+    ) -> QueryResult<()> {
+        DieselRepository::new(conn, eval_case::table).create_missing(&self.cases)?;
+        DieselRepository::new(conn, eval_output::table).create_missing(&self.outputs)?;
+
+        let scores: Vec<InsertableEvalScore> = self
+            .trials
+            .into_iter()
+            .enumerate()
+            .flat_map(|(trial_index, trial)| {
+                let eval_hash = trial.eval_hash;
+                let output_hash = trial.output_hash;
+                trial
+                    .metrics
+                    .into_iter()
+                    .map(move |(metric, value)| InsertableEvalScore {
+                        eval_result_id,
+                        eval_hash: eval_hash.clone(),
+                        metric,
+                        value,
+                        output_hash: output_hash.clone(),
+                        trial_index: first_trial_index + trial_index as i32,
+                    })
+            })
+            .collect();
+        DieselRepository::new(conn, eval_score::table).create_many(&scores)?;
+
+        Ok(())
+    }
+}
+
+/// Every trial of a run, with its named metrics
+pub fn load_trials(conn: &mut PgConnection, eval_result_id: i32) -> Result<Vec<EvalScore>, Status> {
+    let rows = DieselRepository::new(conn, eval_score::table)
+        .find_trials(eval_result_id)
+        .map_err(|_| Status::internal("Failed to fetch eval scores"))?;
+
+    let mut trials: Vec<(i32, EvalScore)> = Vec::new();
+    for row in rows {
+        let is_new_trial = match trials.last() {
+            Some((trial_index, _)) => *trial_index != row.trial_index,
+            None => true,
+        };
+        if is_new_trial {
+            trials.push((
+                row.trial_index,
+                EvalScore {
+                    eval_hash: row.eval_hash.clone(),
+                    score: 0.0,
+                    input: None,
+                    expected_output: None,
+                    actual_output: None,
+                    metrics: Vec::new(),
+                },
+            ));
+        }
+
+        if let Some((_, trial)) = trials.last_mut() {
+            if row.metric == DEFAULT_METRIC {
+                trial.score = row.value;
+            }
+            trial.metrics.push(Metric {
+                name: row.metric,
+                value: row.value,
+            });
+        }
+    }
+
+    Ok(trials.into_iter().map(|(_, trial)| trial).collect())
+}
+
+/// Fill in the recorded inputs, expected outputs and outputs of both runs for the given scores
+pub fn attach_case_details(
+    conn: &mut PgConnection,
+    previous_result_id: i32,
+    current_result_id: i32,
+    scores: &mut [MeaningfulEvalScore],
+) -> Result<(), Status> {
+    if scores.is_empty() {
+        return Ok(());
+    }
+
+    let eval_hashes: Vec<String> = scores.iter().map(|score| score.eval_hash.clone()).collect();
+
+    let cases: HashMap<_, _> = DieselRepository::new(conn, eval_case::table)
+        .find_by_hashes(&eval_hashes)
+        .map_err(|_| Status::internal("Failed to fetch eval cases"))?
+        .into_iter()
+        .map(|case| (case.eval_hash.clone(), case))
+        .collect();
+    let previous_outputs = load_outputs(conn, previous_result_id, &eval_hashes)?;
+    let current_outputs = load_outputs(conn, current_result_id, &eval_hashes)?;
+
+    for score in scores.iter_mut() {
+        if let Some(case) = cases.get(&score.eval_hash) {
+            score.input = case.input.as_ref().map(|input| input.to_string());
+            score.expected_output = case
+                .expected_output
+                .as_ref()
+                .map(|output| output.to_string());
+        }
+        score.previous_output = previous_outputs.get(&score.eval_hash).cloned();
+        score.current_output = current_outputs.get(&score.eval_hash).cloned();
+    }
+
+    Ok(())
+}
+
+fn load_outputs(
+    conn: &mut PgConnection,
+    eval_result_id: i32,
+    eval_hashes: &[String],
+) -> Result<HashMap<String, String>, Status> {
+    let outputs = DieselRepository::new(conn, eval_score::table)
+        .find_outputs(eval_result_id, eval_hashes)
+        .map_err(|_| Status::internal("Failed to fetch eval outputs"))?;
+
+    Ok(outputs
+        .into_iter()
+        .map(|(eval_hash, content)| (eval_hash, content.to_string()))
+        .collect())
+}
+
+/// Named metrics of a trial, falling back to the plain score when none are given
+fn trial_metrics(score: &EvalScore) -> Result<Vec<(String, f32)>, Status> {
+    if score.metrics.is_empty() {
+        return Ok(vec![(DEFAULT_METRIC.to_string(), score.score)]);
+    }
+
+    let mut metrics: Vec<(String, f32)> = Vec::with_capacity(score.metrics.len());
+    for metric in &score.metrics {
+        if metric.name.is_empty() {
+            return Err(Status::invalid_argument(format!(
+                "Missing metric name in case {}",
+                score.eval_hash
+            )));
+        }
+        if metrics.iter().any(|(name, _)| name == &metric.name) {
+            return Err(Status::invalid_argument(format!(
+                "Duplicate metric {} in case {}",
+                metric.name, score.eval_hash
+            )));
+        }
+        metrics.push((metric.name.clone(), metric.value));
+    }
+
+    Ok(metrics)
+}
+
+/// Parse an optional JSON-encoded payload of a case
+pub fn parse_payload(
+    payload: Option<&str>,
+    field: &str,
+    eval_hash: &str,
+) -> Result<Option<serde_json::Value>, Status> {
+    payload
+        .map(|payload| {
+            serde_json::from_str(payload).map_err(|_| {
+                Status::invalid_argument(format!("Invalid JSON in {} of case {}", field, eval_hash))
+            })
+        })
+        .transpose()
+}
+
+/// Hash identifying a case by its input and expected output
+pub fn case_hash(
+    input: Option<&serde_json::Value>,
+    expected_output: Option<&serde_json::Value>,
+) -> String {
+    content_hash(&serde_json::json!({ "input": input, "expected_output": expected_output }))
+}
+
+/// SHA-256 of the serialized content. Object keys serialize in sorted order, so outputs that
+/// only differ in key order or whitespace share a hash.
+fn content_hash(content: &serde_json::Value) -> String {
+    format!("{:x}", Sha256::digest(content.to_string().as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(eval_hash: &str, input: Option<&str>, actual_output: Option<&str>) -> EvalScore {
+        EvalScore {
+            eval_hash: eval_hash.to_string(),
+            score: 1.0,
+            input: input.map(str::to_string),
+            expected_output: None,
+            actual_output: actual_output.map(str::to_string),
+            metrics: Vec::new(),
+        }
+    }
+
+    fn metric(name: &str, value: f32) -> Metric {
+        Metric {
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_prepare_deduplicates_cases_and_outputs() {
+        let prepared = PreparedScores::prepare(
+            vec![
+                score("a", Some(r#"{"q": 1}"#), Some(r#"{"x": 1, "y": 2}"#)),
+                score("a", Some(r#"{"q": 1}"#), Some(r#"{ "y": 2, "x": 1 }"#)),
+                score("b", None, Some(r#""other""#)),
+                score("c", None, None),
+            ],
+            Utc::now(),
+        )
+        .unwrap();
+
+        assert_eq!(prepared.cases.len(), 1);
+        assert_eq!(prepared.outputs.len(), 2);
+        assert_eq!(prepared.trials.len(), 4);
+        assert_eq!(
+            prepared.trials[0].output_hash,
+            prepared.trials[1].output_hash
+        );
+        assert_ne!(
+            prepared.trials[0].output_hash,
+            prepared.trials[2].output_hash
+        );
+        assert_eq!(prepared.trials[3].output_hash, None);
+    }
+
+    #[test]
+    fn test_prepare_rejects_invalid_json() {
+        let result = PreparedScores::prepare(vec![score("a", Some("{not json"), None)], Utc::now());
+
+        assert_eq!(
+            result.err().map(|status| status.code()),
+            Some(tonic::Code::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_plain_score_is_stored_as_default_metric() {
+        let prepared = PreparedScores::prepare(vec![score("a", None, None)], Utc::now()).unwrap();
+
+        assert_eq!(
+            prepared.trials[0].metrics,
+            vec![(DEFAULT_METRIC.to_string(), 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_named_metrics_replace_plain_score() {
+        let mut with_metrics = score("a", None, None);
+        with_metrics.metrics = vec![metric("correctness", 0.8), metric("latency", 120.0)];
+
+        let prepared = PreparedScores::prepare(vec![with_metrics], Utc::now()).unwrap();
+
+        assert_eq!(
+            prepared.trials[0].metrics,
+            vec![
+                ("correctness".to_string(), 0.8),
+                ("latency".to_string(), 120.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_metric_is_rejected() {
+        let mut with_metrics = score("a", None, None);
+        with_metrics.metrics = vec![metric("latency", 1.0), metric("latency", 2.0)];
+
+        let result = PreparedScores::prepare(vec![with_metrics], Utc::now());
+
+        assert_eq!(
+            result.err().map(|status| status.code()),
+            Some(tonic::Code::InvalidArgument)
+        );
+    }
+}
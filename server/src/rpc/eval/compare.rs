@@ -0,0 +1,176 @@
+use diesel::prelude::*;
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        eval::Eval,
+        eval_result::{EvalResult, STATUS_FINISHED},
+        prompt_version::PromptVersion,
+    },
+    schema::{eval, eval_result, prompt_version},
+};
+use ellmo_proto::ellmo::{CompareEvalRunsRequest, CompareEvalRunsResponse, VersionedPrompt};
+
+/// What a stored run is compared against
+pub enum RunBase {
+    /// A specific run of the same eval
+    Run(i32),
+    /// The latest run of another version of the same prompt, on the same dataset version
+    Version(String),
+    /// Like `Version`, with the version a label of the prompt points to
+    Label(String),
+}
+
+impl RunBase {
+    pub fn from_parts(
+        base_run_id: Option<i32>,
+        base_version: Option<String>,
+        base_label: Option<String>,
+    ) -> Result<RunBase, Status> {
+        match (base_run_id, base_version, base_label) {
+            (Some(id), None, None) => Ok(RunBase::Run(id)),
+            (None, Some(version), None) => Ok(RunBase::Version(version)),
+            (None, None, Some(label)) => Ok(RunBase::Label(label)),
+            _ => Err(Status::invalid_argument(
+                "Exactly one of base run ID, base version or base label is required",
+            )),
+        }
+    }
+}
+
+/// Compare two stored runs without recording anything
+pub async fn compare_eval_runs(
+    request: Request<CompareEvalRunsRequest>,
+) -> Result<Response<CompareEvalRunsResponse>, Status> {
+    let message = request.into_inner();
+    let base = RunBase::from_parts(
+        message.base_run_id,
+        message.base_version,
+        message.base_label,
+    )?;
+
+    let mut conn = establish_connection();
+    let comparison = compare_runs(&mut conn, message.current_run_id, base)?;
+
+    Ok(Response::new(comparison))
+}
+
+/// Analyze a stored run against a base, the same way `record_eval` does
+pub fn compare_runs(
+    conn: &mut PgConnection,
+    current_run_id: i32,
+    base: RunBase,
+) -> Result<CompareEvalRunsResponse, Status> {
+    let (current_run, eval, prompt_version) = find_run(conn, current_run_id)?;
+    check_finished(&current_run)?;
+
+    let base_run = match base {
+        RunBase::Run(id) => {
+            let (run, base_eval, _) = find_run(conn, id)?;
+            check_finished(&run)?;
+            if base_eval.name != eval.name {
+                return Err(Status::invalid_argument("Runs belong to different evals"));
+            }
+            run
+        }
+        RunBase::Version(version) => {
+            let prompt = VersionedPrompt {
+                name: prompt_version.name.clone(),
+                version: prompt_version.version.clone(),
+            };
+            let base_version =
+                super::get_base_prompt_version(conn, &prompt, &prompt_version, Some(version))?
+                    .ok_or_else(|| Status::invalid_argument("Base version not found"))?;
+
+            find_base_run(conn, &eval, &base_version, &current_run)?
+        }
+        RunBase::Label(label) => {
+            let base_version =
+                super::label::find_labeled_version(conn, &prompt_version.name, &label)?
+                    .ok_or_else(|| Status::invalid_argument("Base label not found"))?;
+
+            find_base_run(conn, &eval, &base_version, &current_run)?
+        }
+    };
+
+    let metric_outcomes = super::compare_metrics(conn, &eval.name, base_run.id, current_run.id)?;
+    let outcome = super::overall_outcome(metric_outcomes.iter().map(|metric| metric.outcome()));
+    let (meaningful_scores, statistics) = super::default_metric_analysis(&metric_outcomes);
+
+    Ok(CompareEvalRunsResponse {
+        outcome: outcome.into(),
+        meaningful_eval_scores: meaningful_scores,
+        statistics,
+        metric_outcomes,
+        base_run_id: base_run.id,
+        current_run_id: current_run.id,
+        dataset_drift: base_run.dataset_version_id != current_run.dataset_version_id,
+    })
+}
+
+fn find_base_run(
+    conn: &mut PgConnection,
+    eval: &Eval,
+    base_version: &PromptVersion,
+    current_run: &EvalResult,
+) -> Result<EvalResult, Status> {
+    super::get_previous_eval_result(
+        conn,
+        &eval.name,
+        base_version,
+        current_run.dataset_version_id,
+        None,
+    )?
+    .ok_or_else(|| Status::not_found("Base version has no runs on the same dataset version"))
+}
+
+pub fn find_run(
+    conn: &mut PgConnection,
+    id: i32,
+) -> Result<(EvalResult, Eval, PromptVersion), Status> {
+    eval_result::table
+        .inner_join(eval::table.inner_join(prompt_version::table))
+        .filter(eval_result::id.eq(id))
+        .select((
+            EvalResult::as_select(),
+            Eval::as_select(),
+            PromptVersion::as_select(),
+        ))
+        .first::<(EvalResult, Eval, PromptVersion)>(conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to fetch eval run"))?
+        .ok_or_else(|| Status::not_found("Eval run not found"))
+}
+
+/// Open runs are still missing scores, so comparing them would be misleading
+fn check_finished(run: &EvalResult) -> Result<(), Status> {
+    if run.status != STATUS_FINISHED {
+        return Err(Status::failed_precondition("Eval run is not finished"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exactly_one_base_is_required() {
+        assert!(matches!(
+            RunBase::from_parts(Some(1), None, None),
+            Ok(RunBase::Run(1))
+        ));
+        assert!(matches!(
+            RunBase::from_parts(None, Some("1.0.0".to_string()), None),
+            Ok(RunBase::Version(_))
+        ));
+        assert!(matches!(
+            RunBase::from_parts(None, None, Some("production".to_string())),
+            Ok(RunBase::Label(_))
+        ));
+        assert!(RunBase::from_parts(None, None, None).is_err());
+        assert!(RunBase::from_parts(Some(1), Some("1.0.0".to_string()), None).is_err());
+    }
+}
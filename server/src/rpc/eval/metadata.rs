@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use tonic::Status;
+
+use ellmo_proto::ellmo::RunMetadata;
+
+const MAX_ATTRIBUTES: usize = 64;
+
+/// Metadata of a run, validated and ready to be stored on its eval result
+#[derive(Debug, Default, PartialEq)]
+pub struct PreparedMetadata {
+    pub git_sha: Option<String>,
+    pub branch: Option<String>,
+    pub ci_run_url: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub attributes: serde_json::Value,
+}
+
+impl PreparedMetadata {
+    /// Validate the metadata of a run. Empty strings are treated as missing.
+    pub fn prepare(metadata: Option<RunMetadata>) -> Result<Self, Status> {
+        let metadata = metadata.unwrap_or_default();
+
+        if matches!(metadata.temperature, Some(t) if !t.is_finite() || t < 0.0) {
+            return Err(Status::invalid_argument(
+                "Temperature must be a non-negative number",
+            ));
+        }
+        if metadata.attributes.len() > MAX_ATTRIBUTES {
+            return Err(Status::invalid_argument(format!(
+                "Runs may have at most {} attributes",
+                MAX_ATTRIBUTES
+            )));
+        }
+        if metadata.attributes.keys().any(|key| key.is_empty()) {
+            return Err(Status::invalid_argument("Missing attribute name"));
+        }
+
+        // Sorted so the stored object doesn't depend on the order the map was sent in
+        let attributes: BTreeMap<String, String> = metadata.attributes.into_iter().collect();
+
+        Ok(PreparedMetadata {
+            git_sha: non_empty(metadata.git_sha),
+            branch: non_empty(metadata.branch),
+            ci_run_url: non_empty(metadata.ci_run_url),
+            model: non_empty(metadata.model),
+            temperature: metadata.temperature,
+            attributes: serde_json::json!(attributes),
+        })
+    }
+}
+
+pub fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_normalizes_metadata() {
+        let prepared = PreparedMetadata::prepare(Some(RunMetadata {
+            git_sha: Some("abc123".to_string()),
+            branch: Some(String::new()),
+            temperature: Some(0.7),
+            attributes: [("top_p".to_string(), "0.9".to_string())].into(),
+            ..Default::default()
+        }))
+        .unwrap();
+
+        assert_eq!(prepared.git_sha.as_deref(), Some("abc123"));
+        assert_eq!(prepared.branch, None);
+        assert_eq!(prepared.attributes, serde_json::json!({"top_p": "0.9"}));
+        assert_eq!(
+            PreparedMetadata::prepare(None).unwrap().attributes,
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn test_prepare_rejects_invalid_metadata() {
+        let negative_temperature = RunMetadata {
+            temperature: Some(-1.0),
+            ..Default::default()
+        };
+        let unnamed_attribute = RunMetadata {
+            attributes: [(String::new(), "x".to_string())].into(),
+            ..Default::default()
+        };
+
+        assert!(PreparedMetadata::prepare(Some(negative_temperature)).is_err());
+        assert!(PreparedMetadata::prepare(Some(unnamed_attribute)).is_err());
+    }
+}
@@ -0,0 +1,1097 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        eval::{Eval, InsertableEval},
+        eval_result::{EvalResult, InsertableEvalResult, STATUS_FINISHED, STATUS_OPEN},
+        eval_score::DEFAULT_METRIC,
+        prompt_version::{InsertablePromptVersion, PromptVersion},
+        repository::{DieselRepository, Repository},
+    },
+    schema::{eval, eval_result, eval_score, prompt_version},
+};
+use ellmo_proto::ellmo::{
+    EvalOutcome, MeaningfulEvalScore, MetricOutcome, RecordEvalRequest, RecordEvalResponse,
+    StatisticalSummary, VersionedPrompt,
+};
+
+use super::dataset;
+use super::gate;
+use super::label;
+use super::policy::{self, ComparisonPolicy};
+use super::prompt;
+use crate::scorer::ScorerSpec;
+use crate::stats;
+use crate::version::parse_semver;
+use crate::webhook;
+use cases::PreparedScores;
+use metadata::PreparedMetadata;
+use scoring::RunScorers;
+
+pub mod cases;
+pub mod compare;
+pub mod metadata;
+pub mod scoring;
+pub mod stream;
+
+/// Confidence level of the interval reported on the mean delta
+const CONFIDENCE_LEVEL: f64 = 0.95;
+const BOOTSTRAP_ITERATIONS: usize = 2000;
+
+/// Trials of every case in a run, keyed by eval hash
+type CaseSummaries = BTreeMap<String, stats::Summary>;
+
+/// Outcome of comparing two eval runs
+struct Comparison {
+    outcome: EvalOutcome,
+    meaningful_scores: Vec<MeaningfulEvalScore>,
+    statistics: Option<StatisticalSummary>,
+}
+
+/// Record an eval run and compare it to a previous run
+pub async fn record_eval(
+    request: Request<RecordEvalRequest>,
+) -> Result<Response<RecordEvalResponse>, Status> {
+    let message = request.into_inner();
+
+    let eval = message
+        .eval
+        .ok_or_else(|| Status::invalid_argument("Missing eval"))?;
+    let prompt = message
+        .prompt
+        .ok_or_else(|| Status::invalid_argument("Missing prompt"))?;
+
+    let base_request = BaseRequest::new(
+        message.base_version,
+        message.base_label,
+        message.base_branch,
+    )?;
+    let run_metadata = PreparedMetadata::prepare(message.metadata)?;
+    let mut eval_scores = message.eval_scores;
+    let scored_hashes: BTreeSet<String> = eval_scores
+        .iter()
+        .map(|score| score.eval_hash.clone())
+        .collect();
+
+    let mut conn = establish_connection();
+
+    let scorers = scoring::prepare_scorers(&mut conn, message.scorers)?;
+    RunScorers::build(&mut conn, &scorers)?.apply(&mut conn, &mut eval_scores)?;
+    let prepared_scores = PreparedScores::prepare(eval_scores, Utc::now())?;
+
+    // Cases of a dataset run must belong to the referenced version
+    let dataset_version = match &message.dataset {
+        Some(reference) => Some(dataset::find_version(&mut conn, reference)?),
+        None => None,
+    };
+    let missing_case_count = match &dataset_version {
+        Some(version) => {
+            let dataset_hashes: BTreeSet<String> =
+                dataset::find_version_hashes(&mut conn, version)?
+                    .into_iter()
+                    .collect();
+            check_dataset_cases(&scored_hashes, &dataset_hashes)?;
+            dataset_hashes.difference(&scored_hashes).count() as u32
+        }
+        None => 0,
+    };
+    let dataset_version_id = dataset_version.map(|version| version.id);
+
+    // Get or create the prompt version being evaluated
+    let prompt_version = get_or_create_prompt_version(&mut conn, &prompt)?;
+    let existing_eval_version = get_or_create_eval_version(&mut conn, &eval, &prompt_version)?;
+
+    let base = resolve_base(
+        &mut conn,
+        &eval.name,
+        &prompt,
+        &prompt_version,
+        &base_request,
+        dataset_version_id,
+    )?;
+
+    let now = Utc::now();
+    let eval_result = conn
+        .transaction(|conn| {
+            let eval_result =
+                DieselRepository::new(conn, eval_result::table).create(&new_eval_result(
+                    &existing_eval_version,
+                    dataset_version_id,
+                    run_metadata,
+                    &base_request,
+                    &scorers,
+                    None,
+                    Some(now),
+                ))?;
+            prepared_scores.store(conn, eval_result.id, 0)?;
+
+            Ok(eval_result)
+        })
+        .map_err(|_: diesel::result::Error| Status::internal("Failed to create eval result"))?;
+
+    let response = analyze_run(
+        &mut conn,
+        &eval.name,
+        &prompt,
+        &eval_result,
+        base,
+        missing_case_count,
+    )?;
+
+    Ok(Response::new(response))
+}
+
+/// Base a run asked to be compared against
+struct BaseRequest {
+    version: Option<String>,
+    label: Option<String>,
+    branch: Option<String>,
+}
+
+impl BaseRequest {
+    fn new(
+        version: Option<String>,
+        label: Option<String>,
+        branch: Option<String>,
+    ) -> Result<Self, Status> {
+        if version.is_some() && label.is_some() {
+            return Err(Status::invalid_argument(
+                "Only one of base version or base label may be given",
+            ));
+        }
+
+        Ok(BaseRequest {
+            version,
+            label,
+            branch: metadata::non_empty(branch),
+        })
+    }
+}
+
+/// Run a run is compared against, resolved from its `BaseRequest`
+struct ResolvedBase {
+    version: Option<String>,
+    previous_result: Option<EvalResult>,
+    /// The base version did run, just not on the same cases, so the runs are not comparable
+    dataset_drift: bool,
+    branch_requested: bool,
+}
+
+/// Find the finished run of the base that the current run is compared against
+fn resolve_base(
+    conn: &mut PgConnection,
+    eval_name: &str,
+    prompt: &VersionedPrompt,
+    prompt_version: &PromptVersion,
+    request: &BaseRequest,
+    dataset_version_id: Option<i32>,
+) -> Result<ResolvedBase, Status> {
+    let explicit_base = request.version.is_some() || request.label.is_some();
+    let (base_prompt_version, previous_result) = match &request.branch {
+        Some(branch) if !explicit_base => {
+            match get_latest_branch_result(
+                conn,
+                eval_name,
+                &prompt.name,
+                branch,
+                dataset_version_id,
+            )? {
+                Some((result, version)) => (Some(version), Some(result)),
+                None => (None, None),
+            }
+        }
+        _ => {
+            let base_prompt_version = match &request.label {
+                Some(base_label) => Some(
+                    label::find_labeled_version(conn, &prompt.name, base_label)?
+                        .ok_or_else(|| Status::invalid_argument("Base label not found"))?,
+                ),
+                None => {
+                    get_base_prompt_version(conn, prompt, prompt_version, request.version.clone())?
+                }
+            };
+            let previous_result = match &base_prompt_version {
+                Some(base) => get_previous_eval_result(
+                    conn,
+                    eval_name,
+                    base,
+                    dataset_version_id,
+                    request.branch.as_deref(),
+                )?,
+                None => None,
+            };
+            (base_prompt_version, previous_result)
+        }
+    };
+    let dataset_drift = match (&previous_result, &base_prompt_version) {
+        (None, Some(base)) => has_eval_result(conn, eval_name, base)?,
+        _ => false,
+    };
+
+    Ok(ResolvedBase {
+        version: base_prompt_version.map(|base| base.version),
+        previous_result,
+        dataset_drift,
+        branch_requested: request.branch.is_some(),
+    })
+}
+
+/// Compare a finished run against its base, evaluate the gate of the prompt and notify
+/// subscribed webhooks
+fn analyze_run(
+    conn: &mut PgConnection,
+    eval_name: &str,
+    prompt: &VersionedPrompt,
+    eval_result: &EvalResult,
+    base: ResolvedBase,
+    missing_case_count: u32,
+) -> Result<RecordEvalResponse, Status> {
+    let prompt_gate = gate::load_gate(conn, &prompt.name)?;
+
+    let Some(previous_result) = base.previous_result else {
+        let message = if base.dataset_drift {
+            "Base version has no runs on the same dataset version"
+        } else if base.branch_requested {
+            "Base branch has no runs to compare against"
+        } else {
+            "Success"
+        };
+
+        return Ok(RecordEvalResponse {
+            outcome: EvalOutcome::NoChange.into(),
+            previous_eval_scores: Vec::new(),
+            meaningful_eval_scores: Vec::new(),
+            message: message.to_string(),
+            statistics: None,
+            base_version: base.version,
+            eval_run_id: eval_result.id,
+            dataset_drift: base.dataset_drift,
+            missing_case_count,
+            metric_outcomes: Vec::new(),
+            gate: Some(prompt_gate.evaluate(EvalOutcome::NoChange, &[])),
+            prompt_diff: None,
+        });
+    };
+
+    let metric_outcomes = compare_metrics(conn, eval_name, previous_result.id, eval_result.id)?;
+    let outcome = overall_outcome(metric_outcomes.iter().map(|metric| metric.outcome()));
+    let (meaningful_scores, statistics) = default_metric_analysis(&metric_outcomes);
+    let verdict = prompt_gate.evaluate(outcome, &metric_outcomes);
+
+    // Failing to notify must not lose the recorded run
+    let notice = webhook::RunNotice {
+        eval_name,
+        prompt,
+        base_version: base.version.as_deref(),
+        eval_run_id: eval_result.id,
+        outcome,
+        metric_outcomes: &metric_outcomes,
+        gate: &verdict,
+        branch: eval_result.branch.as_deref(),
+        git_sha: eval_result.git_sha.as_deref(),
+        ci_run_url: eval_result.ci_run_url.as_deref(),
+    };
+    if let Err(e) = webhook::notify_run(conn, &notice) {
+        println!("Failed to queue webhook deliveries: {}", e);
+    }
+
+    let previous_scores = cases::load_trials(conn, previous_result.id)?;
+    let prompt_diff = match &base.version {
+        Some(base_version) => {
+            prompt::summarize_changes(conn, &prompt.name, base_version, &prompt.version)?
+        }
+        None => None,
+    };
+
+    Ok(RecordEvalResponse {
+        outcome: outcome.into(),
+        previous_eval_scores: previous_scores,
+        meaningful_eval_scores: meaningful_scores,
+        message: "Success".to_string(),
+        statistics,
+        base_version: base.version,
+        eval_run_id: eval_result.id,
+        dataset_drift: base.dataset_drift,
+        missing_case_count,
+        metric_outcomes,
+        gate: Some(verdict),
+        prompt_diff,
+    })
+}
+
+/// Reject scored cases that are not part of the dataset version of the run
+fn check_dataset_cases(
+    scored_hashes: &BTreeSet<String>,
+    dataset_hashes: &BTreeSet<String>,
+) -> Result<(), Status> {
+    let unknown_count = scored_hashes.difference(dataset_hashes).count();
+    if unknown_count > 0 {
+        return Err(Status::invalid_argument(format!(
+            "{} scored cases are not part of the dataset version",
+            unknown_count
+        )));
+    }
+
+    Ok(())
+}
+
+/// Compare every metric recorded in either run, each under its own policy
+fn compare_metrics(
+    conn: &mut PgConnection,
+    eval_name: &str,
+    previous_result_id: i32,
+    current_result_id: i32,
+) -> Result<Vec<MetricOutcome>, Status> {
+    let metrics = DieselRepository::new(conn, eval_score::table)
+        .find_metrics(&[previous_result_id, current_result_id])
+        .map_err(|_| Status::internal("Failed to fetch eval metrics"))?;
+
+    let mut metric_outcomes = Vec::with_capacity(metrics.len());
+    for metric in metrics {
+        let comparison_policy = policy::load_policy(conn, eval_name, &metric)?;
+        let previous_cases = load_case_summaries(conn, previous_result_id, &metric)?;
+        let current_cases = load_case_summaries(conn, current_result_id, &metric)?;
+
+        let mut comparison = compare_results(&previous_cases, &current_cases, &comparison_policy);
+        cases::attach_case_details(
+            conn,
+            previous_result_id,
+            current_result_id,
+            &mut comparison.meaningful_scores,
+        )?;
+
+        metric_outcomes.push(MetricOutcome {
+            metric,
+            outcome: comparison.outcome.into(),
+            meaningful_eval_scores: comparison.meaningful_scores,
+            statistics: comparison.statistics,
+        });
+    }
+
+    Ok(metric_outcomes)
+}
+
+/// Analysis of the plain score, which keeps being reported at the top level for existing clients
+fn default_metric_analysis(
+    metric_outcomes: &[MetricOutcome],
+) -> (Vec<MeaningfulEvalScore>, Option<StatisticalSummary>) {
+    metric_outcomes
+        .iter()
+        .find(|metric| metric.metric == DEFAULT_METRIC)
+        .map(|metric| (metric.meaningful_eval_scores.clone(), metric.statistics))
+        .unwrap_or_default()
+}
+
+/// Verdict across metrics. A regression of any metric outweighs improvements of others.
+fn overall_outcome(outcomes: impl IntoIterator<Item = EvalOutcome>) -> EvalOutcome {
+    let outcomes: Vec<EvalOutcome> = outcomes.into_iter().collect();
+
+    if outcomes.contains(&EvalOutcome::Regression) {
+        EvalOutcome::Regression
+    } else if outcomes.contains(&EvalOutcome::Improvement) {
+        EvalOutcome::Improvement
+    } else if outcomes.contains(&EvalOutcome::NoChange) {
+        EvalOutcome::NoChange
+    } else {
+        EvalOutcome::Unknown
+    }
+}
+
+fn get_or_create_prompt_version(
+    conn: &mut PgConnection,
+    prompt: &VersionedPrompt,
+) -> Result<PromptVersion, Status> {
+    let find_existing = |conn: &mut PgConnection| {
+        prompt_version::table
+            .filter(prompt_version::name.eq(&prompt.name))
+            .filter(prompt_version::version.eq(&prompt.version))
+            .first::<PromptVersion>(conn)
+            .optional()
+            .map_err(|_| Status::internal("Failed to fetch prompt version"))
+    };
+
+    if let Some(version) = find_existing(conn)? {
+        return Ok(version);
+    }
+
+    let new_prompt_version = InsertablePromptVersion {
+        name: prompt.name.clone(),
+        version: prompt.version.clone(),
+        created_at: Utc::now(),
+        template: None,
+        content_hash: None,
+    };
+
+    // Concurrent runs may race to create the same version, in which case the unique index keeps
+    // a single row and the loser reads it back
+    let created = diesel::insert_into(prompt_version::table)
+        .values(&new_prompt_version)
+        .on_conflict((prompt_version::name, prompt_version::version))
+        .do_nothing()
+        .returning(prompt_version::all_columns)
+        .get_result::<PromptVersion>(conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to create prompt version"))?;
+
+    match created {
+        Some(version) => Ok(version),
+        None => {
+            find_existing(conn)?.ok_or_else(|| Status::internal("Failed to fetch prompt version"))
+        }
+    }
+}
+
+fn get_or_create_eval_version(
+    conn: &mut PgConnection,
+    eval: &ellmo_proto::ellmo::Eval,
+    prompt_version: &PromptVersion,
+) -> Result<Eval, Status> {
+    let find_existing = |conn: &mut PgConnection| {
+        eval::table
+            .filter(eval::name.eq(&eval.name))
+            .filter(eval::prompt_version_id.eq(prompt_version.id))
+            .first::<Eval>(conn)
+            .optional()
+            .map_err(|_| Status::internal("Failed to fetch eval version"))
+    };
+
+    if let Some(version) = find_existing(conn)? {
+        return Ok(version);
+    }
+
+    let new_eval_version = InsertableEval {
+        name: eval.name.clone(),
+        prompt_version_id: prompt_version.id,
+        created_at: Utc::now(),
+    };
+
+    let created = diesel::insert_into(eval::table)
+        .values(&new_eval_version)
+        .on_conflict((eval::name, eval::prompt_version_id))
+        .do_nothing()
+        .returning(eval::all_columns)
+        .get_result::<Eval>(conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to create eval version"))?;
+
+    match created {
+        Some(version) => Ok(version),
+        None => {
+            find_existing(conn)?.ok_or_else(|| Status::internal("Failed to fetch eval version"))
+        }
+    }
+}
+
+/// Latest run of the base version on the same dataset version (or without a dataset), optionally
+/// restricted to a branch
+fn get_previous_eval_result(
+    conn: &mut PgConnection,
+    eval_name: &str,
+    base_prompt_version: &PromptVersion,
+    dataset_version_id: Option<i32>,
+    branch: Option<&str>,
+) -> Result<Option<EvalResult>, Status> {
+    let repo = DieselRepository::new(conn, eval_result::table);
+
+    let mut query = eval_result::table
+        .inner_join(eval::table)
+        .filter(eval::name.eq(eval_name))
+        .filter(eval::prompt_version_id.eq(base_prompt_version.id))
+        .filter(eval_result::status.eq(STATUS_FINISHED))
+        .order(eval_result::created_at.desc())
+        .select(eval_result::all_columns)
+        .into_boxed();
+    query = match dataset_version_id {
+        Some(id) => query.filter(eval_result::dataset_version_id.eq(id)),
+        None => query.filter(eval_result::dataset_version_id.is_null()),
+    };
+    if let Some(branch) = branch {
+        query = query.filter(eval_result::branch.eq(branch));
+    }
+
+    query
+        .first::<EvalResult>(repo.connection)
+        .optional()
+        .map_err(|_| Status::internal("Failed to fetch previous eval result"))
+}
+
+/// Latest run of any version of the prompt recorded on a branch, on the same dataset version (or
+/// without a dataset)
+fn get_latest_branch_result(
+    conn: &mut PgConnection,
+    eval_name: &str,
+    prompt_name: &str,
+    branch: &str,
+    dataset_version_id: Option<i32>,
+) -> Result<Option<(EvalResult, PromptVersion)>, Status> {
+    let mut query = eval_result::table
+        .inner_join(eval::table.inner_join(prompt_version::table))
+        .filter(eval::name.eq(eval_name))
+        .filter(prompt_version::name.eq(prompt_name))
+        .filter(eval_result::branch.eq(branch))
+        .filter(eval_result::status.eq(STATUS_FINISHED))
+        .order(eval_result::created_at.desc())
+        .select((EvalResult::as_select(), PromptVersion::as_select()))
+        .into_boxed();
+    query = match dataset_version_id {
+        Some(id) => query.filter(eval_result::dataset_version_id.eq(id)),
+        None => query.filter(eval_result::dataset_version_id.is_null()),
+    };
+
+    query
+        .first::<(EvalResult, PromptVersion)>(conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to fetch previous eval result"))
+}
+
+/// Whether the base version has any run, regardless of dataset
+fn has_eval_result(
+    conn: &mut PgConnection,
+    eval_name: &str,
+    base_prompt_version: &PromptVersion,
+) -> Result<bool, Status> {
+    diesel::select(diesel::dsl::exists(
+        eval_result::table
+            .inner_join(eval::table)
+            .filter(eval::name.eq(eval_name))
+            .filter(eval::prompt_version_id.eq(base_prompt_version.id))
+            .filter(eval_result::status.eq(STATUS_FINISHED)),
+    ))
+    .get_result::<bool>(conn)
+    .map_err(|_| Status::internal("Failed to fetch previous eval result"))
+}
+
+fn get_base_prompt_version(
+    conn: &mut PgConnection,
+    prompt: &VersionedPrompt,
+    current_version: &PromptVersion,
+    base_version: Option<String>,
+) -> Result<Option<PromptVersion>, Status> {
+    let repo = DieselRepository::new(conn, prompt_version::table);
+
+    if let Some(base_version) = base_version {
+        let res = repo
+            .table
+            .filter(prompt_version::name.eq(&prompt.name))
+            .filter(prompt_version::version.eq(&base_version))
+            .first::<PromptVersion>(conn)
+            .optional()
+            .map_err(|_| Status::internal("Failed to fetch base prompt version"))?
+            .ok_or_else(|| Status::invalid_argument("Base version not found"))?;
+
+        Ok(Some(res))
+    } else {
+        let candidates = repo
+            .table
+            .filter(prompt_version::name.eq(&prompt.name))
+            .filter(prompt_version::id.ne(current_version.id))
+            .load::<PromptVersion>(conn)
+            .map_err(|_| Status::internal("Failed to fetch base prompt version"))?;
+
+        Ok(select_previous_version(current_version, candidates))
+    }
+}
+
+/// Pick the version preceding `current` among other versions of the same prompt.
+///
+/// Semver versions are ordered by precedence; when the current version is not semver (or no lower
+/// semver version exists), the most recently created version before it is used instead. A semver
+/// version never falls back to a version of higher or equal precedence, which would reverse the
+/// comparison.
+fn select_previous_version(
+    current: &PromptVersion,
+    candidates: Vec<PromptVersion>,
+) -> Option<PromptVersion> {
+    let current_semver = parse_semver(&current.version);
+    if let Some(current_semver) = &current_semver {
+        let previous = candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, candidate)| parse_semver(&candidate.version).map(|v| (v, i)))
+            .filter(|(version, _)| version < current_semver)
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, i)| i);
+
+        if let Some(index) = previous {
+            return candidates.into_iter().nth(index);
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.created_at < current.created_at)
+        .filter(
+            |candidate| match (&current_semver, parse_semver(&candidate.version)) {
+                (Some(current_semver), Some(version)) => version < *current_semver,
+                _ => true,
+            },
+        )
+        .max_by_key(|candidate| candidate.created_at)
+}
+
+/// Aggregate the trials of every case in a run for a single metric
+fn load_case_summaries(
+    conn: &mut PgConnection,
+    eval_result_id: i32,
+    metric: &str,
+) -> Result<CaseSummaries, Status> {
+    let aggregates = DieselRepository::new(conn, eval_score::table)
+        .aggregate_by_case(eval_result_id, metric)
+        .map_err(|_| Status::internal("Failed to aggregate eval scores"))?;
+
+    Ok(aggregates
+        .into_iter()
+        .map(|aggregate| {
+            let summary = stats::Summary {
+                count: aggregate.trials as usize,
+                mean: aggregate.mean,
+                variance: aggregate.variance,
+            };
+            (aggregate.eval_hash, summary)
+        })
+        .collect())
+}
+
+/// Row of a new run. Runs without a finish time are open and receive their scores in batches.
+fn new_eval_result(
+    eval_version: &Eval,
+    dataset_version_id: Option<i32>,
+    metadata: PreparedMetadata,
+    base_request: &BaseRequest,
+    scorers: &[ScorerSpec],
+    run_key: Option<String>,
+    finished_at: Option<DateTime<Utc>>,
+) -> InsertableEvalResult {
+    InsertableEvalResult {
+        eval_id: eval_version.id,
+        created_at: Utc::now(),
+        dataset_version_id,
+        git_sha: metadata.git_sha,
+        branch: metadata.branch,
+        ci_run_url: metadata.ci_run_url,
+        model: metadata.model,
+        temperature: metadata.temperature,
+        attributes: metadata.attributes,
+        status: if finished_at.is_some() {
+            STATUS_FINISHED
+        } else {
+            STATUS_OPEN
+        }
+        .to_string(),
+        finished_at,
+        run_key,
+        requested_base_version: base_request.version.clone(),
+        requested_base_label: base_request.label.clone(),
+        requested_base_branch: base_request.branch.clone(),
+        scorers: serde_json::json!(scorers),
+    }
+}
+
+fn compare_results(
+    previous_trials: &CaseSummaries,
+    current_trials: &CaseSummaries,
+    policy: &ComparisonPolicy,
+) -> Comparison {
+    // Changes are oriented so that a positive change is always an improvement
+    let orientation = if policy.higher_is_better { 1.0 } else { -1.0 };
+
+    let mut percent_changes = Vec::new();
+    let mut case_outcomes = Vec::new();
+    let mut deltas = Vec::new();
+    let mut meaningful_changes = Vec::new();
+
+    for (eval_hash, current_summary) in current_trials.iter() {
+        let Some(previous_summary) = previous_trials.get(eval_hash) else {
+            continue;
+        };
+
+        let previous_score = previous_summary.mean as f32;
+        let current_score = current_summary.mean as f32;
+
+        let raw_change = if previous_score != 0.0 {
+            (current_score - previous_score) / previous_score.abs()
+        } else if current_score != 0.0 {
+            current_score.signum()
+        } else {
+            0.0
+        };
+        let percent_change = raw_change * orientation;
+
+        // With repeated trials on both sides, a case only changes when the difference of its
+        // means stands out from the trial-to-trial variance
+        let p_value = stats::welch_t_test(previous_summary, current_summary);
+        let distinguishable = match p_value {
+            Some(p) => p < stats::SIGNIFICANCE_LEVEL,
+            None => true,
+        };
+
+        let individual_outcome = if !distinguishable {
+            EvalOutcome::NoChange
+        } else if percent_change > policy.individual_threshold {
+            EvalOutcome::Improvement
+        } else if percent_change < -policy.individual_threshold {
+            EvalOutcome::Regression
+        } else {
+            EvalOutcome::NoChange
+        };
+
+        percent_changes.push(percent_change);
+        case_outcomes.push(individual_outcome);
+        deltas.push(current_summary.mean - previous_summary.mean);
+
+        if individual_outcome != EvalOutcome::NoChange {
+            meaningful_changes.push(MeaningfulEvalScore {
+                eval_hash: eval_hash.clone(),
+                previous_score,
+                current_score,
+                outcome: individual_outcome.into(),
+                previous_trials: previous_summary.count as u32,
+                current_trials: current_summary.count as u32,
+                previous_std_dev: previous_summary.std_dev() as f32,
+                current_std_dev: current_summary.std_dev() as f32,
+                p_value,
+                input: None,
+                expected_output: None,
+                previous_output: None,
+                current_output: None,
+            });
+        }
+    }
+
+    if percent_changes.is_empty() {
+        return Comparison {
+            outcome: EvalOutcome::Unknown,
+            meaningful_scores: meaningful_changes,
+            statistics: None,
+        };
+    }
+
+    let significant_positives = case_outcomes
+        .iter()
+        .filter(|&&o| o == EvalOutcome::Improvement)
+        .count();
+    let significant_negatives = case_outcomes
+        .iter()
+        .filter(|&&o| o == EvalOutcome::Regression)
+        .count();
+
+    let overall_outcome = if significant_positives > 0 || significant_negatives > 0 {
+        match significant_positives.cmp(&significant_negatives) {
+            std::cmp::Ordering::Greater => EvalOutcome::Improvement,
+            std::cmp::Ordering::Less => EvalOutcome::Regression,
+            std::cmp::Ordering::Equal => EvalOutcome::Unknown,
+        }
+    } else {
+        let mean_percent_change =
+            percent_changes.iter().sum::<f32>() / percent_changes.len() as f32;
+        if mean_percent_change.abs() > policy.mean_threshold {
+            let total = percent_changes.len() as f32;
+            let num_positive = percent_changes.iter().filter(|&&c| c > 0.0).count() as f32;
+            let num_negative = percent_changes.iter().filter(|&&c| c < 0.0).count() as f32;
+
+            if num_positive / total > policy.consistency_threshold {
+                EvalOutcome::Improvement
+            } else if num_negative / total > policy.consistency_threshold {
+                EvalOutcome::Regression
+            } else {
+                EvalOutcome::Unknown
+            }
+        } else {
+            EvalOutcome::NoChange
+        }
+    };
+
+    let statistics = summarize_deltas(&deltas);
+
+    if percent_changes.len() < policy.min_sample_count {
+        return Comparison {
+            outcome: EvalOutcome::Unknown,
+            meaningful_scores: meaningful_changes,
+            statistics,
+        };
+    }
+
+    // Threshold heuristics flag noise on small datasets, so only report a change the paired
+    // tests agree is significant
+    let overall_outcome = match (&statistics, overall_outcome) {
+        (Some(summary), EvalOutcome::Improvement | EvalOutcome::Regression)
+            if !summary.significant =>
+        {
+            EvalOutcome::NoChange
+        }
+        (_, outcome) => outcome,
+    };
+
+    Comparison {
+        outcome: overall_outcome,
+        meaningful_scores: meaningful_changes,
+        statistics,
+    }
+}
+
+/// Run paired significance tests over per-case score deltas
+fn summarize_deltas(deltas: &[f64]) -> Option<StatisticalSummary> {
+    let bootstrap = stats::paired_bootstrap(deltas, BOOTSTRAP_ITERATIONS, CONFIDENCE_LEVEL)?;
+    // All deltas being zero means there is nothing to test
+    let wilcoxon_p_value = stats::wilcoxon_signed_rank(deltas).unwrap_or(1.0);
+
+    let interval_excludes_zero = bootstrap.lower > 0.0 || bootstrap.upper < 0.0;
+
+    Some(StatisticalSummary {
+        sample_size: deltas.len() as u32,
+        mean_delta: bootstrap.mean,
+        ci_lower: bootstrap.lower,
+        ci_upper: bootstrap.upper,
+        confidence_level: CONFIDENCE_LEVEL,
+        wilcoxon_p_value,
+        bootstrap_p_value: bootstrap.p_value,
+        significant: wilcoxon_p_value < stats::SIGNIFICANCE_LEVEL && interval_excludes_zero,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(id: i32, version: &str, minutes_ago: i64) -> PromptVersion {
+        PromptVersion {
+            id,
+            name: "summarizer".to_string(),
+            version: version.to_string(),
+            created_at: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            template: None,
+            content_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_previous_version_uses_semver_ordering() {
+        let current = version(1, "1.10.0", 0);
+        let candidates = vec![
+            version(2, "1.9.0", 30),
+            version(3, "1.2.0", 20),
+            version(4, "1.11.0", 10),
+        ];
+
+        let previous = select_previous_version(&current, candidates).unwrap();
+        assert_eq!(previous.version, "1.9.0");
+    }
+
+    #[test]
+    fn test_previous_version_tolerates_v_prefix() {
+        let current = version(1, "v2.0.0", 0);
+        let candidates = vec![version(2, "v1.0.0", 30), version(3, "v10.0.0", 10)];
+
+        let previous = select_previous_version(&current, candidates).unwrap();
+        assert_eq!(previous.version, "v1.0.0");
+    }
+
+    #[test]
+    fn test_previous_version_falls_back_to_creation_time() {
+        let current = version(1, "2024-09-10", 5);
+        let candidates = vec![
+            version(2, "2024-09-01", 30),
+            version(3, "2024-09-08", 20),
+            version(4, "2024-09-12", 1),
+        ];
+
+        let previous = select_previous_version(&current, candidates).unwrap();
+        assert_eq!(previous.version, "2024-09-08");
+    }
+
+    #[test]
+    fn test_no_previous_version() {
+        let current = version(1, "1.0.0", 0);
+        let candidates = vec![version(2, "2.0.0", -10)];
+
+        assert!(select_previous_version(&current, candidates).is_none());
+    }
+
+    #[test]
+    fn test_previous_version_never_falls_back_to_a_higher_version() {
+        let current = version(1, "1.0.0", 0);
+        let candidates = vec![version(2, "2.0.0", 30)];
+        assert!(select_previous_version(&current, candidates).is_none());
+
+        // Versions that are not semver remain candidates by creation time
+        let candidates = vec![version(2, "2.0.0", 10), version(3, "draft", 30)];
+        let previous = select_previous_version(&current, candidates).unwrap();
+        assert_eq!(previous.version, "draft");
+    }
+
+    fn scores(values: &[(&str, f32)]) -> CaseSummaries {
+        let mut trials: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        for (hash, score) in values {
+            trials
+                .entry(hash.to_string())
+                .or_default()
+                .push(*score as f64);
+        }
+
+        trials
+            .into_iter()
+            .map(|(hash, values)| (hash, stats::Summary::from_values(&values)))
+            .collect()
+    }
+
+    #[test]
+    fn test_small_regression_is_not_significant() {
+        let previous = scores(&[("a", 1.0), ("b", 1.0)]);
+        let current = scores(&[("a", 0.5), ("b", 0.8)]);
+
+        let comparison = compare_results(&previous, &current, &ComparisonPolicy::default());
+
+        assert_eq!(comparison.outcome, EvalOutcome::NoChange);
+        assert_eq!(comparison.meaningful_scores.len(), 2);
+        assert!(!comparison.statistics.unwrap().significant);
+    }
+
+    #[test]
+    fn test_consistent_regression_is_significant() {
+        let hashes: Vec<String> = (0..30).map(|i| format!("case-{}", i)).collect();
+        let previous = scores(&hashes.iter().map(|h| (h.as_str(), 0.9)).collect::<Vec<_>>());
+        let current = scores(
+            &hashes
+                .iter()
+                .enumerate()
+                .map(|(i, h)| (h.as_str(), 0.6 - i as f32 * 0.001))
+                .collect::<Vec<_>>(),
+        );
+
+        let comparison = compare_results(&previous, &current, &ComparisonPolicy::default());
+        let statistics = comparison.statistics.unwrap();
+
+        assert_eq!(comparison.outcome, EvalOutcome::Regression);
+        assert_eq!(statistics.sample_size, 30);
+        assert!(statistics.significant);
+        assert!(statistics.ci_upper < 0.0);
+    }
+
+    #[test]
+    fn test_no_overlap_is_unknown() {
+        let previous = scores(&[("a", 1.0)]);
+        let current = scores(&[("b", 1.0)]);
+
+        let comparison = compare_results(&previous, &current, &ComparisonPolicy::default());
+
+        assert_eq!(comparison.outcome, EvalOutcome::Unknown);
+        assert!(comparison.statistics.is_none());
+    }
+
+    fn latency_scores(offset: f32) -> (CaseSummaries, CaseSummaries) {
+        let hashes: Vec<String> = (0..30).map(|i| format!("case-{}", i)).collect();
+        let previous = scores(
+            &hashes
+                .iter()
+                .map(|h| (h.as_str(), 100.0))
+                .collect::<Vec<_>>(),
+        );
+        let current = scores(
+            &hashes
+                .iter()
+                .enumerate()
+                .map(|(i, h)| (h.as_str(), 100.0 + offset + i as f32))
+                .collect::<Vec<_>>(),
+        );
+        (previous, current)
+    }
+
+    #[test]
+    fn test_lower_is_better_flips_direction() {
+        let policy = ComparisonPolicy {
+            higher_is_better: false,
+            ..ComparisonPolicy::default()
+        };
+
+        let (previous, current) = latency_scores(20.0);
+        let comparison = compare_results(&previous, &current, &policy);
+        assert_eq!(comparison.outcome, EvalOutcome::Regression);
+        assert!(comparison
+            .meaningful_scores
+            .iter()
+            .all(|score| score.outcome() == EvalOutcome::Regression));
+
+        let (previous, current) = latency_scores(-50.0);
+        let comparison = compare_results(&previous, &current, &policy);
+        assert_eq!(comparison.outcome, EvalOutcome::Improvement);
+    }
+
+    #[test]
+    fn test_custom_thresholds() {
+        let policy = ComparisonPolicy {
+            individual_threshold: 1.0,
+            ..ComparisonPolicy::default()
+        };
+
+        let (previous, current) = latency_scores(20.0);
+        let comparison = compare_results(&previous, &current, &policy);
+        assert!(comparison.meaningful_scores.is_empty());
+    }
+
+    #[test]
+    fn test_repeated_trials_are_compared() {
+        let previous = scores(&[("a", 0.9), ("a", 0.92), ("a", 0.91)]);
+        let current = scores(&[("a", 0.5), ("a", 0.52), ("a", 0.51)]);
+
+        let comparison = compare_results(&previous, &current, &ComparisonPolicy::default());
+        let score = &comparison.meaningful_scores[0];
+
+        assert_eq!(score.outcome(), EvalOutcome::Regression);
+        assert_eq!(score.previous_trials, 3);
+        assert_eq!(score.current_trials, 3);
+        assert!((score.previous_score - 0.91).abs() < 1e-5);
+        assert!(score.p_value.unwrap() < 0.05);
+    }
+
+    #[test]
+    fn test_noisy_trials_are_not_meaningful() {
+        let previous = scores(&[("a", 0.2), ("a", 0.9), ("a", 0.5)]);
+        let current = scores(&[("a", 0.3), ("a", 0.8), ("a", 0.9)]);
+
+        let comparison = compare_results(&previous, &current, &ComparisonPolicy::default());
+
+        assert!(comparison.meaningful_scores.is_empty());
+        assert_eq!(comparison.outcome, EvalOutcome::NoChange);
+    }
+
+    #[test]
+    fn test_min_sample_count() {
+        let policy = ComparisonPolicy {
+            min_sample_count: 50,
+            ..ComparisonPolicy::default()
+        };
+
+        let (previous, current) = latency_scores(20.0);
+        let comparison = compare_results(&previous, &current, &policy);
+        assert_eq!(comparison.outcome, EvalOutcome::Unknown);
+        assert_eq!(comparison.statistics.unwrap().sample_size, 30);
+    }
+
+    #[test]
+    fn test_any_metric_regression_is_a_regression() {
+        let outcome = overall_outcome([
+            EvalOutcome::Improvement,
+            EvalOutcome::Regression,
+            EvalOutcome::NoChange,
+        ]);
+
+        assert_eq!(outcome, EvalOutcome::Regression);
+    }
+
+    #[test]
+    fn test_overall_outcome_without_regression() {
+        assert_eq!(
+            overall_outcome([EvalOutcome::Unknown, EvalOutcome::Improvement]),
+            EvalOutcome::Improvement
+        );
+        assert_eq!(
+            overall_outcome([EvalOutcome::Unknown, EvalOutcome::NoChange]),
+            EvalOutcome::NoChange
+        );
+        assert_eq!(overall_outcome([]), EvalOutcome::Unknown);
+    }
+}
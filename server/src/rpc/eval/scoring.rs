@@ -0,0 +1,464 @@
+use diesel::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+use tonic::Status;
+
+use ellmo_db::{
+    models::{repository::DieselRepository, scorer_plugin::ScorerPlugin},
+    schema::{eval_case, scorer_plugin},
+};
+use ellmo_proto::ellmo::{EvalScore, Metric, ScorerConfig, ScorerKind};
+
+use super::cases::parse_payload;
+use super::metadata::non_empty;
+use crate::scorer::{self, Scorer, ScorerSpec};
+
+/// Registered plugins that plugin scorers are resolved against
+pub trait PluginRegistry {
+    /// Version of a plugin, the most recently registered one when no version is given
+    fn find_plugin(
+        &mut self,
+        name: &str,
+        version: Option<&str>,
+    ) -> QueryResult<Option<ScorerPlugin>>;
+
+    /// Module with the given content hash
+    fn find_module(&mut self, content_hash: &str) -> QueryResult<Option<Vec<u8>>>;
+}
+
+impl PluginRegistry for PgConnection {
+    fn find_plugin(
+        &mut self,
+        name: &str,
+        version: Option<&str>,
+    ) -> QueryResult<Option<ScorerPlugin>> {
+        let mut repository = DieselRepository::new(self, scorer_plugin::table);
+        match version {
+            Some(version) => repository.find_by_version(name, version),
+            None => repository.find_latest(name),
+        }
+    }
+
+    fn find_module(&mut self, content_hash: &str) -> QueryResult<Option<Vec<u8>>> {
+        DieselRepository::new(self, scorer_plugin::table).find_module(content_hash)
+    }
+}
+
+/// Validate the scorers requested for a run, turning them into the specs stored with it. Plugins
+/// are pinned to the module of the version they resolve to.
+pub fn prepare_scorers(
+    registry: &mut dyn PluginRegistry,
+    configs: Vec<ScorerConfig>,
+) -> Result<Vec<ScorerSpec>, Status> {
+    let mut specs: Vec<ScorerSpec> = Vec::with_capacity(configs.len());
+    for config in configs {
+        let spec = convert_config(registry, config)?;
+        if specs.iter().any(|other| other.metric == spec.metric) {
+            return Err(Status::invalid_argument(format!(
+                "Duplicate scorer metric {}",
+                spec.metric
+            )));
+        }
+        specs.push(spec);
+    }
+
+    // Compile every scorer once so invalid patterns and schemas are rejected up front
+    RunScorers::build(registry, &specs)?;
+
+    Ok(specs)
+}
+
+fn convert_config(
+    registry: &mut dyn PluginRegistry,
+    config: ScorerConfig,
+) -> Result<ScorerSpec, Status> {
+    let kind = ScorerKind::try_from(config.kind)
+        .map_err(|_| Status::invalid_argument(format!("Unknown scorer kind {}", config.kind)))?;
+
+    let kind = match kind {
+        ScorerKind::ExactMatch => scorer::ScorerKind::ExactMatch,
+        ScorerKind::NormalizedMatch => scorer::ScorerKind::NormalizedMatch,
+        ScorerKind::EditSimilarity => scorer::ScorerKind::EditSimilarity,
+        ScorerKind::RegexMatch => scorer::ScorerKind::RegexMatch {
+            pattern: non_empty(config.pattern)
+                .ok_or_else(|| Status::invalid_argument("Regex match scorer requires a pattern"))?,
+        },
+        ScorerKind::JsonSchema => {
+            let schema = config
+                .schema
+                .ok_or_else(|| Status::invalid_argument("JSON schema scorer requires a schema"))?;
+            scorer::ScorerKind::JsonSchema {
+                schema: serde_json::from_str(&schema)
+                    .map_err(|_| Status::invalid_argument("Invalid JSON in scorer schema"))?,
+            }
+        }
+        ScorerKind::NumericTolerance => scorer::ScorerKind::NumericTolerance {
+            tolerance: config.tolerance.unwrap_or_default(),
+            relative: config.relative,
+        },
+        ScorerKind::Plugin => {
+            let name = non_empty(config.plugin)
+                .ok_or_else(|| Status::invalid_argument("Plugin scorer requires a plugin name"))?;
+            let version = non_empty(config.plugin_version);
+            let plugin = registry
+                .find_plugin(&name, version.as_deref())
+                .map_err(|_| Status::internal("Failed to fetch scorer plugin"))?
+                .ok_or_else(|| match version {
+                    Some(version) => {
+                        Status::not_found(format!("Scorer plugin {} {} not found", name, version))
+                    }
+                    None => Status::not_found(format!("Scorer plugin {} not found", name)),
+                })?;
+            scorer::ScorerKind::Plugin {
+                name: plugin.name,
+                version: plugin.version,
+                content_hash: plugin.content_hash,
+                needs_expected: plugin.needs_expected,
+            }
+        }
+    };
+
+    Ok(ScorerSpec {
+        metric: non_empty(config.metric).unwrap_or_else(|| kind.default_metric().to_string()),
+        kind,
+    })
+}
+
+/// Config a stored scorer spec was created from, with plugins pinned to their resolved version
+pub fn convert_spec(spec: &ScorerSpec) -> ScorerConfig {
+    let mut config = ScorerConfig {
+        metric: Some(spec.metric.clone()),
+        ..Default::default()
+    };
+    let kind = match &spec.kind {
+        scorer::ScorerKind::ExactMatch => ScorerKind::ExactMatch,
+        scorer::ScorerKind::NormalizedMatch => ScorerKind::NormalizedMatch,
+        scorer::ScorerKind::EditSimilarity => ScorerKind::EditSimilarity,
+        scorer::ScorerKind::RegexMatch { pattern } => {
+            config.pattern = Some(pattern.clone());
+            ScorerKind::RegexMatch
+        }
+        scorer::ScorerKind::JsonSchema { schema } => {
+            config.schema = Some(schema.to_string());
+            ScorerKind::JsonSchema
+        }
+        scorer::ScorerKind::NumericTolerance {
+            tolerance,
+            relative,
+        } => {
+            config.tolerance = Some(*tolerance);
+            config.relative = *relative;
+            ScorerKind::NumericTolerance
+        }
+        scorer::ScorerKind::Plugin { name, version, .. } => {
+            config.plugin = Some(name.clone());
+            config.plugin_version = Some(version.clone());
+            ScorerKind::Plugin
+        }
+    };
+    config.set_kind(kind);
+    config
+}
+
+/// Compiled scorers of a run, along with the metrics they record
+pub struct RunScorers {
+    scorers: Vec<(String, Box<dyn Scorer>)>,
+}
+
+impl RunScorers {
+    pub fn build(registry: &mut dyn PluginRegistry, specs: &[ScorerSpec]) -> Result<Self, Status> {
+        let mut load_module = |content_hash: &str| {
+            registry
+                .find_module(content_hash)?
+                .ok_or_else(|| anyhow::anyhow!("Module {} is not registered", content_hash))
+        };
+        let scorers = specs
+            .iter()
+            .map(|spec| {
+                scorer::build(spec, &mut load_module)
+                    .map(|built| (spec.metric.clone(), built))
+                    .map_err(|e| {
+                        Status::invalid_argument(format!("Invalid scorer {}: {}", spec.metric, e))
+                    })
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
+
+        Ok(RunScorers { scorers })
+    }
+
+    /// Scorers stored with a run
+    pub fn load(
+        registry: &mut dyn PluginRegistry,
+        stored: &serde_json::Value,
+    ) -> Result<Self, Status> {
+        let specs: Vec<ScorerSpec> = serde_json::from_value(stored.clone())
+            .map_err(|_| Status::internal("Failed to load scorers of eval run"))?;
+        Self::build(registry, &specs)
+    }
+
+    /// Metric of the first scorer that compares outputs against an expected output, if any
+    pub fn expected_metric(&self) -> Option<&str> {
+        self.scorers
+            .iter()
+            .find(|(_, scorer)| scorer.needs_expected())
+            .map(|(metric, _)| metric.as_str())
+    }
+
+    /// Scores of an output that has no expected output, by metric
+    pub fn score_output(&self, output: &serde_json::Value) -> Vec<(&str, anyhow::Result<f32>)> {
+        self.scorers
+            .iter()
+            .map(|(metric, scorer)| (metric.as_str(), scorer.score(output, None)))
+            .collect()
+    }
+
+    /// Score the actual output of every trial, adding a metric per scorer. Trials without an
+    /// expected output are scored against the expected output stored with their case.
+    pub fn apply(
+        &self,
+        conn: &mut PgConnection,
+        eval_scores: &mut [EvalScore],
+    ) -> Result<(), Status> {
+        if self.scorers.is_empty() {
+            return Ok(());
+        }
+
+        let stored_expected = if self.scorers.iter().any(|(_, s)| s.needs_expected()) {
+            let hashes: BTreeSet<String> = eval_scores
+                .iter()
+                .filter(|score| score.expected_output.is_none())
+                .map(|score| score.eval_hash.clone())
+                .collect();
+            find_expected_outputs(conn, hashes.into_iter().collect())?
+        } else {
+            HashMap::new()
+        };
+
+        for score in eval_scores.iter_mut() {
+            let output = parse_payload(
+                score.actual_output.as_deref(),
+                "actual output",
+                &score.eval_hash,
+            )?
+            .ok_or_else(|| {
+                Status::invalid_argument(format!(
+                    "Case {} has no actual output to score",
+                    score.eval_hash
+                ))
+            })?;
+            let expected = match parse_payload(
+                score.expected_output.as_deref(),
+                "expected output",
+                &score.eval_hash,
+            )? {
+                Some(expected) => Some(expected),
+                None => stored_expected.get(&score.eval_hash).cloned(),
+            };
+
+            for (metric, scorer) in &self.scorers {
+                if scorer.needs_expected() && expected.is_none() {
+                    return Err(Status::invalid_argument(format!(
+                        "Case {} has no expected output to compute {}",
+                        score.eval_hash, metric
+                    )));
+                }
+
+                let value = scorer.score(&output, expected.as_ref()).map_err(|e| {
+                    Status::invalid_argument(format!(
+                        "Failed to compute {} of case {}: {}",
+                        metric, score.eval_hash, e
+                    ))
+                })?;
+                score.metrics.push(Metric {
+                    name: metric.clone(),
+                    value,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn find_expected_outputs(
+    conn: &mut PgConnection,
+    eval_hashes: Vec<String>,
+) -> Result<HashMap<String, serde_json::Value>, Status> {
+    if eval_hashes.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let cases = DieselRepository::new(conn, eval_case::table)
+        .find_by_hashes(&eval_hashes)
+        .map_err(|_| Status::internal("Failed to fetch eval cases"))?;
+
+    Ok(cases
+        .into_iter()
+        .filter_map(|case| {
+            case.expected_output
+                .map(|expected| (case.eval_hash, expected))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Registry with a single plugin
+    struct Plugins;
+
+    impl PluginRegistry for Plugins {
+        fn find_plugin(
+            &mut self,
+            name: &str,
+            version: Option<&str>,
+        ) -> QueryResult<Option<ScorerPlugin>> {
+            if name != "constant" || version.is_some_and(|version| version != "1.0.0") {
+                return Ok(None);
+            }
+
+            Ok(Some(ScorerPlugin {
+                id: 1,
+                name: name.to_string(),
+                version: "1.0.0".to_string(),
+                module: Vec::new(),
+                content_hash: "constant-scorer-test".to_string(),
+                needs_expected: false,
+                created_at: chrono::Utc::now(),
+            }))
+        }
+
+        fn find_module(&mut self, _content_hash: &str) -> QueryResult<Option<Vec<u8>>> {
+            Ok(Some(
+                br#"(module
+                    (memory (export "memory") 1)
+                    (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                    (func (export "score") (param i32 i32 i32 i32) (result f32) (f32.const 0.5)))"#
+                    .to_vec(),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_prepare_scorers_defaults_metric_names() {
+        let specs = prepare_scorers(
+            &mut Plugins,
+            vec![
+                ScorerConfig {
+                    kind: ScorerKind::ExactMatch.into(),
+                    ..Default::default()
+                },
+                ScorerConfig {
+                    kind: ScorerKind::RegexMatch.into(),
+                    metric: Some("is_date".to_string()),
+                    pattern: Some(r"\d{4}".to_string()),
+                    ..Default::default()
+                },
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(specs[0].metric, "exact_match");
+        assert_eq!(specs[1].metric, "is_date");
+    }
+
+    #[test]
+    fn test_prepare_scorers_rejects_invalid_configs() {
+        let exact = ScorerConfig {
+            kind: ScorerKind::ExactMatch.into(),
+            ..Default::default()
+        };
+        let invalid_pattern = ScorerConfig {
+            kind: ScorerKind::RegexMatch.into(),
+            pattern: Some("(".to_string()),
+            ..Default::default()
+        };
+        let missing_schema = ScorerConfig {
+            kind: ScorerKind::JsonSchema.into(),
+            ..Default::default()
+        };
+
+        assert!(prepare_scorers(&mut Plugins, vec![exact.clone(), exact]).is_err());
+        assert!(prepare_scorers(&mut Plugins, vec![invalid_pattern]).is_err());
+        assert!(prepare_scorers(&mut Plugins, vec![missing_schema]).is_err());
+        assert!(prepare_scorers(
+            &mut Plugins,
+            vec![ScorerConfig {
+                kind: 42,
+                ..Default::default()
+            }]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_prepare_scorers_pins_plugins() {
+        let plugin = |version: Option<&str>| ScorerConfig {
+            kind: ScorerKind::Plugin.into(),
+            plugin: Some("constant".to_string()),
+            plugin_version: version.map(str::to_string),
+            ..Default::default()
+        };
+
+        let specs = prepare_scorers(&mut Plugins, vec![plugin(None)]).unwrap();
+        assert_eq!(specs[0].metric, "constant");
+        assert_eq!(
+            specs[0].kind,
+            scorer::ScorerKind::Plugin {
+                name: "constant".to_string(),
+                version: "1.0.0".to_string(),
+                content_hash: "constant-scorer-test".to_string(),
+                needs_expected: false,
+            }
+        );
+
+        let scorers = RunScorers::build(&mut Plugins, &specs).unwrap();
+        let (_, scorer) = &scorers.scorers[0];
+        assert_eq!(scorer.score(&serde_json::json!("a"), None).unwrap(), 0.5);
+
+        let missing = prepare_scorers(&mut Plugins, vec![plugin(Some("2.0.0"))]).unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+        assert!(prepare_scorers(
+            &mut Plugins,
+            vec![ScorerConfig {
+                kind: ScorerKind::Plugin.into(),
+                ..Default::default()
+            }]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_convert_spec_round_trips() {
+        let configs = vec![
+            ScorerConfig {
+                kind: ScorerKind::JsonSchema.into(),
+                schema: Some(r#"{"type": "object"}"#.to_string()),
+                ..Default::default()
+            },
+            ScorerConfig {
+                kind: ScorerKind::NumericTolerance.into(),
+                tolerance: Some(0.1),
+                relative: true,
+                ..Default::default()
+            },
+            ScorerConfig {
+                kind: ScorerKind::Plugin.into(),
+                plugin: Some("constant".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let specs = prepare_scorers(&mut Plugins, configs).unwrap();
+        let converted: Vec<ScorerConfig> = specs.iter().map(convert_spec).collect();
+        assert_eq!(converted[2].plugin_version.as_deref(), Some("1.0.0"));
+        assert_eq!(prepare_scorers(&mut Plugins, converted).unwrap(), specs);
+
+        let scorers = RunScorers::build(&mut Plugins, &specs).unwrap();
+        assert_eq!(scorers.expected_metric(), Some("numeric_tolerance"));
+        let scores = scorers.score_output(&serde_json::json!({"a": 1}));
+        assert_eq!(scores[0].0, "json_schema");
+        assert_eq!(scores[0].1.as_ref().unwrap(), &1.0);
+        assert_eq!(scores[2].1.as_ref().unwrap(), &0.5);
+    }
+}
@@ -0,0 +1,423 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use std::collections::BTreeSet;
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        eval_result::{EvalResult, STATUS_FINISHED, STATUS_OPEN},
+        eval_run_batch::{EvalRunBatch, InsertableEvalRunBatch},
+        repository::{DieselRepository, Repository},
+    },
+    schema::{dataset_version_case, eval_result, eval_run_batch, eval_score},
+};
+use ellmo_proto::ellmo::{
+    AppendEvalScoresRequest, AppendEvalScoresResponse, FinishEvalRunRequest, RecordEvalResponse,
+    StartEvalRunRequest, StartEvalRunResponse, VersionedPrompt,
+};
+
+use super::cases::PreparedScores;
+use super::metadata::{self, PreparedMetadata};
+use super::scoring::{self, RunScorers};
+use super::{compare, dataset, BaseRequest};
+
+/// How far the scores of a run have been appended
+#[derive(Debug, Default, PartialEq)]
+struct Progress {
+    next_sequence: i32,
+    trial_count: i32,
+}
+
+impl Progress {
+    fn after(last_batch: Option<&EvalRunBatch>) -> Self {
+        match last_batch {
+            Some(batch) => Progress {
+                next_sequence: batch.sequence + 1,
+                trial_count: batch.first_trial_index + batch.trial_count,
+            },
+            None => Progress::default(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum BatchOrder {
+    /// Already stored, e.g. resent by a client that did not see the response
+    Duplicate,
+    Next,
+    /// Batches before it are missing
+    Gap,
+}
+
+fn batch_order(sequence: i32, progress: &Progress) -> BatchOrder {
+    match sequence.cmp(&progress.next_sequence) {
+        std::cmp::Ordering::Less => BatchOrder::Duplicate,
+        std::cmp::Ordering::Equal => BatchOrder::Next,
+        std::cmp::Ordering::Greater => BatchOrder::Gap,
+    }
+}
+
+enum Append {
+    Stored(Progress),
+    Duplicate(Progress),
+    Gap(Progress),
+    Finished,
+}
+
+/// Open a run whose scores are appended in batches, or resume the open run with the same key
+pub async fn start_eval_run(
+    request: Request<StartEvalRunRequest>,
+) -> Result<Response<StartEvalRunResponse>, Status> {
+    let message = request.into_inner();
+
+    let eval = message
+        .eval
+        .ok_or_else(|| Status::invalid_argument("Missing eval"))?;
+    let prompt = message
+        .prompt
+        .ok_or_else(|| Status::invalid_argument("Missing prompt"))?;
+    let base_request = BaseRequest::new(
+        message.base_version,
+        message.base_label,
+        message.base_branch,
+    )?;
+    let run_metadata = PreparedMetadata::prepare(message.metadata)?;
+    let run_key = metadata::non_empty(message.run_key);
+
+    let mut conn = establish_connection();
+    let scorers = scoring::prepare_scorers(&mut conn, message.scorers)?;
+
+    let dataset_version_id = match &message.dataset {
+        Some(reference) => Some(dataset::find_version(&mut conn, reference)?.id),
+        None => None,
+    };
+    let prompt_version = super::get_or_create_prompt_version(&mut conn, &prompt)?;
+    let eval_version = super::get_or_create_eval_version(&mut conn, &eval, &prompt_version)?;
+
+    if let Some(run_key) = &run_key {
+        let existing = DieselRepository::new(&mut conn, eval_result::table)
+            .find_by_run_key(run_key)
+            .map_err(|_| Status::internal("Failed to fetch eval run"))?;
+        if let Some(existing) = existing {
+            return resume(&mut conn, existing, eval_version.id, dataset_version_id);
+        }
+    }
+
+    // Fail before any scores are sent when the base cannot be resolved
+    super::resolve_base(
+        &mut conn,
+        &eval.name,
+        &prompt,
+        &prompt_version,
+        &base_request,
+        dataset_version_id,
+    )?;
+
+    let new_run = super::new_eval_result(
+        &eval_version,
+        dataset_version_id,
+        run_metadata,
+        &base_request,
+        &scorers,
+        run_key.clone(),
+        None,
+    );
+    // A concurrent start with the same key may have won the race, in which case its run is
+    // resumed instead
+    let created = diesel::insert_into(eval_result::table)
+        .values(&new_run)
+        .on_conflict(eval_result::run_key)
+        .do_nothing()
+        .returning(eval_result::all_columns)
+        .get_result::<EvalResult>(&mut conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to create eval run"))?;
+
+    match (created, run_key) {
+        (Some(run), _) => Ok(Response::new(StartEvalRunResponse {
+            eval_run_id: run.id,
+            resumed: false,
+            next_sequence: 0,
+            trial_count: 0,
+        })),
+        (None, Some(run_key)) => {
+            let existing = DieselRepository::new(&mut conn, eval_result::table)
+                .find_by_run_key(&run_key)
+                .map_err(|_| Status::internal("Failed to fetch eval run"))?
+                .ok_or_else(|| Status::internal("Failed to create eval run"))?;
+            resume(&mut conn, existing, eval_version.id, dataset_version_id)
+        }
+        (None, None) => Err(Status::internal("Failed to create eval run")),
+    }
+}
+
+/// Append a batch of scores to an open run. Batches must be appended in order; resending a
+/// batch that was already stored is acknowledged without storing it again.
+pub async fn append_eval_scores(
+    request: Request<AppendEvalScoresRequest>,
+) -> Result<Response<AppendEvalScoresResponse>, Status> {
+    let message = request.into_inner();
+
+    let sequence = i32::try_from(message.sequence)
+        .map_err(|_| Status::invalid_argument("Invalid sequence number"))?;
+    let mut eval_scores = message.eval_scores;
+    let scored_hashes: BTreeSet<String> = eval_scores
+        .iter()
+        .map(|score| score.eval_hash.clone())
+        .collect();
+
+    let mut conn = establish_connection();
+    let run = find_open_run(&mut conn, message.eval_run_id)?;
+    if let Some(dataset_version_id) = run.dataset_version_id {
+        check_batch_cases(&mut conn, dataset_version_id, &scored_hashes)?;
+    }
+
+    RunScorers::load(&mut conn, &run.scorers)?.apply(&mut conn, &mut eval_scores)?;
+    let prepared_scores = PreparedScores::prepare(eval_scores, Utc::now())?;
+    let batch_trial_count = prepared_scores.trial_count() as i32;
+
+    let append = conn
+        .transaction(|conn| {
+            // Lock the run so concurrent appends and finishing it are serialized
+            let run = eval_result::table
+                .find(run.id)
+                .for_update()
+                .first::<EvalResult>(conn)?;
+            if run.status != STATUS_OPEN {
+                return Ok(Append::Finished);
+            }
+
+            let last_batch =
+                DieselRepository::new(conn, eval_run_batch::table).find_last(run.id)?;
+            let progress = Progress::after(last_batch.as_ref());
+            match batch_order(sequence, &progress) {
+                BatchOrder::Duplicate => return Ok(Append::Duplicate(progress)),
+                BatchOrder::Gap => return Ok(Append::Gap(progress)),
+                BatchOrder::Next => {}
+            }
+
+            prepared_scores.store(conn, run.id, progress.trial_count)?;
+            DieselRepository::new(conn, eval_run_batch::table).create(&InsertableEvalRunBatch {
+                eval_result_id: run.id,
+                sequence,
+                first_trial_index: progress.trial_count,
+                trial_count: batch_trial_count,
+                created_at: Utc::now(),
+            })?;
+
+            Ok(Append::Stored(Progress {
+                next_sequence: sequence + 1,
+                trial_count: progress.trial_count + batch_trial_count,
+            }))
+        })
+        .map_err(|_: diesel::result::Error| Status::internal("Failed to append eval scores"))?;
+
+    let (duplicate, progress) = match append {
+        Append::Stored(progress) => (false, progress),
+        Append::Duplicate(progress) => (true, progress),
+        Append::Gap(progress) => {
+            return Err(Status::failed_precondition(format!(
+                "Expected batch {}",
+                progress.next_sequence
+            )))
+        }
+        Append::Finished => {
+            return Err(Status::failed_precondition("Eval run is already finished"))
+        }
+    };
+
+    Ok(Response::new(AppendEvalScoresResponse {
+        duplicate,
+        next_sequence: progress.next_sequence as u32,
+        trial_count: progress.trial_count as u32,
+    }))
+}
+
+/// Close a run and compare it against the base requested when it was started
+pub async fn finish_eval_run(
+    request: Request<FinishEvalRunRequest>,
+) -> Result<Response<RecordEvalResponse>, Status> {
+    let eval_run_id = request.into_inner().eval_run_id;
+
+    let mut conn = establish_connection();
+    let (run, eval, prompt_version) = compare::find_run(&mut conn, eval_run_id)?;
+    if run.status != STATUS_OPEN {
+        return Err(Status::failed_precondition("Eval run is already finished"));
+    }
+
+    let prompt = VersionedPrompt {
+        name: prompt_version.name.clone(),
+        version: prompt_version.version.clone(),
+    };
+    let base_request = BaseRequest {
+        version: run.requested_base_version.clone(),
+        label: run.requested_base_label.clone(),
+        branch: run.requested_base_branch.clone(),
+    };
+    let base = super::resolve_base(
+        &mut conn,
+        &eval.name,
+        &prompt,
+        &prompt_version,
+        &base_request,
+        run.dataset_version_id,
+    )?;
+    let missing_case_count = match run.dataset_version_id {
+        Some(dataset_version_id) => count_missing_cases(&mut conn, dataset_version_id, run.id)?,
+        None => 0,
+    };
+
+    // Waits for appends in progress, and only one of concurrent finishes gets the run
+    let finished = diesel::update(
+        eval_result::table
+            .filter(eval_result::id.eq(run.id))
+            .filter(eval_result::status.eq(STATUS_OPEN)),
+    )
+    .set((
+        eval_result::status.eq(STATUS_FINISHED),
+        eval_result::finished_at.eq(Utc::now()),
+    ))
+    .get_result::<EvalResult>(&mut conn)
+    .optional()
+    .map_err(|_| Status::internal("Failed to finish eval run"))?
+    .ok_or_else(|| Status::failed_precondition("Eval run is already finished"))?;
+
+    let response = super::analyze_run(
+        &mut conn,
+        &eval.name,
+        &prompt,
+        &finished,
+        base,
+        missing_case_count,
+    )?;
+
+    Ok(Response::new(response))
+}
+
+fn resume(
+    conn: &mut PgConnection,
+    run: EvalResult,
+    eval_id: i32,
+    dataset_version_id: Option<i32>,
+) -> Result<Response<StartEvalRunResponse>, Status> {
+    if run.status != STATUS_OPEN {
+        return Err(Status::already_exists(
+            "A finished run has the same run key",
+        ));
+    }
+    if run.eval_id != eval_id || run.dataset_version_id != dataset_version_id {
+        return Err(Status::already_exists(
+            "A run of another eval, prompt version or dataset version has the same run key",
+        ));
+    }
+
+    let last_batch = DieselRepository::new(conn, eval_run_batch::table)
+        .find_last(run.id)
+        .map_err(|_| Status::internal("Failed to fetch eval run batches"))?;
+    let progress = Progress::after(last_batch.as_ref());
+
+    Ok(Response::new(StartEvalRunResponse {
+        eval_run_id: run.id,
+        resumed: true,
+        next_sequence: progress.next_sequence as u32,
+        trial_count: progress.trial_count as u32,
+    }))
+}
+
+fn find_open_run(conn: &mut PgConnection, id: i32) -> Result<EvalResult, Status> {
+    let run = DieselRepository::new(conn, eval_result::table)
+        .find_by_id(id)
+        .optional()
+        .map_err(|_| Status::internal("Failed to fetch eval run"))?
+        .ok_or_else(|| Status::not_found("Eval run not found"))?;
+    if run.status != STATUS_OPEN {
+        return Err(Status::failed_precondition("Eval run is already finished"));
+    }
+
+    Ok(run)
+}
+
+/// Reject a batch scoring cases that are not part of the dataset version of the run
+fn check_batch_cases(
+    conn: &mut PgConnection,
+    dataset_version_id: i32,
+    scored_hashes: &BTreeSet<String>,
+) -> Result<(), Status> {
+    let scored_hashes: Vec<&String> = scored_hashes.iter().collect();
+    let known_count = dataset_version_case::table
+        .filter(dataset_version_case::dataset_version_id.eq(dataset_version_id))
+        .filter(dataset_version_case::eval_hash.eq_any(&scored_hashes))
+        .count()
+        .get_result::<i64>(conn)
+        .map_err(|_| Status::internal("Failed to fetch dataset cases"))?;
+
+    let unknown_count = scored_hashes.len() as i64 - known_count;
+    if unknown_count > 0 {
+        return Err(Status::invalid_argument(format!(
+            "{} scored cases are not part of the dataset version",
+            unknown_count
+        )));
+    }
+
+    Ok(())
+}
+
+/// Number of cases of the dataset version without a score in the run
+fn count_missing_cases(
+    conn: &mut PgConnection,
+    dataset_version_id: i32,
+    eval_result_id: i32,
+) -> Result<u32, Status> {
+    let dataset_hashes = DieselRepository::new(conn, dataset_version_case::table)
+        .find_hashes(dataset_version_id)
+        .map_err(|_| Status::internal("Failed to fetch dataset cases"))?;
+    let scored_hashes: BTreeSet<String> = DieselRepository::new(conn, eval_score::table)
+        .find_hashes(eval_result_id)
+        .map_err(|_| Status::internal("Failed to fetch eval scores"))?
+        .into_iter()
+        .collect();
+
+    Ok(dataset_hashes
+        .iter()
+        .filter(|hash| !scored_hashes.contains(*hash))
+        .count() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(sequence: i32, first_trial_index: i32, trial_count: i32) -> EvalRunBatch {
+        EvalRunBatch {
+            id: 1,
+            eval_result_id: 1,
+            sequence,
+            first_trial_index,
+            trial_count,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_progress_after_last_batch() {
+        assert_eq!(Progress::after(None), Progress::default());
+        assert_eq!(
+            Progress::after(Some(&batch(2, 2000, 500))),
+            Progress {
+                next_sequence: 3,
+                trial_count: 2500,
+            }
+        );
+    }
+
+    #[test]
+    fn test_batch_order() {
+        let progress = Progress::after(Some(&batch(2, 2000, 500)));
+
+        assert_eq!(batch_order(0, &progress), BatchOrder::Duplicate);
+        assert_eq!(batch_order(2, &progress), BatchOrder::Duplicate);
+        assert_eq!(batch_order(3, &progress), BatchOrder::Next);
+        assert_eq!(batch_order(5, &progress), BatchOrder::Gap);
+    }
+}
@@ -0,0 +1,361 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::sql_types::{Float8, Int4, Nullable, Text};
+use serde_json::json;
+use std::collections::HashSet;
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        experiment::{Experiment, InsertableExperiment},
+        experiment_arm::{ExperimentArm, InsertableExperimentArm},
+        prompt_version::PromptVersion,
+        repository::DieselRepository,
+        span::InsertableSpan,
+    },
+    schema::{experiment, experiment_arm, prompt_version},
+};
+use ellmo_proto::ellmo::{
+    AssignExperimentRequest, AssignExperimentResponse, CreateExperimentRequest,
+    CreateExperimentResponse, Experiment as ExperimentMessage, ExperimentArm as ArmMessage,
+    ExperimentArmResult, GetExperimentResultsRequest, GetExperimentResultsResponse,
+    VersionedPrompt,
+};
+
+use super::eval::metadata::non_empty;
+use super::span::record_span;
+use super::to_timestamp;
+use crate::experiment::{analyze, assign, by_subject, ArmOutcome};
+
+/// Operation name of the spans recording that a subject was served an arm
+const EXPOSURE_OPERATION: &str = "experiment.exposure";
+
+/// Define an experiment between versions of a prompt
+pub async fn create_experiment(
+    request: Request<CreateExperimentRequest>,
+) -> Result<Response<CreateExperimentResponse>, Status> {
+    let definition = request
+        .into_inner()
+        .experiment
+        .ok_or_else(|| Status::invalid_argument("Missing experiment"))?;
+    if definition.name.is_empty() {
+        return Err(Status::invalid_argument("Missing experiment name"));
+    }
+    if definition.prompt_name.is_empty() {
+        return Err(Status::invalid_argument("Missing prompt name"));
+    }
+    if definition.arms.len() < 2 {
+        return Err(Status::invalid_argument(
+            "An experiment needs at least two arms",
+        ));
+    }
+    let mut seen = HashSet::new();
+    for arm in &definition.arms {
+        if !seen.insert(arm.version.as_str()) {
+            return Err(Status::invalid_argument(format!(
+                "Version {} is used by more than one arm",
+                arm.version
+            )));
+        }
+        if arm.weight == 0 || i32::try_from(arm.weight).is_err() {
+            return Err(Status::invalid_argument(format!(
+                "Invalid weight for version {}",
+                arm.version
+            )));
+        }
+    }
+
+    let mut conn = establish_connection();
+    let mut versions = Vec::new();
+    for arm in &definition.arms {
+        let version = DieselRepository::new(&mut conn, prompt_version::table)
+            .find_by_version(&definition.prompt_name, &arm.version)
+            .map_err(|_| Status::internal("Failed to fetch prompt version"))?
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "Version {} of prompt {} not found",
+                    arm.version, definition.prompt_name
+                ))
+            })?;
+        versions.push(version);
+    }
+
+    let created = conn
+        .transaction(|conn| {
+            let Some(created) = diesel::insert_into(experiment::table)
+                .values(&InsertableExperiment {
+                    name: definition.name.clone(),
+                    prompt_name: definition.prompt_name.clone(),
+                    created_at: Utc::now(),
+                })
+                .on_conflict(experiment::name)
+                .do_nothing()
+                .returning(experiment::all_columns)
+                .get_result::<Experiment>(conn)
+                .optional()?
+            else {
+                return Ok(None);
+            };
+
+            let arms: Vec<InsertableExperimentArm> = definition
+                .arms
+                .iter()
+                .zip(&versions)
+                .map(|(arm, version)| InsertableExperimentArm {
+                    experiment_id: created.id,
+                    prompt_version_id: version.id,
+                    weight: arm.weight as i32,
+                })
+                .collect();
+            let arms = diesel::insert_into(experiment_arm::table)
+                .values(&arms)
+                .returning(experiment_arm::all_columns)
+                .get_results::<ExperimentArm>(conn)?;
+
+            Ok(Some((created, arms)))
+        })
+        .map_err(|_: diesel::result::Error| Status::internal("Failed to create experiment"))?;
+
+    let Some((created, arms)) = created else {
+        return Err(Status::already_exists(format!(
+            "Experiment {} already exists",
+            definition.name
+        )));
+    };
+    let arms: Vec<(ExperimentArm, PromptVersion)> = arms.into_iter().zip(versions).collect();
+
+    Ok(Response::new(CreateExperimentResponse {
+        experiment: Some(convert_experiment(&created, &arms)),
+    }))
+}
+
+/// Assign a subject to an arm of an experiment, recording the exposure as a span. A subject
+/// assigned several times gets the same arm and counts once in the results.
+pub async fn assign_experiment(
+    request: Request<AssignExperimentRequest>,
+) -> Result<Response<AssignExperimentResponse>, Status> {
+    let message = request.into_inner();
+    if message.subject_key.is_empty() {
+        return Err(Status::invalid_argument("Missing subject key"));
+    }
+
+    let mut conn = establish_connection();
+    let (stored, arms) = find_experiment(&mut conn, &message.experiment)?;
+    let weights: Vec<u32> = arms.iter().map(|(arm, _)| arm.weight as u32).collect();
+    let (_, version) = &arms[assign(&stored.name, &message.subject_key, &weights)];
+
+    let now = Utc::now();
+    let span_uuid = record_span(
+        &mut conn,
+        InsertableSpan {
+            ts_start: now,
+            ts_end: now,
+            operation_name: EXPOSURE_OPERATION.to_string(),
+            parent_span_id: None,
+            external_uuid: None,
+            attributes: json!({
+                "experiment.name": stored.name,
+                "experiment.subject": message.subject_key,
+                "prompt.name": version.name,
+                "prompt.version": version.version,
+            }),
+            input: None,
+            output: None,
+            prompt_version_id: Some(version.id),
+        },
+        non_empty(message.parent_span_id).as_deref(),
+    )?;
+
+    Ok(Response::new(AssignExperimentResponse {
+        prompt: Some(VersionedPrompt {
+            name: version.name.clone(),
+            version: version.version.clone(),
+        }),
+        span_id: span_uuid.to_string(),
+    }))
+}
+
+/// Outcome of each arm of an experiment on a metric
+pub async fn get_experiment_results(
+    request: Request<GetExperimentResultsRequest>,
+) -> Result<Response<GetExperimentResultsResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let results = experiment_results(&mut conn, &message.experiment, &message.metric)?;
+
+    Ok(Response::new(results))
+}
+
+/// Outcome of each arm of an experiment, measured by the feedback and numeric attributes named
+/// after the metric on the exposure spans and their descendants
+pub fn experiment_results(
+    conn: &mut PgConnection,
+    name: &str,
+    metric: &str,
+) -> Result<GetExperimentResultsResponse, Status> {
+    if metric.is_empty() {
+        return Err(Status::invalid_argument("Missing metric"));
+    }
+
+    let (stored, arms) = find_experiment(conn, name)?;
+    let rows = load_exposure_values(conn, &stored.name, metric)
+        .map_err(|_| Status::internal("Failed to fetch exposures"))?;
+
+    let exposures = by_subject(rows.into_iter().filter_map(|row| {
+        let arm = arms
+            .iter()
+            .position(|(arm, _)| Some(arm.prompt_version_id) == row.prompt_version_id)?;
+        Some((row.subject, arm, row.value))
+    }));
+    let outcomes = analyze(arms.len(), &exposures);
+
+    Ok(GetExperimentResultsResponse {
+        experiment: Some(convert_experiment(&stored, &arms)),
+        metric: metric.to_string(),
+        arms: arms
+            .iter()
+            .zip(&outcomes)
+            .map(|((_, version), outcome)| ExperimentArmResult {
+                version: version.version.clone(),
+                exposures: outcome.exposures as u32,
+                observations: outcome.summary.count as u32,
+                mean: outcome.summary.mean,
+                std_dev: outcome.summary.std_dev(),
+                difference: outcome.difference,
+                relative_lift: outcome.relative_lift,
+                p_value: outcome.p_value,
+                significant: outcome.significant,
+            })
+            .collect(),
+        readout: readout(metric, &arms, &outcomes),
+    })
+}
+
+/// One line summary of the arms that differ significantly from the control
+fn readout(
+    metric: &str,
+    arms: &[(ExperimentArm, PromptVersion)],
+    outcomes: &[ArmOutcome],
+) -> String {
+    let control = &arms[0].1.version;
+    let parts: Vec<String> = arms
+        .iter()
+        .zip(outcomes)
+        .filter(|(_, outcome)| outcome.significant)
+        .map(|((_, version), outcome)| {
+            let difference = outcome.difference.unwrap_or_default();
+            let lift = outcome
+                .relative_lift
+                .map(|lift| format!(" ({:+.1}%)", lift * 100.0))
+                .unwrap_or_default();
+            format!(
+                "{} {} {} on {} by {:+.4}{}, p={:.4}",
+                version.version,
+                if difference > 0.0 { "beats" } else { "trails" },
+                control,
+                metric,
+                difference,
+                lift,
+                outcome.p_value.unwrap_or_default()
+            )
+        })
+        .collect();
+
+    if parts.is_empty() {
+        format!("No significant difference from {} on {}", control, metric)
+    } else {
+        parts.join("; ")
+    }
+}
+
+/// Experiment by name along with its arms and their versions, in the order of the arms
+fn find_experiment(
+    conn: &mut PgConnection,
+    name: &str,
+) -> Result<(Experiment, Vec<(ExperimentArm, PromptVersion)>), Status> {
+    if name.is_empty() {
+        return Err(Status::invalid_argument("Missing experiment name"));
+    }
+
+    let stored = DieselRepository::new(conn, experiment::table)
+        .find_by_name(name)
+        .map_err(|_| Status::internal("Failed to fetch experiment"))?
+        .ok_or_else(|| Status::not_found("Experiment not found"))?;
+    let arms = experiment_arm::table
+        .inner_join(prompt_version::table)
+        .filter(experiment_arm::experiment_id.eq(stored.id))
+        .order(experiment_arm::id.asc())
+        .select((ExperimentArm::as_select(), PromptVersion::as_select()))
+        .load::<(ExperimentArm, PromptVersion)>(conn)
+        .map_err(|_| Status::internal("Failed to fetch experiment arms"))?;
+
+    Ok((stored, arms))
+}
+
+#[derive(QueryableByName)]
+struct ExposureValueRow {
+    #[diesel(sql_type = Text)]
+    subject: String,
+    #[diesel(sql_type = Nullable<Int4>)]
+    prompt_version_id: Option<i32>,
+    #[diesel(sql_type = Nullable<Float8>)]
+    value: Option<f64>,
+}
+
+/// Values of a metric recorded against the exposures of an experiment, from the feedback and the
+/// attributes of the exposure spans and their descendants, along with the subject exposed. Every
+/// exposure has at least one row, with a null value when nothing was recorded.
+fn load_exposure_values(
+    conn: &mut PgConnection,
+    experiment_name: &str,
+    metric: &str,
+) -> QueryResult<Vec<ExposureValueRow>> {
+    diesel::sql_query(
+        "WITH RECURSIVE exposure_tree AS ( \
+             SELECT COALESCE(s.attributes->>'experiment.subject', s.id::text) AS subject, \
+                    s.prompt_version_id, s.id AS span_id \
+             FROM span s \
+             WHERE s.operation_name = $1 AND s.attributes->>'experiment.name' = $2 \
+             UNION ALL \
+             SELECT t.subject, t.prompt_version_id, c.id \
+             FROM span c JOIN exposure_tree t ON c.parent_span_id = t.span_id \
+         ) \
+         SELECT t.subject, t.prompt_version_id, v.value \
+         FROM exposure_tree t \
+         LEFT JOIN LATERAL ( \
+             SELECT f.score AS value FROM span_feedback f \
+             WHERE f.span_id = t.span_id AND f.name = $3 \
+             UNION ALL \
+             SELECT CASE jsonb_typeof(s.attributes->$3) \
+                        WHEN 'boolean' THEN CASE WHEN (s.attributes->>$3)::boolean \
+                                                 THEN 1::float8 ELSE 0::float8 END \
+                        ELSE (s.attributes->>$3)::float8 END \
+             FROM span s \
+             WHERE s.id = t.span_id AND jsonb_typeof(s.attributes->$3) IN ('number', 'boolean') \
+         ) v ON TRUE",
+    )
+    .bind::<Text, _>(EXPOSURE_OPERATION)
+    .bind::<Text, _>(experiment_name)
+    .bind::<Text, _>(metric)
+    .load::<ExposureValueRow>(conn)
+}
+
+fn convert_experiment(
+    stored: &Experiment,
+    arms: &[(ExperimentArm, PromptVersion)],
+) -> ExperimentMessage {
+    ExperimentMessage {
+        name: stored.name.clone(),
+        prompt_name: stored.prompt_name.clone(),
+        arms: arms
+            .iter()
+            .map(|(arm, version)| ArmMessage {
+                version: version.version.clone(),
+                weight: arm.weight as u32,
+            })
+            .collect(),
+        created_at: Some(to_timestamp(stored.created_at)),
+    }
+}
@@ -0,0 +1,298 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::gate_policy::{GatePolicy, InsertableGatePolicy},
+    schema::gate_policy,
+};
+use ellmo_proto::ellmo::{
+    EvalOutcome, GateVerdict, GetGatePolicyRequest, GetGatePolicyResponse, MetricOutcome,
+    SetGatePolicyRequest, SetGatePolicyResponse,
+};
+
+/// Rules deciding whether a run of a prompt passes its CI gate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gate {
+    pub fail_on_regression: bool,
+    pub max_case_regression: Option<f32>,
+    pub fail_on_unknown: bool,
+}
+
+impl Default for Gate {
+    fn default() -> Self {
+        Gate {
+            fail_on_regression: true,
+            max_case_regression: None,
+            fail_on_unknown: false,
+        }
+    }
+}
+
+impl From<GatePolicy> for Gate {
+    fn from(policy: GatePolicy) -> Self {
+        Gate {
+            fail_on_regression: policy.fail_on_regression,
+            max_case_regression: policy.max_case_regression,
+            fail_on_unknown: policy.fail_on_unknown,
+        }
+    }
+}
+
+impl Gate {
+    fn to_proto(&self, prompt_name: &str) -> ellmo_proto::ellmo::GatePolicy {
+        ellmo_proto::ellmo::GatePolicy {
+            prompt_name: prompt_name.to_string(),
+            fail_on_regression: self.fail_on_regression,
+            max_case_regression: self.max_case_regression,
+            fail_on_unknown: self.fail_on_unknown,
+        }
+    }
+
+    /// Judge the outcome of a run. Only cases reported as meaningful are checked against the
+    /// case regression limit.
+    pub fn evaluate(&self, outcome: EvalOutcome, metric_outcomes: &[MetricOutcome]) -> GateVerdict {
+        let mut reasons = Vec::new();
+
+        if self.fail_on_regression && outcome == EvalOutcome::Regression {
+            let regressed: Vec<&str> = metric_outcomes
+                .iter()
+                .filter(|metric| metric.outcome() == EvalOutcome::Regression)
+                .map(|metric| metric.metric.as_str())
+                .collect();
+            reasons.push(format!(
+                "Overall outcome is a regression (regressed metrics: {})",
+                regressed.join(", ")
+            ));
+        }
+
+        if let Some(max_case_regression) = self.max_case_regression {
+            for metric in metric_outcomes {
+                let mut exceeding: Vec<(&str, f32)> = metric
+                    .meaningful_eval_scores
+                    .iter()
+                    .filter(|score| score.outcome() == EvalOutcome::Regression)
+                    .map(|score| {
+                        let change = relative_change(score.previous_score, score.current_score);
+                        (score.eval_hash.as_str(), change)
+                    })
+                    .filter(|(_, change)| *change > max_case_regression)
+                    .collect();
+                exceeding.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+                if let Some((worst_hash, worst_change)) = exceeding.first() {
+                    reasons.push(format!(
+                        "{} cases of {} regressed by more than {:.1}% (worst: {} by {:.1}%)",
+                        exceeding.len(),
+                        metric.metric,
+                        max_case_regression * 100.0,
+                        worst_hash,
+                        worst_change * 100.0
+                    ));
+                }
+            }
+        }
+
+        if self.fail_on_unknown && outcome == EvalOutcome::Unknown {
+            reasons.push("Outcome could not be determined".to_string());
+        }
+
+        GateVerdict {
+            passed: reasons.is_empty(),
+            reasons,
+        }
+    }
+}
+
+/// Size of a change relative to the previous value
+fn relative_change(previous: f32, current: f32) -> f32 {
+    if previous == 0.0 {
+        if current == 0.0 {
+            0.0
+        } else {
+            f32::INFINITY
+        }
+    } else {
+        ((current - previous) / previous).abs()
+    }
+}
+
+/// Load the stored gate of a prompt, falling back to the defaults
+pub fn load_gate(conn: &mut PgConnection, prompt_name: &str) -> Result<Gate, Status> {
+    Ok(find_gate_policy(conn, prompt_name)?
+        .map(Gate::from)
+        .unwrap_or_default())
+}
+
+fn find_gate_policy(
+    conn: &mut PgConnection,
+    prompt_name: &str,
+) -> Result<Option<GatePolicy>, Status> {
+    gate_policy::table
+        .filter(gate_policy::prompt_name.eq(prompt_name))
+        .first::<GatePolicy>(conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to fetch gate policy"))
+}
+
+/// Create or replace the gate policy of a prompt
+pub async fn set_gate_policy(
+    request: Request<SetGatePolicyRequest>,
+) -> Result<Response<SetGatePolicyResponse>, Status> {
+    let policy = request
+        .into_inner()
+        .policy
+        .ok_or_else(|| Status::invalid_argument("Missing policy"))?;
+
+    if policy.prompt_name.is_empty() {
+        return Err(Status::invalid_argument("Missing prompt name"));
+    }
+    validate_case_regression_limit(policy.max_case_regression)?;
+
+    let now = Utc::now();
+    let insertable = InsertableGatePolicy {
+        prompt_name: policy.prompt_name.clone(),
+        fail_on_regression: policy.fail_on_regression,
+        max_case_regression: policy.max_case_regression,
+        fail_on_unknown: policy.fail_on_unknown,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let mut conn = establish_connection();
+    let stored = diesel::insert_into(gate_policy::table)
+        .values(&insertable)
+        .on_conflict(gate_policy::prompt_name)
+        .do_update()
+        .set((
+            gate_policy::fail_on_regression.eq(insertable.fail_on_regression),
+            gate_policy::max_case_regression.eq(insertable.max_case_regression),
+            gate_policy::fail_on_unknown.eq(insertable.fail_on_unknown),
+            gate_policy::updated_at.eq(now),
+        ))
+        .returning(gate_policy::all_columns)
+        .get_result::<GatePolicy>(&mut conn)
+        .map_err(|_| Status::internal("Failed to store gate policy"))?;
+
+    let prompt_name = stored.prompt_name.clone();
+    Ok(Response::new(SetGatePolicyResponse {
+        policy: Some(Gate::from(stored).to_proto(&prompt_name)),
+    }))
+}
+
+/// Fetch the gate policy applied to a prompt
+pub async fn get_gate_policy(
+    request: Request<GetGatePolicyRequest>,
+) -> Result<Response<GetGatePolicyResponse>, Status> {
+    let prompt_name = request.into_inner().prompt_name;
+    if prompt_name.is_empty() {
+        return Err(Status::invalid_argument("Missing prompt name"));
+    }
+
+    let mut conn = establish_connection();
+    let stored = find_gate_policy(&mut conn, &prompt_name)?;
+    let is_default = stored.is_none();
+    let gate = stored.map(Gate::from).unwrap_or_default();
+
+    Ok(Response::new(GetGatePolicyResponse {
+        policy: Some(gate.to_proto(&prompt_name)),
+        is_default,
+    }))
+}
+
+/// A limit that is not a number would never be exceeded, silently turning the check off
+fn validate_case_regression_limit(max_case_regression: Option<f32>) -> Result<(), Status> {
+    match max_case_regression {
+        Some(max) if !max.is_finite() => Err(Status::invalid_argument(
+            "Case regression limit must be a finite number",
+        )),
+        Some(max) if max < 0.0 => Err(Status::invalid_argument(
+            "Case regression limit must not be negative",
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ellmo_proto::ellmo::MeaningfulEvalScore;
+
+    fn metric(name: &str, outcome: EvalOutcome, changes: &[(&str, f32, f32)]) -> MetricOutcome {
+        MetricOutcome {
+            metric: name.to_string(),
+            outcome: outcome.into(),
+            meaningful_eval_scores: changes
+                .iter()
+                .map(
+                    |(eval_hash, previous_score, current_score)| MeaningfulEvalScore {
+                        eval_hash: eval_hash.to_string(),
+                        previous_score: *previous_score,
+                        current_score: *current_score,
+                        outcome: EvalOutcome::Regression.into(),
+                        ..Default::default()
+                    },
+                )
+                .collect(),
+            statistics: None,
+        }
+    }
+
+    #[test]
+    fn test_default_gate_fails_on_regression() {
+        let metrics = [metric("latency", EvalOutcome::Regression, &[])];
+
+        let verdict = Gate::default().evaluate(EvalOutcome::Regression, &metrics);
+
+        assert!(!verdict.passed);
+        assert_eq!(
+            verdict.reasons,
+            vec!["Overall outcome is a regression (regressed metrics: latency)"]
+        );
+        assert!(Gate::default().evaluate(EvalOutcome::Unknown, &[]).passed);
+    }
+
+    #[test]
+    fn test_case_regression_limit() {
+        let gate = Gate {
+            fail_on_regression: false,
+            max_case_regression: Some(0.2),
+            fail_on_unknown: false,
+        };
+        let metrics = [metric(
+            "score",
+            EvalOutcome::NoChange,
+            &[("a", 1.0, 0.9), ("b", 1.0, 0.5), ("c", 1.0, 0.7)],
+        )];
+
+        let verdict = gate.evaluate(EvalOutcome::NoChange, &metrics);
+
+        assert!(!verdict.passed);
+        assert_eq!(
+            verdict.reasons,
+            vec!["2 cases of score regressed by more than 20.0% (worst: b by 50.0%)"]
+        );
+    }
+
+    #[test]
+    fn test_fail_on_unknown() {
+        let gate = Gate {
+            fail_on_unknown: true,
+            ..Gate::default()
+        };
+
+        assert!(!gate.evaluate(EvalOutcome::Unknown, &[]).passed);
+        assert!(gate.evaluate(EvalOutcome::Improvement, &[]).passed);
+    }
+
+    #[test]
+    fn test_case_regression_limit_must_be_finite() {
+        assert!(validate_case_regression_limit(None).is_ok());
+        assert!(validate_case_regression_limit(Some(0.1)).is_ok());
+        assert!(validate_case_regression_limit(Some(-0.1)).is_err());
+        assert!(validate_case_regression_limit(Some(f32::NAN)).is_err());
+        assert!(validate_case_regression_limit(Some(f32::INFINITY)).is_err());
+        assert!(validate_case_regression_limit(Some(f32::NEG_INFINITY)).is_err());
+    }
+}
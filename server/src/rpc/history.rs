@@ -0,0 +1,127 @@
+use tonic::{Request, Response, Status};
+
+use ellmo_db::establish_connection;
+use ellmo_proto::ellmo::{
+    EvalRun, EvalScore, EvalSummary, GetEvalRunRequest, GetEvalRunResponse, ListEvalRunsRequest,
+    ListEvalRunsResponse, ListEvalsRequest, ListEvalsResponse, Metric, MetricSummary, RunMetadata,
+    VersionedPrompt,
+};
+
+use super::{from_timestamp, to_timestamp};
+use crate::history::{self, RunFilter, RunSummary};
+
+/// List evals, optionally restricted to a single prompt
+pub async fn list_evals(
+    request: Request<ListEvalsRequest>,
+) -> Result<Response<ListEvalsResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let evals = history::list_evals(&mut conn, message.prompt_name.as_deref())
+        .map_err(|_| Status::internal("Failed to list evals"))?;
+
+    Ok(Response::new(ListEvalsResponse {
+        evals: evals
+            .into_iter()
+            .map(|eval| EvalSummary {
+                name: eval.name,
+                prompt_name: eval.prompt_name,
+                run_count: eval.run_count as u32,
+                last_run_at: eval.last_run_at.map(to_timestamp),
+            })
+            .collect(),
+    }))
+}
+
+/// List the runs of an eval over time
+pub async fn list_eval_runs(
+    request: Request<ListEvalRunsRequest>,
+) -> Result<Response<ListEvalRunsResponse>, Status> {
+    let message = request.into_inner();
+    if message.eval_name.is_empty() {
+        return Err(Status::invalid_argument("Missing eval name"));
+    }
+
+    let filter = RunFilter {
+        prompt_name: message.prompt_name,
+        min_version: message.min_version,
+        max_version: message.max_version,
+        created_after: message.created_after.map(from_timestamp).transpose()?,
+        created_before: message.created_before.map(from_timestamp).transpose()?,
+        branch: message.branch,
+    };
+
+    let mut conn = establish_connection();
+    let runs = history::list_runs(&mut conn, &message.eval_name, &filter)
+        .map_err(|_| Status::internal("Failed to list eval runs"))?;
+
+    Ok(Response::new(ListEvalRunsResponse {
+        runs: runs.into_iter().map(convert_run).collect(),
+    }))
+}
+
+/// Fetch a single run with its scores
+pub async fn get_eval_run(
+    request: Request<GetEvalRunRequest>,
+) -> Result<Response<GetEvalRunResponse>, Status> {
+    let id = request.into_inner().id;
+
+    let mut conn = establish_connection();
+    let detail = history::get_run(&mut conn, id)
+        .map_err(|_| Status::internal("Failed to fetch eval run"))?
+        .ok_or_else(|| Status::not_found("Eval run not found"))?;
+
+    Ok(Response::new(GetEvalRunResponse {
+        run: Some(convert_run(detail.run)),
+        eval_scores: detail
+            .scores
+            .into_iter()
+            .map(|score| EvalScore {
+                eval_hash: score.eval_hash,
+                score: score.score,
+                input: None,
+                expected_output: None,
+                actual_output: None,
+                metrics: score
+                    .metrics
+                    .into_iter()
+                    .map(|metric| Metric {
+                        name: metric.name,
+                        value: metric.value,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }))
+}
+
+fn convert_run(run: RunSummary) -> EvalRun {
+    EvalRun {
+        id: run.id,
+        eval_name: run.eval_name,
+        prompt: Some(VersionedPrompt {
+            name: run.prompt_name,
+            version: run.prompt_version,
+        }),
+        created_at: Some(to_timestamp(run.created_at)),
+        score_count: run.score_count as u32,
+        mean_score: run.mean_score,
+        metadata: Some(RunMetadata {
+            git_sha: run.metadata.git_sha,
+            branch: run.metadata.branch,
+            ci_run_url: run.metadata.ci_run_url,
+            model: run.metadata.model,
+            temperature: run.metadata.temperature,
+            attributes: run.metadata.attributes.into_iter().collect(),
+        }),
+        metrics: run
+            .metrics
+            .into_iter()
+            .map(|metric| MetricSummary {
+                name: metric.name,
+                count: metric.count as u32,
+                mean: metric.mean,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,295 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        prompt_label::{InsertablePromptLabel, PromptLabel},
+        prompt_label_history::{InsertablePromptLabelHistory, PromptLabelHistory},
+        prompt_version::PromptVersion,
+        repository::{DieselRepository, Repository},
+    },
+    schema::{prompt_label, prompt_label_history, prompt_version},
+};
+use ellmo_proto::ellmo::{
+    DeletePromptLabelRequest, GetPromptLabelHistoryRequest, GetPromptLabelHistoryResponse,
+    ListPromptLabelsRequest, ListPromptLabelsResponse, PromptLabelMove, SetPromptLabelRequest,
+    SetPromptLabelResponse,
+};
+
+use super::to_timestamp;
+
+const MAX_LABEL_LENGTH: usize = 64;
+
+/// Point a label at a prompt version, recording the move in the label history
+pub async fn set_prompt_label(
+    request: Request<SetPromptLabelRequest>,
+) -> Result<Response<SetPromptLabelResponse>, Status> {
+    let message = request.into_inner();
+    let prompt = message
+        .prompt
+        .ok_or_else(|| Status::invalid_argument("Missing prompt"))?;
+    validate_label(&message.label)?;
+
+    let mut conn = establish_connection();
+    let version = prompt_version::table
+        .filter(prompt_version::name.eq(&prompt.name))
+        .filter(prompt_version::version.eq(&prompt.version))
+        .first::<PromptVersion>(&mut conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to fetch prompt version"))?
+        .ok_or_else(|| Status::not_found("Prompt version not found"))?;
+
+    let label = conn
+        .transaction(|conn| move_label(conn, &version, &message.label, message.actor))
+        .map_err(|_| Status::internal("Failed to set prompt label"))?;
+
+    Ok(Response::new(SetPromptLabelResponse {
+        label: Some(ellmo_proto::ellmo::PromptLabel {
+            prompt_name: label.prompt_name,
+            label: label.label,
+            version: version.version,
+            updated_at: Some(to_timestamp(label.updated_at)),
+        }),
+    }))
+}
+
+/// Remove a label from a prompt, recording the removal in the label history
+pub async fn delete_prompt_label(
+    request: Request<DeletePromptLabelRequest>,
+) -> Result<Response<()>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let deleted = conn
+        .transaction(|conn| {
+            let Some(existing) = find_label_for_update(conn, &message.prompt_name, &message.label)?
+            else {
+                return Ok(false);
+            };
+
+            DieselRepository::new(conn, prompt_label::table).delete(existing.id)?;
+            DieselRepository::new(conn, prompt_label_history::table).create(
+                &InsertablePromptLabelHistory {
+                    prompt_name: existing.prompt_name,
+                    label: existing.label,
+                    previous_prompt_version_id: Some(existing.prompt_version_id),
+                    prompt_version_id: None,
+                    actor: message.actor,
+                    created_at: Utc::now(),
+                },
+            )?;
+
+            Ok(true)
+        })
+        .map_err(|_: diesel::result::Error| Status::internal("Failed to delete prompt label"))?;
+
+    if !deleted {
+        return Err(Status::not_found("Prompt label not found"));
+    }
+
+    Ok(Response::new(()))
+}
+
+/// List the labels of a prompt along with the versions they point to
+pub async fn list_prompt_labels(
+    request: Request<ListPromptLabelsRequest>,
+) -> Result<Response<ListPromptLabelsResponse>, Status> {
+    let prompt_name = request.into_inner().prompt_name;
+
+    let mut conn = establish_connection();
+    let labels = prompt_label::table
+        .inner_join(prompt_version::table)
+        .filter(prompt_label::prompt_name.eq(&prompt_name))
+        .order(prompt_label::label.asc())
+        .select((PromptLabel::as_select(), prompt_version::version))
+        .load::<(PromptLabel, String)>(&mut conn)
+        .map_err(|_| Status::internal("Failed to list prompt labels"))?;
+
+    Ok(Response::new(ListPromptLabelsResponse {
+        labels: labels
+            .into_iter()
+            .map(|(label, version)| ellmo_proto::ellmo::PromptLabel {
+                prompt_name: label.prompt_name,
+                label: label.label,
+                version,
+                updated_at: Some(to_timestamp(label.updated_at)),
+            })
+            .collect(),
+    }))
+}
+
+/// Fetch the audit history of the labels of a prompt
+pub async fn get_prompt_label_history(
+    request: Request<GetPromptLabelHistoryRequest>,
+) -> Result<Response<GetPromptLabelHistoryResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let mut query = prompt_label_history::table
+        .filter(prompt_label_history::prompt_name.eq(&message.prompt_name))
+        .order((
+            prompt_label_history::created_at.asc(),
+            prompt_label_history::id.asc(),
+        ))
+        .into_boxed();
+    if let Some(label) = &message.label {
+        query = query.filter(prompt_label_history::label.eq(label));
+    }
+    let moves = query
+        .load::<PromptLabelHistory>(&mut conn)
+        .map_err(|_| Status::internal("Failed to fetch prompt label history"))?;
+
+    let version_ids: Vec<i32> = moves
+        .iter()
+        .flat_map(|entry| [entry.previous_prompt_version_id, entry.prompt_version_id])
+        .flatten()
+        .collect();
+    let versions: HashMap<i32, String> = prompt_version::table
+        .filter(prompt_version::id.eq_any(&version_ids))
+        .select((prompt_version::id, prompt_version::version))
+        .load::<(i32, String)>(&mut conn)
+        .map_err(|_| Status::internal("Failed to fetch prompt versions"))?
+        .into_iter()
+        .collect();
+    let version_of = |id: Option<i32>| id.and_then(|id| versions.get(&id).cloned());
+
+    Ok(Response::new(GetPromptLabelHistoryResponse {
+        moves: moves
+            .into_iter()
+            .map(|entry| PromptLabelMove {
+                label: entry.label,
+                previous_version: version_of(entry.previous_prompt_version_id),
+                version: version_of(entry.prompt_version_id),
+                actor: entry.actor,
+                moved_at: Some(to_timestamp(entry.created_at)),
+            })
+            .collect(),
+    }))
+}
+
+/// Resolve a label of a prompt to the version it points to
+pub fn find_labeled_version(
+    conn: &mut PgConnection,
+    prompt_name: &str,
+    label: &str,
+) -> Result<Option<PromptVersion>, Status> {
+    prompt_label::table
+        .inner_join(prompt_version::table)
+        .filter(prompt_label::prompt_name.eq(prompt_name))
+        .filter(prompt_label::label.eq(label))
+        .select(PromptVersion::as_select())
+        .first::<PromptVersion>(conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to fetch prompt label"))
+}
+
+fn move_label(
+    conn: &mut PgConnection,
+    version: &PromptVersion,
+    label: &str,
+    actor: Option<String>,
+) -> QueryResult<PromptLabel> {
+    let now = Utc::now();
+    let mut existing = find_label_for_update(conn, &version.name, label)?;
+    let mut created = None;
+    if existing.is_none() {
+        // Concurrent first moves both miss the label: the one creating it second finds it created
+        // and moves it like any existing label
+        created = diesel::insert_into(prompt_label::table)
+            .values(&InsertablePromptLabel {
+                prompt_name: version.name.clone(),
+                label: label.to_string(),
+                prompt_version_id: version.id,
+                created_at: now,
+                updated_at: now,
+            })
+            .on_conflict((prompt_label::prompt_name, prompt_label::label))
+            .do_nothing()
+            .returning(prompt_label::all_columns)
+            .get_result::<PromptLabel>(conn)
+            .optional()?;
+        if created.is_none() {
+            existing = find_label_for_update(conn, &version.name, label)?;
+        }
+    }
+
+    let (stored, previous_prompt_version_id) = match (created, existing) {
+        (Some(created), _) => (created, None),
+        // Already pointing at the version, nothing moves
+        (None, Some(existing)) if existing.prompt_version_id == version.id => return Ok(existing),
+        (None, Some(existing)) => {
+            let updated = diesel::update(prompt_label::table.find(existing.id))
+                .set((
+                    prompt_label::prompt_version_id.eq(version.id),
+                    prompt_label::updated_at.eq(now),
+                ))
+                .returning(prompt_label::all_columns)
+                .get_result::<PromptLabel>(conn)?;
+            (updated, Some(existing.prompt_version_id))
+        }
+        // Created and removed again in the meantime
+        (None, None) => return Err(diesel::result::Error::NotFound),
+    };
+
+    DieselRepository::new(conn, prompt_label_history::table).create(
+        &InsertablePromptLabelHistory {
+            prompt_name: version.name.clone(),
+            label: label.to_string(),
+            previous_prompt_version_id,
+            prompt_version_id: Some(version.id),
+            actor,
+            created_at: now,
+        },
+    )?;
+
+    Ok(stored)
+}
+
+fn find_label_for_update(
+    conn: &mut PgConnection,
+    prompt_name: &str,
+    label: &str,
+) -> QueryResult<Option<PromptLabel>> {
+    prompt_label::table
+        .filter(prompt_label::prompt_name.eq(prompt_name))
+        .filter(prompt_label::label.eq(label))
+        .for_update()
+        .first::<PromptLabel>(conn)
+        .optional()
+}
+
+fn validate_label(label: &str) -> Result<(), Status> {
+    if label.is_empty() {
+        return Err(Status::invalid_argument("Missing label"));
+    }
+    if label.len() > MAX_LABEL_LENGTH {
+        return Err(Status::invalid_argument("Label is too long"));
+    }
+    if !label
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(Status::invalid_argument(
+            "Labels may only contain letters, digits, '-', '_' and '.'",
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_label() {
+        assert!(validate_label("production").is_ok());
+        assert!(validate_label("golden-2024.09_a").is_ok());
+        assert!(validate_label("").is_err());
+        assert!(validate_label("has space").is_err());
+        assert!(validate_label(&"a".repeat(MAX_LABEL_LENGTH + 1)).is_err());
+    }
+}
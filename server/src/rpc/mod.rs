@@ -1,13 +1,55 @@
-mod eval;
+// Handlers and their helpers fail with `tonic::Status`, which is larger than clippy likes
+#![allow(clippy::result_large_err)]
+
+mod annotation;
+mod dataset;
+pub mod eval;
+pub mod experiment;
+mod gate;
+mod history;
+mod label;
+pub mod online_eval;
+mod policy;
+pub mod prompt;
+mod scorer;
+pub mod span;
+mod webhook;
 
 use std::future::Future;
 use std::pin::Pin;
 
+use chrono::{DateTime, TimeZone, Utc};
 use tonic::transport;
 
 use ellmo_proto::ellmo::ellmo_service_server::{EllmoService, EllmoServiceServer};
 use ellmo_proto::ellmo::{
-    RecordEvalRequest, RecordEvalResponse, ReportSpanRequest, TestExecutionRequest,
+    AddDatasetCasesFromSpansRequest, AddDatasetCasesFromSpansResponse, AddDatasetCasesRequest,
+    AddDatasetCasesResponse, AppendEvalScoresRequest, AppendEvalScoresResponse,
+    AssignExperimentRequest, AssignExperimentResponse, ClaimAnnotationItemRequest,
+    ClaimAnnotationItemResponse, CompareEvalRunsRequest, CompareEvalRunsResponse,
+    CreateAnnotationQueueRequest, CreateAnnotationQueueResponse, CreateDatasetRequest,
+    CreateDatasetResponse, CreateExperimentRequest, CreateExperimentResponse,
+    CreateOnlineEvalRuleRequest, CreateOnlineEvalRuleResponse, CreateWebhookRequest,
+    CreateWebhookResponse, DeletePromptLabelRequest, DeleteWebhookRequest,
+    DiffPromptVersionsRequest, DiffPromptVersionsResponse, EnqueueAnnotationItemsRequest,
+    EnqueueAnnotationItemsResponse, ExportAnnotationsRequest, ExportAnnotationsResponse,
+    FinishEvalRunRequest, GetAnnotationAgreementRequest, GetAnnotationAgreementResponse,
+    GetEvalPolicyRequest, GetEvalPolicyResponse, GetEvalRunRequest, GetEvalRunResponse,
+    GetExperimentResultsRequest, GetExperimentResultsResponse, GetGatePolicyRequest,
+    GetGatePolicyResponse, GetOnlineEvalScoresRequest, GetOnlineEvalScoresResponse,
+    GetPromptLabelHistoryRequest, GetPromptLabelHistoryResponse, GetPromptRequest,
+    GetPromptResponse, ListDatasetCasesRequest, ListDatasetCasesResponse, ListEvalRunsRequest,
+    ListEvalRunsResponse, ListEvalsRequest, ListEvalsResponse, ListOnlineEvalRulesRequest,
+    ListOnlineEvalRulesResponse, ListPromptLabelsRequest, ListPromptLabelsResponse,
+    ListScorerPluginsRequest, ListScorerPluginsResponse, ListWebhookDeliveriesRequest,
+    ListWebhookDeliveriesResponse, ListWebhooksRequest, ListWebhooksResponse, RecordEvalRequest,
+    RecordEvalResponse, RecordSpanFeedbackRequest, RegisterPromptRequest, RegisterPromptResponse,
+    RegisterScorerPluginRequest, RegisterScorerPluginResponse, RemoveDatasetCasesRequest,
+    RemoveDatasetCasesResponse, RenderPromptRequest, RenderPromptResponse, ReportSpanRequest,
+    SetEvalPolicyRequest, SetEvalPolicyResponse, SetGatePolicyRequest, SetGatePolicyResponse,
+    SetOnlineEvalRuleEnabledRequest, SetOnlineEvalRuleEnabledResponse, SetPromptLabelRequest,
+    SetPromptLabelResponse, SnapshotDatasetRequest, SnapshotDatasetResponse, StartEvalRunRequest,
+    StartEvalRunResponse, SubmitAnnotationRequest, SubmitAnnotationResponse, TestExecutionRequest,
 };
 
 #[derive(Default)]
@@ -51,6 +93,321 @@ impl EllmoService for EllmoRpcDefinition {
     ) -> Result<tonic::Response<RecordEvalResponse>, tonic::Status> {
         eval::record_eval(request).await
     }
+
+    async fn start_eval_run(
+        &self,
+        request: tonic::Request<StartEvalRunRequest>,
+    ) -> Result<tonic::Response<StartEvalRunResponse>, tonic::Status> {
+        eval::stream::start_eval_run(request).await
+    }
+
+    async fn append_eval_scores(
+        &self,
+        request: tonic::Request<AppendEvalScoresRequest>,
+    ) -> Result<tonic::Response<AppendEvalScoresResponse>, tonic::Status> {
+        eval::stream::append_eval_scores(request).await
+    }
+
+    async fn finish_eval_run(
+        &self,
+        request: tonic::Request<FinishEvalRunRequest>,
+    ) -> Result<tonic::Response<RecordEvalResponse>, tonic::Status> {
+        eval::stream::finish_eval_run(request).await
+    }
+
+    async fn set_eval_policy(
+        &self,
+        request: tonic::Request<SetEvalPolicyRequest>,
+    ) -> Result<tonic::Response<SetEvalPolicyResponse>, tonic::Status> {
+        policy::set_eval_policy(request).await
+    }
+
+    async fn get_eval_policy(
+        &self,
+        request: tonic::Request<GetEvalPolicyRequest>,
+    ) -> Result<tonic::Response<GetEvalPolicyResponse>, tonic::Status> {
+        policy::get_eval_policy(request).await
+    }
+
+    async fn set_gate_policy(
+        &self,
+        request: tonic::Request<SetGatePolicyRequest>,
+    ) -> Result<tonic::Response<SetGatePolicyResponse>, tonic::Status> {
+        gate::set_gate_policy(request).await
+    }
+
+    async fn get_gate_policy(
+        &self,
+        request: tonic::Request<GetGatePolicyRequest>,
+    ) -> Result<tonic::Response<GetGatePolicyResponse>, tonic::Status> {
+        gate::get_gate_policy(request).await
+    }
+
+    async fn list_evals(
+        &self,
+        request: tonic::Request<ListEvalsRequest>,
+    ) -> Result<tonic::Response<ListEvalsResponse>, tonic::Status> {
+        history::list_evals(request).await
+    }
+
+    async fn list_eval_runs(
+        &self,
+        request: tonic::Request<ListEvalRunsRequest>,
+    ) -> Result<tonic::Response<ListEvalRunsResponse>, tonic::Status> {
+        history::list_eval_runs(request).await
+    }
+
+    async fn get_eval_run(
+        &self,
+        request: tonic::Request<GetEvalRunRequest>,
+    ) -> Result<tonic::Response<GetEvalRunResponse>, tonic::Status> {
+        history::get_eval_run(request).await
+    }
+
+    async fn compare_eval_runs(
+        &self,
+        request: tonic::Request<CompareEvalRunsRequest>,
+    ) -> Result<tonic::Response<CompareEvalRunsResponse>, tonic::Status> {
+        eval::compare::compare_eval_runs(request).await
+    }
+
+    async fn create_dataset(
+        &self,
+        request: tonic::Request<CreateDatasetRequest>,
+    ) -> Result<tonic::Response<CreateDatasetResponse>, tonic::Status> {
+        dataset::create_dataset(request).await
+    }
+
+    async fn add_dataset_cases(
+        &self,
+        request: tonic::Request<AddDatasetCasesRequest>,
+    ) -> Result<tonic::Response<AddDatasetCasesResponse>, tonic::Status> {
+        dataset::add_dataset_cases(request).await
+    }
+
+    async fn add_dataset_cases_from_spans(
+        &self,
+        request: tonic::Request<AddDatasetCasesFromSpansRequest>,
+    ) -> Result<tonic::Response<AddDatasetCasesFromSpansResponse>, tonic::Status> {
+        dataset::add_dataset_cases_from_spans(request).await
+    }
+
+    async fn remove_dataset_cases(
+        &self,
+        request: tonic::Request<RemoveDatasetCasesRequest>,
+    ) -> Result<tonic::Response<RemoveDatasetCasesResponse>, tonic::Status> {
+        dataset::remove_dataset_cases(request).await
+    }
+
+    async fn snapshot_dataset(
+        &self,
+        request: tonic::Request<SnapshotDatasetRequest>,
+    ) -> Result<tonic::Response<SnapshotDatasetResponse>, tonic::Status> {
+        dataset::snapshot_dataset(request).await
+    }
+
+    async fn list_dataset_cases(
+        &self,
+        request: tonic::Request<ListDatasetCasesRequest>,
+    ) -> Result<tonic::Response<ListDatasetCasesResponse>, tonic::Status> {
+        dataset::list_dataset_cases(request).await
+    }
+
+    async fn set_prompt_label(
+        &self,
+        request: tonic::Request<SetPromptLabelRequest>,
+    ) -> Result<tonic::Response<SetPromptLabelResponse>, tonic::Status> {
+        label::set_prompt_label(request).await
+    }
+
+    async fn delete_prompt_label(
+        &self,
+        request: tonic::Request<DeletePromptLabelRequest>,
+    ) -> Result<tonic::Response<()>, tonic::Status> {
+        label::delete_prompt_label(request).await
+    }
+
+    async fn list_prompt_labels(
+        &self,
+        request: tonic::Request<ListPromptLabelsRequest>,
+    ) -> Result<tonic::Response<ListPromptLabelsResponse>, tonic::Status> {
+        label::list_prompt_labels(request).await
+    }
+
+    async fn get_prompt_label_history(
+        &self,
+        request: tonic::Request<GetPromptLabelHistoryRequest>,
+    ) -> Result<tonic::Response<GetPromptLabelHistoryResponse>, tonic::Status> {
+        label::get_prompt_label_history(request).await
+    }
+
+    async fn create_webhook(
+        &self,
+        request: tonic::Request<CreateWebhookRequest>,
+    ) -> Result<tonic::Response<CreateWebhookResponse>, tonic::Status> {
+        webhook::create_webhook(request).await
+    }
+
+    async fn delete_webhook(
+        &self,
+        request: tonic::Request<DeleteWebhookRequest>,
+    ) -> Result<tonic::Response<()>, tonic::Status> {
+        webhook::delete_webhook(request).await
+    }
+
+    async fn list_webhooks(
+        &self,
+        request: tonic::Request<ListWebhooksRequest>,
+    ) -> Result<tonic::Response<ListWebhooksResponse>, tonic::Status> {
+        webhook::list_webhooks(request).await
+    }
+
+    async fn list_webhook_deliveries(
+        &self,
+        request: tonic::Request<ListWebhookDeliveriesRequest>,
+    ) -> Result<tonic::Response<ListWebhookDeliveriesResponse>, tonic::Status> {
+        webhook::list_webhook_deliveries(request).await
+    }
+
+    async fn register_scorer_plugin(
+        &self,
+        request: tonic::Request<RegisterScorerPluginRequest>,
+    ) -> Result<tonic::Response<RegisterScorerPluginResponse>, tonic::Status> {
+        scorer::register_scorer_plugin(request).await
+    }
+
+    async fn list_scorer_plugins(
+        &self,
+        request: tonic::Request<ListScorerPluginsRequest>,
+    ) -> Result<tonic::Response<ListScorerPluginsResponse>, tonic::Status> {
+        scorer::list_scorer_plugins(request).await
+    }
+
+    async fn register_prompt(
+        &self,
+        request: tonic::Request<RegisterPromptRequest>,
+    ) -> Result<tonic::Response<RegisterPromptResponse>, tonic::Status> {
+        prompt::register_prompt(request).await
+    }
+
+    async fn get_prompt(
+        &self,
+        request: tonic::Request<GetPromptRequest>,
+    ) -> Result<tonic::Response<GetPromptResponse>, tonic::Status> {
+        prompt::get_prompt(request).await
+    }
+
+    async fn diff_prompt_versions(
+        &self,
+        request: tonic::Request<DiffPromptVersionsRequest>,
+    ) -> Result<tonic::Response<DiffPromptVersionsResponse>, tonic::Status> {
+        prompt::diff_prompt_versions(request).await
+    }
+
+    async fn render_prompt(
+        &self,
+        request: tonic::Request<RenderPromptRequest>,
+    ) -> Result<tonic::Response<RenderPromptResponse>, tonic::Status> {
+        prompt::render_prompt(request).await
+    }
+
+    async fn record_span_feedback(
+        &self,
+        request: tonic::Request<RecordSpanFeedbackRequest>,
+    ) -> Result<tonic::Response<()>, tonic::Status> {
+        span::record_span_feedback(request).await
+    }
+
+    async fn create_experiment(
+        &self,
+        request: tonic::Request<CreateExperimentRequest>,
+    ) -> Result<tonic::Response<CreateExperimentResponse>, tonic::Status> {
+        experiment::create_experiment(request).await
+    }
+
+    async fn assign_experiment(
+        &self,
+        request: tonic::Request<AssignExperimentRequest>,
+    ) -> Result<tonic::Response<AssignExperimentResponse>, tonic::Status> {
+        experiment::assign_experiment(request).await
+    }
+
+    async fn get_experiment_results(
+        &self,
+        request: tonic::Request<GetExperimentResultsRequest>,
+    ) -> Result<tonic::Response<GetExperimentResultsResponse>, tonic::Status> {
+        experiment::get_experiment_results(request).await
+    }
+
+    async fn create_online_eval_rule(
+        &self,
+        request: tonic::Request<CreateOnlineEvalRuleRequest>,
+    ) -> Result<tonic::Response<CreateOnlineEvalRuleResponse>, tonic::Status> {
+        online_eval::create_online_eval_rule(request).await
+    }
+
+    async fn list_online_eval_rules(
+        &self,
+        request: tonic::Request<ListOnlineEvalRulesRequest>,
+    ) -> Result<tonic::Response<ListOnlineEvalRulesResponse>, tonic::Status> {
+        online_eval::list_online_eval_rules(request).await
+    }
+
+    async fn set_online_eval_rule_enabled(
+        &self,
+        request: tonic::Request<SetOnlineEvalRuleEnabledRequest>,
+    ) -> Result<tonic::Response<SetOnlineEvalRuleEnabledResponse>, tonic::Status> {
+        online_eval::set_online_eval_rule_enabled(request).await
+    }
+
+    async fn get_online_eval_scores(
+        &self,
+        request: tonic::Request<GetOnlineEvalScoresRequest>,
+    ) -> Result<tonic::Response<GetOnlineEvalScoresResponse>, tonic::Status> {
+        online_eval::get_online_eval_scores(request).await
+    }
+
+    async fn create_annotation_queue(
+        &self,
+        request: tonic::Request<CreateAnnotationQueueRequest>,
+    ) -> Result<tonic::Response<CreateAnnotationQueueResponse>, tonic::Status> {
+        annotation::create_annotation_queue(request).await
+    }
+
+    async fn enqueue_annotation_items(
+        &self,
+        request: tonic::Request<EnqueueAnnotationItemsRequest>,
+    ) -> Result<tonic::Response<EnqueueAnnotationItemsResponse>, tonic::Status> {
+        annotation::enqueue_annotation_items(request).await
+    }
+
+    async fn claim_annotation_item(
+        &self,
+        request: tonic::Request<ClaimAnnotationItemRequest>,
+    ) -> Result<tonic::Response<ClaimAnnotationItemResponse>, tonic::Status> {
+        annotation::claim_annotation_item(request).await
+    }
+
+    async fn submit_annotation(
+        &self,
+        request: tonic::Request<SubmitAnnotationRequest>,
+    ) -> Result<tonic::Response<SubmitAnnotationResponse>, tonic::Status> {
+        annotation::submit_annotation(request).await
+    }
+
+    async fn get_annotation_agreement(
+        &self,
+        request: tonic::Request<GetAnnotationAgreementRequest>,
+    ) -> Result<tonic::Response<GetAnnotationAgreementResponse>, tonic::Status> {
+        annotation::get_annotation_agreement(request).await
+    }
+
+    async fn export_annotations(
+        &self,
+        request: tonic::Request<ExportAnnotationsRequest>,
+    ) -> Result<tonic::Response<ExportAnnotationsResponse>, tonic::Status> {
+        annotation::export_annotations(request).await
+    }
 }
 
 pub struct RpcServer {
@@ -74,3 +431,16 @@ impl RpcServer {
         Ok(())
     }
 }
+
+fn to_timestamp(time: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn from_timestamp(timestamp: prost_types::Timestamp) -> Result<DateTime<Utc>, tonic::Status> {
+    Utc.timestamp_opt(timestamp.seconds, timestamp.nanos.max(0) as u32)
+        .single()
+        .ok_or_else(|| tonic::Status::invalid_argument("Invalid timestamp"))
+}
@@ -0,0 +1,232 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Double, Nullable, Text, Timestamptz};
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::online_eval_rule::{InsertableOnlineEvalRule, OnlineEvalRule},
+    schema::online_eval_rule,
+};
+use ellmo_proto::ellmo::{
+    CreateOnlineEvalRuleRequest, CreateOnlineEvalRuleResponse, GetOnlineEvalScoresRequest,
+    GetOnlineEvalScoresResponse, ListOnlineEvalRulesRequest, ListOnlineEvalRulesResponse,
+    OnlineEvalInterval, OnlineEvalRule as RuleMessage, OnlineEvalScoreBucket,
+    SetOnlineEvalRuleEnabledRequest, SetOnlineEvalRuleEnabledResponse,
+};
+
+use super::eval::metadata::non_empty;
+use super::eval::scoring::{convert_spec, prepare_scorers, RunScorers};
+use super::{from_timestamp, to_timestamp};
+use crate::scorer::ScorerSpec;
+
+/// Create a rule scoring a sample of the spans ingested from now on
+pub async fn create_online_eval_rule(
+    request: Request<CreateOnlineEvalRuleRequest>,
+) -> Result<Response<CreateOnlineEvalRuleResponse>, Status> {
+    let rule = request
+        .into_inner()
+        .rule
+        .ok_or_else(|| Status::invalid_argument("Missing rule"))?;
+    if rule.name.is_empty() {
+        return Err(Status::invalid_argument("Missing rule name"));
+    }
+    if !(rule.sample_rate > 0.0 && rule.sample_rate <= 1.0) {
+        return Err(Status::invalid_argument(
+            "Sample rate must be greater than 0 and at most 1",
+        ));
+    }
+    let attributes = if rule.attributes.is_empty() {
+        serde_json::json!({})
+    } else {
+        serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&rule.attributes)
+            .map(serde_json::Value::Object)
+            .map_err(|_| Status::invalid_argument("Attributes must be a JSON object"))?
+    };
+    if rule.scorers.is_empty() {
+        return Err(Status::invalid_argument("A rule needs at least one scorer"));
+    }
+
+    let mut conn = establish_connection();
+    let specs = prepare_scorers(&mut conn, rule.scorers)?;
+    if let Some(metric) = RunScorers::build(&mut conn, &specs)?.expected_metric() {
+        return Err(Status::invalid_argument(format!(
+            "Scorer {} needs an expected output, which spans do not have",
+            metric
+        )));
+    }
+    let scorers =
+        serde_json::to_value(&specs).map_err(|_| Status::internal("Failed to encode scorers"))?;
+
+    let created = diesel::insert_into(online_eval_rule::table)
+        .values(&InsertableOnlineEvalRule {
+            name: rule.name.clone(),
+            operation_name: non_empty(rule.operation_name),
+            attributes,
+            sample_rate: rule.sample_rate,
+            scorers,
+            enabled: true,
+            created_at: Utc::now(),
+        })
+        .on_conflict(online_eval_rule::name)
+        .do_nothing()
+        .returning(online_eval_rule::all_columns)
+        .get_result::<OnlineEvalRule>(&mut conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to create online eval rule"))?
+        .ok_or_else(|| {
+            Status::already_exists(format!("Online eval rule {} already exists", rule.name))
+        })?;
+
+    Ok(Response::new(CreateOnlineEvalRuleResponse {
+        rule: Some(convert_rule(created)?),
+    }))
+}
+
+pub async fn list_online_eval_rules(
+    _request: Request<ListOnlineEvalRulesRequest>,
+) -> Result<Response<ListOnlineEvalRulesResponse>, Status> {
+    let mut conn = establish_connection();
+    let rules = online_eval_rule::table
+        .order(online_eval_rule::id.asc())
+        .load::<OnlineEvalRule>(&mut conn)
+        .map_err(|_| Status::internal("Failed to fetch online eval rules"))?;
+
+    Ok(Response::new(ListOnlineEvalRulesResponse {
+        rules: rules
+            .into_iter()
+            .map(convert_rule)
+            .collect::<Result<_, _>>()?,
+    }))
+}
+
+/// Pause or resume scoring newly ingested spans with a rule. Queued spans of a paused rule are
+/// not scored either.
+pub async fn set_online_eval_rule_enabled(
+    request: Request<SetOnlineEvalRuleEnabledRequest>,
+) -> Result<Response<SetOnlineEvalRuleEnabledResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let updated = diesel::update(online_eval_rule::table)
+        .filter(online_eval_rule::name.eq(&message.name))
+        .set(online_eval_rule::enabled.eq(message.enabled))
+        .returning(online_eval_rule::all_columns)
+        .get_result::<OnlineEvalRule>(&mut conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to update online eval rule"))?
+        .ok_or_else(|| Status::not_found("Online eval rule not found"))?;
+
+    Ok(Response::new(SetOnlineEvalRuleEnabledResponse {
+        rule: Some(convert_rule(updated)?),
+    }))
+}
+
+/// Online scores of the versions of a prompt, aggregated over time buckets
+pub async fn get_online_eval_scores(
+    request: Request<GetOnlineEvalScoresRequest>,
+) -> Result<Response<GetOnlineEvalScoresResponse>, Status> {
+    let message = request.into_inner();
+    let filter = ScoreFilter {
+        rule: non_empty(message.rule.clone()),
+        metric: non_empty(message.metric.clone()),
+        interval: message.interval(),
+        since: message.since.map(from_timestamp).transpose()?,
+        until: message.until.map(from_timestamp).transpose()?,
+    };
+
+    let mut conn = establish_connection();
+    let buckets = online_scores(&mut conn, &message.prompt_name, &filter)?;
+
+    Ok(Response::new(GetOnlineEvalScoresResponse { buckets }))
+}
+
+/// Filters applied when aggregating online scores. Time bounds apply to the start of the spans.
+pub struct ScoreFilter {
+    pub rule: Option<String>,
+    pub metric: Option<String>,
+    pub interval: OnlineEvalInterval,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(QueryableByName)]
+struct ScoreBucketRow {
+    #[diesel(sql_type = Text)]
+    version: String,
+    #[diesel(sql_type = Text)]
+    metric: String,
+    #[diesel(sql_type = Timestamptz)]
+    bucket_start: DateTime<Utc>,
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+    #[diesel(sql_type = Double)]
+    mean: f64,
+}
+
+/// Mean online score of every metric for each version of a prompt and time bucket, ordered by
+/// time, version and metric
+pub fn online_scores(
+    conn: &mut PgConnection,
+    prompt_name: &str,
+    filter: &ScoreFilter,
+) -> Result<Vec<OnlineEvalScoreBucket>, Status> {
+    if prompt_name.is_empty() {
+        return Err(Status::invalid_argument("Missing prompt name"));
+    }
+    let interval = match filter.interval {
+        OnlineEvalInterval::OnlineEvalHour => "hour",
+        OnlineEvalInterval::OnlineEvalDay => "day",
+    };
+
+    let rows = diesel::sql_query(
+        "SELECT pv.version, ss.metric, \
+                date_trunc($2, s.ts_start AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS bucket_start, \
+                COUNT(*) AS count, AVG(ss.score) AS mean \
+         FROM span_score ss \
+         JOIN span s ON s.id = ss.span_id \
+         JOIN prompt_version pv ON pv.id = s.prompt_version_id \
+         JOIN online_eval_rule r ON r.id = ss.online_eval_rule_id \
+         WHERE pv.name = $1 \
+           AND ($3::text IS NULL OR r.name = $3) \
+           AND ($4::text IS NULL OR ss.metric = $4) \
+           AND ($5::timestamptz IS NULL OR s.ts_start >= $5) \
+           AND ($6::timestamptz IS NULL OR s.ts_start < $6) \
+         GROUP BY pv.version, ss.metric, bucket_start \
+         ORDER BY bucket_start, pv.version, ss.metric",
+    )
+    .bind::<Text, _>(prompt_name)
+    .bind::<Text, _>(interval)
+    .bind::<Nullable<Text>, _>(filter.rule.as_deref())
+    .bind::<Nullable<Text>, _>(filter.metric.as_deref())
+    .bind::<Nullable<Timestamptz>, _>(filter.since)
+    .bind::<Nullable<Timestamptz>, _>(filter.until)
+    .load::<ScoreBucketRow>(conn)
+    .map_err(|_| Status::internal("Failed to fetch online scores"))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| OnlineEvalScoreBucket {
+            version: row.version,
+            metric: row.metric,
+            bucket_start: Some(to_timestamp(row.bucket_start)),
+            count: row.count as u32,
+            mean: row.mean,
+        })
+        .collect())
+}
+
+fn convert_rule(rule: OnlineEvalRule) -> Result<RuleMessage, Status> {
+    let specs: Vec<ScorerSpec> = serde_json::from_value(rule.scorers)
+        .map_err(|_| Status::internal("Failed to load scorers of online eval rule"))?;
+
+    Ok(RuleMessage {
+        name: rule.name,
+        operation_name: rule.operation_name,
+        attributes: rule.attributes.to_string(),
+        sample_rate: rule.sample_rate,
+        scorers: specs.iter().map(convert_spec).collect(),
+        enabled: rule.enabled,
+        created_at: Some(to_timestamp(rule.created_at)),
+    })
+}
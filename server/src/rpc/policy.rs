@@ -0,0 +1,199 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        eval_policy::{EvalPolicy, InsertableEvalPolicy},
+        eval_score::DEFAULT_METRIC,
+    },
+    schema::eval_policy,
+};
+use ellmo_proto::ellmo::{
+    GetEvalPolicyRequest, GetEvalPolicyResponse, ScoreDirection, SetEvalPolicyRequest,
+    SetEvalPolicyResponse,
+};
+
+/// Thresholds and direction used when comparing a metric between two runs of an eval
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonPolicy {
+    pub individual_threshold: f32,
+    pub mean_threshold: f32,
+    pub consistency_threshold: f32,
+    pub higher_is_better: bool,
+    pub min_sample_count: usize,
+}
+
+impl Default for ComparisonPolicy {
+    fn default() -> Self {
+        ComparisonPolicy {
+            individual_threshold: 0.10,
+            mean_threshold: 0.01,
+            consistency_threshold: 0.7,
+            higher_is_better: true,
+            min_sample_count: 1,
+        }
+    }
+}
+
+impl From<EvalPolicy> for ComparisonPolicy {
+    fn from(policy: EvalPolicy) -> Self {
+        ComparisonPolicy {
+            individual_threshold: policy.individual_threshold,
+            mean_threshold: policy.mean_threshold,
+            consistency_threshold: policy.consistency_threshold,
+            higher_is_better: policy.higher_is_better,
+            min_sample_count: policy.min_sample_count.max(0) as usize,
+        }
+    }
+}
+
+impl ComparisonPolicy {
+    fn to_proto(&self, eval_name: &str, metric: &str) -> ellmo_proto::ellmo::EvalPolicy {
+        let direction = if self.higher_is_better {
+            ScoreDirection::HigherIsBetter
+        } else {
+            ScoreDirection::LowerIsBetter
+        };
+
+        ellmo_proto::ellmo::EvalPolicy {
+            eval_name: eval_name.to_string(),
+            individual_threshold: self.individual_threshold,
+            mean_threshold: self.mean_threshold,
+            consistency_threshold: self.consistency_threshold,
+            direction: direction.into(),
+            min_sample_count: self.min_sample_count as u32,
+            metric: metric.to_string(),
+        }
+    }
+}
+
+/// Load the stored policy of a metric of an eval, falling back to the defaults
+pub fn load_policy(
+    conn: &mut PgConnection,
+    eval_name: &str,
+    metric: &str,
+) -> Result<ComparisonPolicy, Status> {
+    Ok(find_policy(conn, eval_name, metric)?
+        .map(ComparisonPolicy::from)
+        .unwrap_or_default())
+}
+
+fn find_policy(
+    conn: &mut PgConnection,
+    eval_name: &str,
+    metric: &str,
+) -> Result<Option<EvalPolicy>, Status> {
+    eval_policy::table
+        .filter(eval_policy::eval_name.eq(eval_name))
+        .filter(eval_policy::metric.eq(metric))
+        .first::<EvalPolicy>(conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to fetch eval policy"))
+}
+
+/// Create or replace the comparison policy of a metric of an eval
+pub async fn set_eval_policy(
+    request: Request<SetEvalPolicyRequest>,
+) -> Result<Response<SetEvalPolicyResponse>, Status> {
+    let policy = request
+        .into_inner()
+        .policy
+        .ok_or_else(|| Status::invalid_argument("Missing policy"))?;
+
+    validate_policy(&policy)?;
+    let min_sample_count = i32::try_from(policy.min_sample_count)
+        .map_err(|_| Status::invalid_argument("Minimum sample count is too large"))?;
+
+    let metric = if policy.metric.is_empty() {
+        DEFAULT_METRIC.to_string()
+    } else {
+        policy.metric.clone()
+    };
+
+    let now = Utc::now();
+    let insertable = InsertableEvalPolicy {
+        eval_name: policy.eval_name.clone(),
+        individual_threshold: policy.individual_threshold,
+        mean_threshold: policy.mean_threshold,
+        consistency_threshold: policy.consistency_threshold,
+        higher_is_better: policy.direction() == ScoreDirection::HigherIsBetter,
+        min_sample_count,
+        created_at: now,
+        updated_at: now,
+        metric,
+    };
+
+    let mut conn = establish_connection();
+    let stored = diesel::insert_into(eval_policy::table)
+        .values(&insertable)
+        .on_conflict((eval_policy::eval_name, eval_policy::metric))
+        .do_update()
+        .set((
+            eval_policy::individual_threshold.eq(insertable.individual_threshold),
+            eval_policy::mean_threshold.eq(insertable.mean_threshold),
+            eval_policy::consistency_threshold.eq(insertable.consistency_threshold),
+            eval_policy::higher_is_better.eq(insertable.higher_is_better),
+            eval_policy::min_sample_count.eq(insertable.min_sample_count),
+            eval_policy::updated_at.eq(now),
+        ))
+        .returning(eval_policy::all_columns)
+        .get_result::<EvalPolicy>(&mut conn)
+        .map_err(|_| Status::internal("Failed to store eval policy"))?;
+
+    let eval_name = stored.eval_name.clone();
+    let metric = stored.metric.clone();
+    Ok(Response::new(SetEvalPolicyResponse {
+        policy: Some(ComparisonPolicy::from(stored).to_proto(&eval_name, &metric)),
+    }))
+}
+
+/// Fetch the comparison policy applied to a metric of an eval
+pub async fn get_eval_policy(
+    request: Request<GetEvalPolicyRequest>,
+) -> Result<Response<GetEvalPolicyResponse>, Status> {
+    let message = request.into_inner();
+    let eval_name = message.eval_name;
+    if eval_name.is_empty() {
+        return Err(Status::invalid_argument("Missing eval name"));
+    }
+    let metric = message
+        .metric
+        .filter(|metric| !metric.is_empty())
+        .unwrap_or_else(|| DEFAULT_METRIC.to_string());
+
+    let mut conn = establish_connection();
+    let stored = find_policy(&mut conn, &eval_name, &metric)?;
+    let is_default = stored.is_none();
+    let policy = stored.map(ComparisonPolicy::from).unwrap_or_default();
+
+    Ok(Response::new(GetEvalPolicyResponse {
+        policy: Some(policy.to_proto(&eval_name, &metric)),
+        is_default,
+    }))
+}
+
+fn validate_policy(policy: &ellmo_proto::ellmo::EvalPolicy) -> Result<(), Status> {
+    if policy.eval_name.is_empty() {
+        return Err(Status::invalid_argument("Missing eval name"));
+    }
+    if !policy.individual_threshold.is_finite() || !policy.mean_threshold.is_finite() {
+        return Err(Status::invalid_argument(
+            "Thresholds must be finite numbers",
+        ));
+    }
+    if policy.individual_threshold < 0.0 || policy.mean_threshold < 0.0 {
+        return Err(Status::invalid_argument("Thresholds must not be negative"));
+    }
+    if !(0.0..=1.0).contains(&policy.consistency_threshold) {
+        return Err(Status::invalid_argument(
+            "Consistency threshold must be between 0 and 1",
+        ));
+    }
+    if ScoreDirection::try_from(policy.direction).is_err() {
+        return Err(Status::invalid_argument("Unknown score direction"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,488 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use serde_json::{json, Map, Value};
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        prompt_version::{InsertablePromptVersion, PromptVersion},
+        repository::DieselRepository,
+        span::InsertableSpan,
+    },
+    schema::prompt_version,
+};
+use ellmo_proto::ellmo::{
+    DiffChange, DiffLine, DiffPromptVersionsRequest, DiffPromptVersionsResponse, DiffSpan,
+    GetPromptRequest, GetPromptResponse, Prompt, PromptDiff, PromptDiffSummary, PromptMessage,
+    PromptSectionDiff, PromptSettingChange, PromptTemplate, RegisterPromptRequest,
+    RegisterPromptResponse, RenderPromptRequest, RenderPromptResponse, VersionedPrompt,
+};
+
+use super::eval::metadata::non_empty;
+use super::label::find_labeled_version;
+use super::span::record_span;
+use super::to_timestamp;
+use crate::prompt::diff::{self, Change, TemplateDiff};
+use crate::prompt::render::{self, Rendered};
+use crate::prompt::{Message, Template};
+
+/// Outcome of registering the template of a version
+enum Registration {
+    Created(PromptVersion),
+    Unchanged(PromptVersion),
+    Conflict,
+}
+
+/// Register the template of a prompt version. Registering the same template again is a no-op,
+/// registering another template under an existing version is rejected.
+pub async fn register_prompt(
+    request: Request<RegisterPromptRequest>,
+) -> Result<Response<RegisterPromptResponse>, Status> {
+    let message = request.into_inner();
+    let prompt = message
+        .prompt
+        .ok_or_else(|| Status::invalid_argument("Missing prompt"))?;
+    if prompt.name.is_empty() {
+        return Err(Status::invalid_argument("Missing prompt name"));
+    }
+    if prompt.version.is_empty() {
+        return Err(Status::invalid_argument("Missing prompt version"));
+    }
+
+    let template = convert_template(
+        message
+            .template
+            .ok_or_else(|| Status::invalid_argument("Missing template"))?,
+    )?;
+    template
+        .validate()
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+    let content_hash = template.content_hash();
+    let stored = serde_json::to_value(&template)
+        .map_err(|_| Status::internal("Failed to encode template"))?;
+
+    let mut conn = establish_connection();
+    let registration = conn
+        .transaction(|conn| register(conn, &prompt, &stored, &content_hash))
+        .map_err(|_: diesel::result::Error| Status::internal("Failed to register prompt"))?;
+
+    let (version, created) = match registration {
+        Registration::Created(version) => (version, true),
+        Registration::Unchanged(version) => (version, false),
+        Registration::Conflict => {
+            return Err(Status::already_exists(format!(
+                "Version {} of prompt {} is already registered with another template",
+                prompt.version, prompt.name
+            )))
+        }
+    };
+
+    Ok(Response::new(RegisterPromptResponse {
+        prompt: convert_prompt(version)?,
+        created,
+    }))
+}
+
+/// Fetch the template of a prompt version, given by version or by label
+pub async fn get_prompt(
+    request: Request<GetPromptRequest>,
+) -> Result<Response<GetPromptResponse>, Status> {
+    let message = request.into_inner();
+    if message.name.is_empty() {
+        return Err(Status::invalid_argument("Missing prompt name"));
+    }
+
+    let mut conn = establish_connection();
+    let version = find_version(
+        &mut conn,
+        &message.name,
+        non_empty(message.version),
+        non_empty(message.label),
+    )?;
+
+    let prompt = convert_prompt(version)?
+        .ok_or_else(|| Status::not_found("Prompt version has no registered template"))?;
+
+    Ok(Response::new(GetPromptResponse {
+        prompt: Some(prompt),
+    }))
+}
+
+/// Compare the templates of two versions of a prompt
+pub async fn diff_prompt_versions(
+    request: Request<DiffPromptVersionsRequest>,
+) -> Result<Response<DiffPromptVersionsResponse>, Status> {
+    let message = request.into_inner();
+
+    let mut conn = establish_connection();
+    let diff = diff_versions(
+        &mut conn,
+        &message.name,
+        &message.base_version,
+        &message.version,
+    )?;
+
+    Ok(Response::new(DiffPromptVersionsResponse {
+        diff: Some(diff),
+    }))
+}
+
+/// Render a prompt version with a set of variables, recording the render as a span
+pub async fn render_prompt(
+    request: Request<RenderPromptRequest>,
+) -> Result<Response<RenderPromptResponse>, Status> {
+    let message = request.into_inner();
+    let variables = if message.variables.is_empty() {
+        Map::new()
+    } else {
+        serde_json::from_str::<Map<String, Value>>(&message.variables)
+            .map_err(|_| Status::invalid_argument("Variables must be a JSON object"))?
+    };
+
+    let mut conn = establish_connection();
+    let rendered = render_version(
+        &mut conn,
+        &message.name,
+        non_empty(message.version),
+        non_empty(message.label),
+        &variables,
+        non_empty(message.parent_span_id).as_deref(),
+    )?;
+
+    Ok(Response::new(rendered))
+}
+
+/// Render the version of a prompt given by version or label. The render is recorded as a span
+/// linked to the version, with the variables as input and the rendered prompt as output.
+pub fn render_version(
+    conn: &mut PgConnection,
+    name: &str,
+    version: Option<String>,
+    label: Option<String>,
+    variables: &Map<String, Value>,
+    parent_span_id: Option<&str>,
+) -> Result<RenderPromptResponse, Status> {
+    if name.is_empty() {
+        return Err(Status::invalid_argument("Missing prompt name"));
+    }
+
+    let ts_start = Utc::now();
+    let stored = find_version(conn, name, version, label.clone())?;
+    let template = load_template(&stored)?
+        .ok_or_else(|| Status::not_found("Prompt version has no registered template"))?;
+    let rendered = render::render(&template, variables)
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+    let mut attributes = json!({
+        "prompt.name": stored.name,
+        "prompt.version": stored.version,
+    });
+    if let Some(label) = &label {
+        attributes["prompt.label"] = json!(label);
+    }
+    let span_uuid = record_span(
+        conn,
+        InsertableSpan {
+            ts_start,
+            ts_end: Utc::now(),
+            operation_name: "prompt.render".to_string(),
+            parent_span_id: None,
+            external_uuid: None,
+            attributes,
+            input: Some(Value::Object(variables.clone())),
+            output: Some(rendered_json(&rendered)),
+            prompt_version_id: Some(stored.id),
+        },
+        parent_span_id,
+    )?;
+
+    Ok(RenderPromptResponse {
+        prompt: Some(VersionedPrompt {
+            name: stored.name,
+            version: stored.version,
+        }),
+        text: rendered.text,
+        messages: rendered
+            .messages
+            .into_iter()
+            .map(|message| PromptMessage {
+                role: message.role,
+                content: message.content,
+            })
+            .collect(),
+        model: template.model,
+        parameters: template.parameters.map(|parameters| parameters.to_string()),
+        span_id: span_uuid.to_string(),
+    })
+}
+
+fn rendered_json(rendered: &Rendered) -> Value {
+    match &rendered.text {
+        Some(text) => json!({ "text": text }),
+        None => json!({
+            "messages": rendered
+                .messages
+                .iter()
+                .map(|message| json!({ "role": message.role, "content": message.content }))
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
+
+/// Diff between the templates of two versions of a prompt, both of which must have one
+pub fn diff_versions(
+    conn: &mut PgConnection,
+    name: &str,
+    base_version: &str,
+    version: &str,
+) -> Result<PromptDiff, Status> {
+    if name.is_empty() {
+        return Err(Status::invalid_argument("Missing prompt name"));
+    }
+    if base_version.is_empty() || version.is_empty() {
+        return Err(Status::invalid_argument(
+            "Both a base version and a version are required",
+        ));
+    }
+
+    let mut load = |version: &str| {
+        let stored = find_version(conn, name, Some(version.to_string()), None)?;
+        load_template(&stored)?.ok_or_else(|| {
+            Status::not_found(format!(
+                "Version {} of prompt {} has no registered template",
+                version, name
+            ))
+        })
+    };
+    let base_template = load(base_version)?;
+    let template = load(version)?;
+
+    let diff = diff::diff(&base_template, &template);
+    Ok(PromptDiff {
+        summary: Some(convert_summary(base_version, version, &diff)),
+        sections: diff
+            .sections
+            .into_iter()
+            .map(|section| PromptSectionDiff {
+                section: section.section,
+                lines: section
+                    .lines
+                    .into_iter()
+                    .map(|line| DiffLine {
+                        change: match line.change {
+                            Change::Equal => DiffChange::DiffEqual,
+                            Change::Insert => DiffChange::DiffInsert,
+                            Change::Delete => DiffChange::DiffDelete,
+                        }
+                        .into(),
+                        text: line.text(),
+                        spans: line
+                            .spans
+                            .into_iter()
+                            .map(|span| DiffSpan {
+                                text: span.text,
+                                changed: span.changed,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    })
+}
+
+/// Summary of the changes between two versions of a prompt, if both have a template
+pub fn summarize_changes(
+    conn: &mut PgConnection,
+    name: &str,
+    base_version: &str,
+    version: &str,
+) -> Result<Option<PromptDiffSummary>, Status> {
+    let mut load = |version: &str| {
+        DieselRepository::new(conn, prompt_version::table)
+            .find_by_version(name, version)
+            .map_err(|_| Status::internal("Failed to fetch prompt version"))?
+            .map_or(Ok(None), |stored| load_template(&stored))
+    };
+    let (Some(base_template), Some(template)) = (load(base_version)?, load(version)?) else {
+        return Ok(None);
+    };
+
+    let diff = diff::diff(&base_template, &template);
+    Ok(Some(convert_summary(base_version, version, &diff)))
+}
+
+fn convert_summary(base_version: &str, version: &str, diff: &TemplateDiff) -> PromptDiffSummary {
+    PromptDiffSummary {
+        base_version: base_version.to_string(),
+        version: version.to_string(),
+        identical: diff.is_identical(),
+        lines_added: diff.lines_added() as u32,
+        lines_removed: diff.lines_removed() as u32,
+        changed_sections: diff.changed_sections(),
+        setting_changes: diff
+            .setting_changes
+            .iter()
+            .map(|change| PromptSettingChange {
+                name: change.name.clone(),
+                old_value: change.old_value.as_ref().map(|value| value.to_string()),
+                new_value: change.new_value.as_ref().map(|value| value.to_string()),
+            })
+            .collect(),
+        description: diff.summary(),
+    }
+}
+
+/// Version of a prompt given by exactly one of a version or a label
+fn find_version(
+    conn: &mut PgConnection,
+    name: &str,
+    version: Option<String>,
+    label: Option<String>,
+) -> Result<PromptVersion, Status> {
+    match (version, label) {
+        (Some(version), None) => DieselRepository::new(conn, prompt_version::table)
+            .find_by_version(name, &version)
+            .map_err(|_| Status::internal("Failed to fetch prompt version"))?
+            .ok_or_else(|| Status::not_found("Prompt version not found")),
+        (None, Some(label)) => find_labeled_version(conn, name, &label)?
+            .ok_or_else(|| Status::not_found("Prompt label not found")),
+        _ => Err(Status::invalid_argument(
+            "Exactly one of version or label is required",
+        )),
+    }
+}
+
+fn register(
+    conn: &mut PgConnection,
+    prompt: &VersionedPrompt,
+    template: &serde_json::Value,
+    content_hash: &str,
+) -> QueryResult<Registration> {
+    let created = diesel::insert_into(prompt_version::table)
+        .values(&InsertablePromptVersion {
+            name: prompt.name.clone(),
+            version: prompt.version.clone(),
+            created_at: Utc::now(),
+            template: Some(template.clone()),
+            content_hash: Some(content_hash.to_string()),
+        })
+        .on_conflict((prompt_version::name, prompt_version::version))
+        .do_nothing()
+        .returning(prompt_version::all_columns)
+        .get_result::<PromptVersion>(conn)
+        .optional()?;
+    if let Some(created) = created {
+        return Ok(Registration::Created(created));
+    }
+
+    let existing = prompt_version::table
+        .filter(prompt_version::name.eq(&prompt.name))
+        .filter(prompt_version::version.eq(&prompt.version))
+        .for_update()
+        .first::<PromptVersion>(conn)?;
+
+    match &existing.content_hash {
+        Some(existing_hash) if existing_hash == content_hash => {
+            Ok(Registration::Unchanged(existing))
+        }
+        Some(_) => Ok(Registration::Conflict),
+        // Versions first seen in eval runs get their template attached once
+        None => diesel::update(prompt_version::table.find(existing.id))
+            .set((
+                prompt_version::template.eq(template),
+                prompt_version::content_hash.eq(content_hash),
+            ))
+            .returning(prompt_version::all_columns)
+            .get_result::<PromptVersion>(conn)
+            .map(Registration::Created),
+    }
+}
+
+/// Stored template of a version, if one was registered
+fn load_template(version: &PromptVersion) -> Result<Option<Template>, Status> {
+    version
+        .template
+        .clone()
+        .map(serde_json::from_value::<Template>)
+        .transpose()
+        .map_err(|_| Status::internal("Failed to load prompt template"))
+}
+
+fn convert_template(template: PromptTemplate) -> Result<Template, Status> {
+    let parameters = non_empty(template.parameters)
+        .map(|parameters| serde_json::from_str(&parameters))
+        .transpose()
+        .map_err(|_| Status::invalid_argument("Invalid JSON in prompt parameters"))?;
+
+    Ok(Template {
+        text: template.text,
+        messages: template
+            .messages
+            .into_iter()
+            .map(|message| Message {
+                role: message.role,
+                content: message.content,
+            })
+            .collect(),
+        variables: template.variables,
+        model: non_empty(template.model),
+        parameters,
+    })
+}
+
+fn convert_prompt(version: PromptVersion) -> Result<Option<Prompt>, Status> {
+    let Some(template) = load_template(&version)? else {
+        return Ok(None);
+    };
+
+    Ok(Some(Prompt {
+        name: version.name,
+        version: version.version,
+        template: Some(PromptTemplate {
+            text: template.text,
+            messages: template
+                .messages
+                .into_iter()
+                .map(|message| PromptMessage {
+                    role: message.role,
+                    content: message.content,
+                })
+                .collect(),
+            variables: template.variables,
+            model: template.model,
+            parameters: template.parameters.map(|parameters| parameters.to_string()),
+        }),
+        content_hash: version.content_hash.unwrap_or_default(),
+        created_at: Some(to_timestamp(version.created_at)),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_template() {
+        let template = convert_template(PromptTemplate {
+            text: Some("Summarize {{ document }}".to_string()),
+            variables: vec!["document".to_string()],
+            model: Some(String::new()),
+            parameters: Some(r#"{"temperature": 0.2}"#.to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(template.model, None);
+        assert_eq!(
+            template.parameters,
+            Some(serde_json::json!({"temperature": 0.2}))
+        );
+        assert!(convert_template(PromptTemplate {
+            parameters: Some("{".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use sha2::{Digest, Sha256};
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        repository::DieselRepository,
+        scorer_plugin::{InsertableScorerPlugin, ScorerPlugin, ScorerPluginSummary},
+    },
+    schema::scorer_plugin,
+};
+use ellmo_proto::ellmo::{
+    ListScorerPluginsRequest, ListScorerPluginsResponse, RegisterScorerPluginRequest,
+    RegisterScorerPluginResponse,
+};
+
+use super::to_timestamp;
+use crate::scorer::plugin;
+
+/// Register a version of a WebAssembly scorer. Registering the same module again is a no-op,
+/// registering another module under an existing version is rejected.
+pub async fn register_scorer_plugin(
+    request: Request<RegisterScorerPluginRequest>,
+) -> Result<Response<RegisterScorerPluginResponse>, Status> {
+    let message = request.into_inner();
+    if message.name.is_empty() {
+        return Err(Status::invalid_argument("Missing plugin name"));
+    }
+    if message.version.is_empty() {
+        return Err(Status::invalid_argument("Missing plugin version"));
+    }
+    if message.module.is_empty() {
+        return Err(Status::invalid_argument("Missing plugin module"));
+    }
+
+    let content_hash = format!("{:x}", Sha256::digest(&message.module));
+    plugin::validate(&content_hash, &message.module)
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+    let mut conn = establish_connection();
+    let created = diesel::insert_into(scorer_plugin::table)
+        .values(&InsertableScorerPlugin {
+            name: message.name.clone(),
+            version: message.version.clone(),
+            module: message.module,
+            content_hash: content_hash.clone(),
+            needs_expected: message.needs_expected,
+            created_at: Utc::now(),
+        })
+        .on_conflict((scorer_plugin::name, scorer_plugin::version))
+        .do_nothing()
+        .returning(scorer_plugin::all_columns)
+        .get_result::<ScorerPlugin>(&mut conn)
+        .optional()
+        .map_err(|_| Status::internal("Failed to register scorer plugin"))?;
+
+    if let Some(created) = created {
+        return Ok(Response::new(RegisterScorerPluginResponse {
+            plugin: Some(convert_plugin(created)),
+            created: true,
+        }));
+    }
+
+    // Versions are immutable, so an existing version must hold the same module
+    let existing = DieselRepository::new(&mut conn, scorer_plugin::table)
+        .find_by_version(&message.name, &message.version)
+        .map_err(|_| Status::internal("Failed to fetch scorer plugin"))?
+        .ok_or_else(|| Status::internal("Failed to register scorer plugin"))?;
+    if existing.content_hash != content_hash || existing.needs_expected != message.needs_expected {
+        return Err(Status::already_exists(format!(
+            "Version {} of plugin {} is already registered with another module",
+            message.version, message.name
+        )));
+    }
+
+    Ok(Response::new(RegisterScorerPluginResponse {
+        plugin: Some(convert_plugin(existing)),
+        created: false,
+    }))
+}
+
+/// List registered plugin versions
+pub async fn list_scorer_plugins(
+    request: Request<ListScorerPluginsRequest>,
+) -> Result<Response<ListScorerPluginsResponse>, Status> {
+    let name = request.into_inner().name.filter(|name| !name.is_empty());
+
+    let mut conn = establish_connection();
+    let plugins = DieselRepository::new(&mut conn, scorer_plugin::table)
+        .find_summaries(name.as_deref())
+        .map_err(|_| Status::internal("Failed to list scorer plugins"))?;
+
+    Ok(Response::new(ListScorerPluginsResponse {
+        plugins: plugins.into_iter().map(convert_summary).collect(),
+    }))
+}
+
+fn convert_plugin(plugin: ScorerPlugin) -> ellmo_proto::ellmo::ScorerPlugin {
+    ellmo_proto::ellmo::ScorerPlugin {
+        size_bytes: plugin.module.len() as u32,
+        name: plugin.name,
+        version: plugin.version,
+        content_hash: plugin.content_hash,
+        needs_expected: plugin.needs_expected,
+        created_at: Some(to_timestamp(plugin.created_at)),
+    }
+}
+
+fn convert_summary(plugin: ScorerPluginSummary) -> ellmo_proto::ellmo::ScorerPlugin {
+    ellmo_proto::ellmo::ScorerPlugin {
+        name: plugin.name,
+        version: plugin.version,
+        content_hash: plugin.content_hash,
+        needs_expected: plugin.needs_expected,
+        size_bytes: plugin.size_bytes as u32,
+        created_at: Some(to_timestamp(plugin.created_at)),
+    }
+}
@@ -0,0 +1,199 @@
+use chrono::Utc;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Double};
+use serde_json::json;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        repository::{DieselRepository, Repository},
+        span::{InsertableSpan, Span},
+        span_feedback::InsertableSpanFeedback,
+    },
+    schema::{span, span_feedback},
+};
+use ellmo_proto::ellmo::{RecordSpanFeedbackRequest, SpanFilter};
+
+use super::eval::metadata::non_empty;
+use super::from_timestamp;
+
+/// Number of spans a search returns by default
+const DEFAULT_SEARCH_LIMIT: u32 = 100;
+/// Largest number of spans a search returns
+const MAX_SEARCH_LIMIT: u32 = 1000;
+
+/// Score a span after the fact, e.g. with a user rating of an answer
+pub async fn record_span_feedback(
+    request: Request<RecordSpanFeedbackRequest>,
+) -> Result<Response<()>, Status> {
+    let message = request.into_inner();
+    if message.name.is_empty() {
+        return Err(Status::invalid_argument("Missing feedback name"));
+    }
+    if !message.score.is_finite() {
+        return Err(Status::invalid_argument("Feedback score must be finite"));
+    }
+
+    let mut conn = establish_connection();
+    let span = find_span(&mut conn, &message.span_id)?;
+    DieselRepository::new(&mut conn, span_feedback::table)
+        .create(&InsertableSpanFeedback {
+            span_id: span.id,
+            name: message.name,
+            score: message.score,
+            comment: non_empty(message.comment),
+            created_at: Utc::now(),
+        })
+        .map_err(|_| Status::internal("Failed to record feedback"))?;
+
+    Ok(Response::new(()))
+}
+
+pub fn parse_span_id(span_id: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(span_id).map_err(|_| Status::invalid_argument("Invalid span ID"))
+}
+
+/// Span reported with the given ID
+pub fn find_span(conn: &mut PgConnection, span_id: &str) -> Result<Span, Status> {
+    DieselRepository::new(conn, span::table)
+        .find_by_external_uuid(parse_span_id(span_id)?)
+        .map_err(|_| Status::internal("Failed to fetch span"))?
+        .ok_or_else(|| Status::not_found("Span not found"))
+}
+
+/// Spans either given by ID or found by a search, for requests accepting exactly one of both
+pub fn select_spans(
+    conn: &mut PgConnection,
+    span_ids: &[String],
+    filter: Option<&SpanFilter>,
+) -> Result<Vec<Span>, Status> {
+    match (span_ids.is_empty(), filter) {
+        (false, None) => find_spans(conn, span_ids),
+        (true, Some(filter)) => search_spans(conn, filter),
+        (true, None) => Err(Status::invalid_argument("Missing span IDs or filter")),
+        (false, Some(_)) => Err(Status::invalid_argument(
+            "Span IDs and filter are mutually exclusive",
+        )),
+    }
+}
+
+/// Spans reported with the given IDs, in the same order
+pub fn find_spans(conn: &mut PgConnection, span_ids: &[String]) -> Result<Vec<Span>, Status> {
+    let uuids = span_ids
+        .iter()
+        .map(|span_id| parse_span_id(span_id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let spans = DieselRepository::new(conn, span::table)
+        .find_by_external_uuids(&uuids)
+        .map_err(|_| Status::internal("Failed to fetch spans"))?;
+
+    uuids
+        .iter()
+        .zip(span_ids)
+        .map(|(uuid, span_id)| {
+            spans
+                .iter()
+                .find(|span| span.external_uuid == Some(*uuid))
+                .cloned()
+                .ok_or_else(|| Status::not_found(format!("Span {} not found", span_id)))
+        })
+        .collect()
+}
+
+/// Spans matching a search, most recently started first
+pub fn search_spans(conn: &mut PgConnection, filter: &SpanFilter) -> Result<Vec<Span>, Status> {
+    let attributes = if filter.attributes.is_empty() {
+        None
+    } else {
+        let attributes =
+            serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&filter.attributes)
+                .map_err(|_| Status::invalid_argument("Attributes must be a JSON object"))?;
+        Some(serde_json::Value::Object(attributes))
+    };
+    if filter
+        .sample_rate
+        .is_some_and(|rate| !(rate > 0.0 && rate <= 1.0))
+    {
+        return Err(Status::invalid_argument(
+            "Sample rate must be greater than 0 and at most 1",
+        ));
+    }
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .min(MAX_SEARCH_LIMIT);
+
+    let mut query = span::table.into_boxed();
+    if let Some(operation_name) = &filter.operation_name {
+        query = query.filter(span::operation_name.eq(operation_name));
+    }
+    if let Some(attributes) = attributes {
+        query = query.filter(span::attributes.contains(attributes));
+    }
+    if let Some(since) = filter.since.map(from_timestamp).transpose()? {
+        query = query.filter(span::ts_start.ge(since));
+    }
+    if let Some(until) = filter.until.map(from_timestamp).transpose()? {
+        query = query.filter(span::ts_start.lt(until));
+    }
+    if let Some(sample_rate) = filter.sample_rate.filter(|&rate| rate < 1.0) {
+        query = query.filter(sql::<Bool>("random() < ").bind::<Double, _>(sample_rate));
+    }
+
+    query
+        .order(span::ts_start.desc())
+        .limit(i64::from(limit))
+        .load::<Span>(conn)
+        .map_err(|_| Status::internal("Failed to search spans"))
+}
+
+/// Record a span of the server itself, e.g. the render of a prompt, returning its ID. Parents are
+/// usually reported after their children, in which case only the ID of the parent is kept, as
+/// the parent.id attribute.
+pub fn record_span(
+    conn: &mut PgConnection,
+    mut span: InsertableSpan,
+    parent_span_id: Option<&str>,
+) -> Result<Uuid, Status> {
+    let parent_uuid = parent_span_id
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|_| Status::invalid_argument("Invalid parent span ID"))?;
+    if let Some(parent_uuid) = parent_uuid {
+        let parent = DieselRepository::new(conn, span::table)
+            .find_by_external_uuid(parent_uuid)
+            .map_err(|_| Status::internal("Failed to fetch parent span"))?;
+        match parent {
+            Some(parent) => span.parent_span_id = Some(parent.id),
+            None => span.attributes["parent.id"] = json!(parent_uuid.to_string()),
+        }
+    }
+
+    let uuid = Uuid::new_v4();
+    span.external_uuid = Some(uuid);
+    DieselRepository::new(conn, span::table)
+        .create(&span)
+        .map_err(|_| Status::internal("Failed to record span"))?;
+
+    Ok(uuid)
+}
+
+/// Span of an operation with the given attributes and nothing captured, shared by tests
+#[cfg(test)]
+pub(crate) fn sample_span(operation_name: &str, attributes: serde_json::Value) -> Span {
+    Span {
+        id: 1,
+        ts_start: Utc::now(),
+        ts_end: Utc::now(),
+        operation_name: operation_name.to_string(),
+        parent_span_id: None,
+        external_uuid: None,
+        attributes,
+        input: None,
+        output: None,
+        prompt_version_id: None,
+    }
+}
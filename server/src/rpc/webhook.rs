@@ -0,0 +1,248 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use tonic::{Request, Response, Status};
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        repository::{DieselRepository, Repository},
+        webhook_delivery::{STATUS_DELIVERED, STATUS_FAILED},
+        webhook_delivery_attempt::WebhookDeliveryAttempt,
+        webhook_endpoint::{InsertableWebhookEndpoint, WebhookEndpoint},
+    },
+    schema::{webhook_delivery, webhook_delivery_attempt, webhook_endpoint},
+};
+use ellmo_proto::ellmo::{
+    CreateWebhookRequest, CreateWebhookResponse, DeleteWebhookRequest,
+    ListWebhookDeliveriesRequest, ListWebhookDeliveriesResponse, ListWebhooksRequest,
+    ListWebhooksResponse, Webhook, WebhookDelivery, WebhookDeliveryStatus, WebhookEvent,
+};
+
+use super::to_timestamp;
+
+const DEFAULT_DELIVERY_LIMIT: u32 = 50;
+const MAX_DELIVERY_LIMIT: u32 = 500;
+const MIN_SECRET_LENGTH: usize = 16;
+
+/// Register a webhook notified of the given events
+pub async fn create_webhook(
+    request: Request<CreateWebhookRequest>,
+) -> Result<Response<CreateWebhookResponse>, Status> {
+    let message = request.into_inner();
+
+    validate_url(&message.url)?;
+    let events = parse_events(&message.events)?;
+    let secret = match message.secret {
+        Some(secret) if secret.len() < MIN_SECRET_LENGTH => {
+            return Err(Status::invalid_argument(format!(
+                "Secret must be at least {} characters long",
+                MIN_SECRET_LENGTH
+            )))
+        }
+        Some(secret) => secret,
+        None => generate_secret(),
+    };
+
+    let mut conn = establish_connection();
+    let created = DieselRepository::new(&mut conn, webhook_endpoint::table)
+        .create(&InsertableWebhookEndpoint {
+            url: message.url,
+            secret,
+            events: events
+                .iter()
+                .map(|event| event.as_str_name().to_string())
+                .collect(),
+            eval_name: message.eval_name.filter(|name| !name.is_empty()),
+            prompt_name: message.prompt_name.filter(|name| !name.is_empty()),
+            created_at: Utc::now(),
+        })
+        .map_err(|_| Status::internal("Failed to create webhook"))?;
+
+    Ok(Response::new(CreateWebhookResponse {
+        secret: created.secret.clone(),
+        webhook: Some(convert_webhook(created)),
+    }))
+}
+
+/// Remove a webhook along with its deliveries
+pub async fn delete_webhook(
+    request: Request<DeleteWebhookRequest>,
+) -> Result<Response<()>, Status> {
+    let id = request.into_inner().id;
+
+    let mut conn = establish_connection();
+    let deleted = diesel::delete(webhook_endpoint::table.find(id))
+        .execute(&mut conn)
+        .map_err(|_| Status::internal("Failed to delete webhook"))?;
+
+    if deleted == 0 {
+        return Err(Status::not_found("Webhook not found"));
+    }
+
+    Ok(Response::new(()))
+}
+
+/// List the registered webhooks. Secrets are not included.
+pub async fn list_webhooks(
+    _request: Request<ListWebhooksRequest>,
+) -> Result<Response<ListWebhooksResponse>, Status> {
+    let mut conn = establish_connection();
+    let webhooks = webhook_endpoint::table
+        .order(webhook_endpoint::id.asc())
+        .load::<WebhookEndpoint>(&mut conn)
+        .map_err(|_| Status::internal("Failed to list webhooks"))?;
+
+    Ok(Response::new(ListWebhooksResponse {
+        webhooks: webhooks.into_iter().map(convert_webhook).collect(),
+    }))
+}
+
+/// List the most recent deliveries of a webhook along with their attempts
+pub async fn list_webhook_deliveries(
+    request: Request<ListWebhookDeliveriesRequest>,
+) -> Result<Response<ListWebhookDeliveriesResponse>, Status> {
+    let message = request.into_inner();
+    let limit = message
+        .limit
+        .unwrap_or(DEFAULT_DELIVERY_LIMIT)
+        .clamp(1, MAX_DELIVERY_LIMIT);
+
+    let mut conn = establish_connection();
+    DieselRepository::new(&mut conn, webhook_endpoint::table)
+        .find_by_id(message.webhook_id)
+        .optional()
+        .map_err(|_| Status::internal("Failed to fetch webhook"))?
+        .ok_or_else(|| Status::not_found("Webhook not found"))?;
+
+    let deliveries = DieselRepository::new(&mut conn, webhook_delivery::table)
+        .find_by_endpoint(message.webhook_id, limit as i64)
+        .map_err(|_| Status::internal("Failed to fetch webhook deliveries"))?;
+
+    let delivery_ids: Vec<i32> = deliveries.iter().map(|delivery| delivery.id).collect();
+    let mut attempts: HashMap<i32, Vec<WebhookDeliveryAttempt>> = HashMap::new();
+    for attempt in DieselRepository::new(&mut conn, webhook_delivery_attempt::table)
+        .find_by_deliveries(&delivery_ids)
+        .map_err(|_| Status::internal("Failed to fetch webhook delivery attempts"))?
+    {
+        attempts
+            .entry(attempt.webhook_delivery_id)
+            .or_default()
+            .push(attempt);
+    }
+
+    Ok(Response::new(ListWebhookDeliveriesResponse {
+        deliveries: deliveries
+            .into_iter()
+            .map(|delivery| WebhookDelivery {
+                id: delivery.id,
+                event: WebhookEvent::from_str_name(&delivery.event)
+                    .unwrap_or_default()
+                    .into(),
+                payload: delivery.payload.to_string(),
+                status: delivery_status(&delivery.status).into(),
+                attempts: attempts
+                    .remove(&delivery.id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|attempt| ellmo_proto::ellmo::WebhookDeliveryAttempt {
+                        attempt: attempt.attempt as u32,
+                        response_status: attempt.response_status,
+                        error: attempt.error,
+                        duration_ms: attempt.duration_ms as u32,
+                        attempted_at: Some(to_timestamp(attempt.created_at)),
+                    })
+                    .collect(),
+                next_attempt_at: delivery.next_attempt_at.map(to_timestamp),
+                created_at: Some(to_timestamp(delivery.created_at)),
+            })
+            .collect(),
+    }))
+}
+
+fn convert_webhook(endpoint: WebhookEndpoint) -> Webhook {
+    Webhook {
+        id: endpoint.id,
+        url: endpoint.url,
+        events: endpoint
+            .events
+            .iter()
+            .filter_map(|event| WebhookEvent::from_str_name(event))
+            .map(Into::into)
+            .collect(),
+        eval_name: endpoint.eval_name,
+        prompt_name: endpoint.prompt_name,
+        created_at: Some(to_timestamp(endpoint.created_at)),
+    }
+}
+
+fn delivery_status(status: &str) -> WebhookDeliveryStatus {
+    match status {
+        STATUS_DELIVERED => WebhookDeliveryStatus::DeliverySucceeded,
+        STATUS_FAILED => WebhookDeliveryStatus::DeliveryFailed,
+        _ => WebhookDeliveryStatus::DeliveryPending,
+    }
+}
+
+fn validate_url(url: &str) -> Result<(), Status> {
+    let parsed =
+        reqwest::Url::parse(url).map_err(|_| Status::invalid_argument("Invalid webhook URL"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(Status::invalid_argument(
+            "Webhook URL must use http or https",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Events of a webhook without duplicates
+fn parse_events(events: &[i32]) -> Result<Vec<WebhookEvent>, Status> {
+    if events.is_empty() {
+        return Err(Status::invalid_argument("Missing events"));
+    }
+
+    let mut parsed: Vec<WebhookEvent> = Vec::with_capacity(events.len());
+    for event in events {
+        let event = WebhookEvent::try_from(*event)
+            .map_err(|_| Status::invalid_argument(format!("Unknown event {}", event)))?;
+        if !parsed.contains(&event) {
+            parsed.push(event);
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn generate_secret() -> String {
+    format!(
+        "whsec_{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events() {
+        let regression = WebhookEvent::EvalRegression as i32;
+        let gate_failure = WebhookEvent::GateFailure as i32;
+
+        assert_eq!(
+            parse_events(&[gate_failure, regression, gate_failure]).unwrap(),
+            vec![WebhookEvent::GateFailure, WebhookEvent::EvalRegression]
+        );
+        assert!(parse_events(&[]).is_err());
+        assert!(parse_events(&[42]).is_err());
+    }
+
+    #[test]
+    fn test_validate_url() {
+        assert!(validate_url("https://example.com/hooks/ellmo").is_ok());
+        assert!(validate_url("ftp://example.com").is_err());
+        assert!(validate_url("not a url").is_err());
+    }
+}
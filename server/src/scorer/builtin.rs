@@ -0,0 +1,280 @@
+use anyhow::{anyhow, bail};
+use serde_json::Value;
+
+use super::{text, Scorer};
+
+fn expected(expected: Option<&Value>) -> anyhow::Result<&Value> {
+    expected.ok_or_else(|| anyhow!("Missing expected output"))
+}
+
+fn matches(matched: bool) -> f32 {
+    if matched {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Output and expected output are the same JSON value
+pub struct ExactMatch;
+
+impl Scorer for ExactMatch {
+    fn needs_expected(&self) -> bool {
+        true
+    }
+
+    fn score(&self, output: &Value, expected_output: Option<&Value>) -> anyhow::Result<f32> {
+        Ok(matches(output == expected(expected_output)?))
+    }
+}
+
+/// Output and expected output are the same text, ignoring case, punctuation and how words are
+/// separated
+pub struct NormalizedMatch;
+
+impl Scorer for NormalizedMatch {
+    fn needs_expected(&self) -> bool {
+        true
+    }
+
+    fn score(&self, output: &Value, expected_output: Option<&Value>) -> anyhow::Result<f32> {
+        let expected_output = expected(expected_output)?;
+        Ok(matches(
+            normalize(&text(output)) == normalize(&text(expected_output)),
+        ))
+    }
+}
+
+fn normalize(text: &str) -> String {
+    let words: String = text
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .flat_map(char::to_lowercase)
+        .collect();
+    words.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// One minus the Levenshtein distance of the texts, relative to the length of the longer one
+pub struct EditSimilarity;
+
+impl Scorer for EditSimilarity {
+    fn needs_expected(&self) -> bool {
+        true
+    }
+
+    fn score(&self, output: &Value, expected_output: Option<&Value>) -> anyhow::Result<f32> {
+        let output: Vec<char> = text(output).chars().collect();
+        let expected_output: Vec<char> = text(expected(expected_output)?).chars().collect();
+
+        let longest = output.len().max(expected_output.len());
+        if longest == 0 {
+            return Ok(1.0);
+        }
+
+        Ok(1.0 - levenshtein(&output, &expected_output) as f32 / longest as f32)
+    }
+}
+
+/// Number of single character insertions, deletions and substitutions turning `a` into `b`
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Output contains a match of a regular expression
+pub struct RegexMatch {
+    pattern: regex::Regex,
+}
+
+impl RegexMatch {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let pattern =
+            regex::Regex::new(pattern).map_err(|e| anyhow!("Invalid regex pattern: {}", e))?;
+        Ok(RegexMatch { pattern })
+    }
+}
+
+impl Scorer for RegexMatch {
+    fn needs_expected(&self) -> bool {
+        false
+    }
+
+    fn score(&self, output: &Value, _expected_output: Option<&Value>) -> anyhow::Result<f32> {
+        Ok(matches(self.pattern.is_match(&text(output))))
+    }
+}
+
+/// Output is valid against a JSON schema. String outputs are parsed as JSON first, as models
+/// usually return JSON as text.
+pub struct JsonSchema {
+    validator: jsonschema::Validator,
+}
+
+impl JsonSchema {
+    pub fn new(schema: &Value) -> anyhow::Result<Self> {
+        let validator =
+            jsonschema::validator_for(schema).map_err(|e| anyhow!("Invalid JSON schema: {}", e))?;
+        Ok(JsonSchema { validator })
+    }
+}
+
+impl Scorer for JsonSchema {
+    fn needs_expected(&self) -> bool {
+        false
+    }
+
+    fn score(&self, output: &Value, _expected_output: Option<&Value>) -> anyhow::Result<f32> {
+        let valid = match output {
+            Value::String(text) => match serde_json::from_str::<Value>(text) {
+                Ok(parsed) => self.validator.is_valid(&parsed),
+                Err(_) => false,
+            },
+            other => self.validator.is_valid(other),
+        };
+
+        Ok(matches(valid))
+    }
+}
+
+/// Output is a number within a tolerance of the expected output. Numbers may be given as text.
+pub struct NumericTolerance {
+    tolerance: f64,
+    relative: bool,
+}
+
+impl NumericTolerance {
+    pub fn new(tolerance: f64, relative: bool) -> anyhow::Result<Self> {
+        if !tolerance.is_finite() || tolerance < 0.0 {
+            bail!("Tolerance must be a non-negative number");
+        }
+        Ok(NumericTolerance {
+            tolerance,
+            relative,
+        })
+    }
+}
+
+impl Scorer for NumericTolerance {
+    fn needs_expected(&self) -> bool {
+        true
+    }
+
+    fn score(&self, output: &Value, expected_output: Option<&Value>) -> anyhow::Result<f32> {
+        let expected_number = number(expected(expected_output)?)
+            .ok_or_else(|| anyhow!("Expected output is not a number"))?;
+        // An output that is not a number is simply wrong
+        let Some(number) = number(output) else {
+            return Ok(0.0);
+        };
+
+        let tolerance = if self.relative {
+            self.tolerance * expected_number.abs()
+        } else {
+            self.tolerance
+        };
+
+        Ok(matches((number - expected_number).abs() <= tolerance))
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn score(scorer: &dyn Scorer, output: Value, expected: Value) -> f32 {
+        scorer.score(&output, Some(&expected)).unwrap()
+    }
+
+    #[test]
+    fn test_exact_and_normalized_match() {
+        assert_eq!(
+            score(&ExactMatch, json!({"a": [1, 2]}), json!({"a": [1, 2]})),
+            1.0
+        );
+        assert_eq!(score(&ExactMatch, json!("Paris"), json!("paris")), 0.0);
+        assert_eq!(
+            score(
+                &NormalizedMatch,
+                json!("  The  Eiffel-Tower!"),
+                json!("the eiffel tower")
+            ),
+            1.0
+        );
+        assert_eq!(score(&NormalizedMatch, json!("Paris"), json!("Lyon")), 0.0);
+        assert!(ExactMatch.score(&json!("Paris"), None).is_err());
+    }
+
+    #[test]
+    fn test_edit_similarity() {
+        assert_eq!(levenshtein(&['a', 'b', 'c'], &['a', 'b', 'c']), 0);
+        let kitten: Vec<char> = "kitten".chars().collect();
+        let sitting: Vec<char> = "sitting".chars().collect();
+        assert_eq!(levenshtein(&kitten, &sitting), 3);
+
+        let similarity = score(&EditSimilarity, json!("kitten"), json!("sitting"));
+        assert!((similarity - (1.0 - 3.0 / 7.0)).abs() < 1e-6);
+        assert_eq!(score(&EditSimilarity, json!(""), json!("")), 1.0);
+    }
+
+    #[test]
+    fn test_regex_match() {
+        let scorer = RegexMatch::new(r"^\d{4}-\d{2}-\d{2}$").unwrap();
+
+        assert_eq!(scorer.score(&json!("2024-10-16"), None).unwrap(), 1.0);
+        assert_eq!(scorer.score(&json!("16 October"), None).unwrap(), 0.0);
+        assert!(RegexMatch::new("(").is_err());
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let scorer = JsonSchema::new(&json!({
+            "type": "object",
+            "required": ["answer"],
+            "properties": {"answer": {"type": "string"}},
+        }))
+        .unwrap();
+
+        assert_eq!(scorer.score(&json!({"answer": "yes"}), None).unwrap(), 1.0);
+        assert_eq!(
+            scorer.score(&json!("{\"answer\": \"yes\"}"), None).unwrap(),
+            1.0
+        );
+        assert_eq!(scorer.score(&json!({"answer": 1}), None).unwrap(), 0.0);
+        assert_eq!(scorer.score(&json!("not json"), None).unwrap(), 0.0);
+        assert!(JsonSchema::new(&json!({"type": 12})).is_err());
+    }
+
+    #[test]
+    fn test_numeric_tolerance() {
+        let absolute = NumericTolerance::new(0.5, false).unwrap();
+        let relative = NumericTolerance::new(0.1, true).unwrap();
+
+        assert_eq!(score(&absolute, json!(10.4), json!(10)), 1.0);
+        assert_eq!(score(&absolute, json!(" 10.6 "), json!(10)), 0.0);
+        assert_eq!(score(&relative, json!(95), json!("100")), 1.0);
+        assert_eq!(score(&relative, json!(89), json!(100)), 0.0);
+        assert_eq!(score(&absolute, json!("ten"), json!(10)), 0.0);
+        assert!(absolute.score(&json!(1), Some(&json!("ten"))).is_err());
+        assert!(NumericTolerance::new(-1.0, false).is_err());
+    }
+}
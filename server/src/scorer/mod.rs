@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+mod builtin;
+pub mod plugin;
+
+/// Deterministic scorer of the output of a trial, evaluated on the server so that every client
+/// gets the same scoring semantics
+pub trait Scorer: Send + Sync {
+    /// Whether the scorer compares the output against the expected output of its case
+    fn needs_expected(&self) -> bool;
+
+    /// Score of an output, between 0 and 1. `expected` is given whenever `needs_expected` is.
+    fn score(&self, output: &Value, expected: Option<&Value>) -> anyhow::Result<f32>;
+}
+
+/// Scorer applied to a run along with the metric it records, as stored with the run
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScorerSpec {
+    pub metric: String,
+    #[serde(flatten)]
+    pub kind: ScorerKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScorerKind {
+    ExactMatch,
+    NormalizedMatch,
+    EditSimilarity,
+    RegexMatch {
+        pattern: String,
+    },
+    JsonSchema {
+        schema: Value,
+    },
+    NumericTolerance {
+        tolerance: f64,
+        relative: bool,
+    },
+    /// Registered plugin, pinned to the module it resolved to when the run started
+    Plugin {
+        name: String,
+        version: String,
+        content_hash: String,
+        needs_expected: bool,
+    },
+}
+
+impl ScorerKind {
+    /// Metric a scorer records when none is given
+    pub fn default_metric(&self) -> &str {
+        match self {
+            ScorerKind::ExactMatch => "exact_match",
+            ScorerKind::NormalizedMatch => "normalized_match",
+            ScorerKind::EditSimilarity => "edit_similarity",
+            ScorerKind::RegexMatch { .. } => "regex_match",
+            ScorerKind::JsonSchema { .. } => "json_schema",
+            ScorerKind::NumericTolerance { .. } => "numeric_tolerance",
+            ScorerKind::Plugin { name, .. } => name,
+        }
+    }
+}
+
+/// Compile the scorer described by a spec, rejecting invalid patterns and schemas. Plugin modules
+/// not compiled yet are fetched by content hash with `load_module`.
+pub fn build(
+    spec: &ScorerSpec,
+    load_module: &mut dyn FnMut(&str) -> anyhow::Result<Vec<u8>>,
+) -> anyhow::Result<Box<dyn Scorer>> {
+    Ok(match &spec.kind {
+        ScorerKind::ExactMatch => Box::new(builtin::ExactMatch),
+        ScorerKind::NormalizedMatch => Box::new(builtin::NormalizedMatch),
+        ScorerKind::EditSimilarity => Box::new(builtin::EditSimilarity),
+        ScorerKind::RegexMatch { pattern } => Box::new(builtin::RegexMatch::new(pattern)?),
+        ScorerKind::JsonSchema { schema } => Box::new(builtin::JsonSchema::new(schema)?),
+        ScorerKind::NumericTolerance {
+            tolerance,
+            relative,
+        } => Box::new(builtin::NumericTolerance::new(*tolerance, *relative)?),
+        ScorerKind::Plugin {
+            content_hash,
+            needs_expected,
+            ..
+        } => Box::new(plugin::PluginScorer::new(
+            content_hash,
+            *needs_expected,
+            || load_module(content_hash),
+        )?),
+    })
+}
+
+/// Text an output is compared by: the content of strings, the JSON encoding of anything else
+pub fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_round_trips_through_json() {
+        let spec = ScorerSpec {
+            metric: "close_enough".to_string(),
+            kind: ScorerKind::NumericTolerance {
+                tolerance: 0.1,
+                relative: true,
+            },
+        };
+
+        let encoded = serde_json::to_value(&spec).unwrap();
+        assert_eq!(
+            encoded,
+            serde_json::json!({
+                "metric": "close_enough",
+                "kind": "numeric_tolerance",
+                "tolerance": 0.1,
+                "relative": true,
+            })
+        );
+        assert_eq!(serde_json::from_value::<ScorerSpec>(encoded).unwrap(), spec);
+    }
+}
@@ -0,0 +1,302 @@
+use anyhow::{anyhow, bail};
+use lazy_static::lazy_static;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wasmtime::{
+    Config, Engine, ExternType, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+    Trap, TypedFunc, ValType,
+};
+
+use super::Scorer;
+
+/// Largest module accepted, in bytes
+const MAX_MODULE_SIZE: usize = 4 * 1024 * 1024;
+/// Fuel a single score may consume, roughly the number of instructions it executes
+const FUEL_LIMIT: u64 = 50_000_000;
+/// Largest linear memory an instance may grow to, in bytes
+const MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+/// Compiled modules kept in memory, beyond which an arbitrary one is dropped to make room
+const MAX_CACHED_MODULES: usize = 64;
+
+lazy_static! {
+    static ref ENGINE: Engine = {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        Engine::new(&config).expect("Failed to create WebAssembly engine")
+    };
+    /// Compiled modules by content hash, so that a module is only compiled once per process
+    static ref MODULES: Mutex<HashMap<String, Module>> = Mutex::new(HashMap::new());
+}
+
+/// Check that a module implements the scoring ABI, keeping it compiled for later runs
+pub fn validate(content_hash: &str, bytes: &[u8]) -> anyhow::Result<()> {
+    let module = compile(bytes)?;
+    cache(content_hash, module);
+    Ok(())
+}
+
+fn cache(content_hash: &str, module: Module) {
+    let mut modules = MODULES.lock().unwrap();
+    if modules.len() >= MAX_CACHED_MODULES && !modules.contains_key(content_hash) {
+        if let Some(evicted) = modules.keys().next().cloned() {
+            modules.remove(&evicted);
+        }
+    }
+    modules.insert(content_hash.to_string(), module);
+}
+
+fn compile(bytes: &[u8]) -> anyhow::Result<Module> {
+    if bytes.len() > MAX_MODULE_SIZE {
+        bail!("Module is larger than {} bytes", MAX_MODULE_SIZE);
+    }
+
+    let module =
+        Module::new(&ENGINE, bytes).map_err(|e| anyhow!("Invalid WebAssembly module: {}", e))?;
+    if let Some(import) = module.imports().next() {
+        bail!(
+            "Module may not import anything, found {}.{}",
+            import.module(),
+            import.name()
+        );
+    }
+    if !matches!(module.get_export("memory"), Some(ExternType::Memory(_))) {
+        bail!("Module must export its memory as memory");
+    }
+    check_function(&module, "alloc", &[ValType::I32], ValType::I32)?;
+    check_function(
+        &module,
+        "score",
+        &[ValType::I32, ValType::I32, ValType::I32, ValType::I32],
+        ValType::F32,
+    )?;
+
+    Ok(module)
+}
+
+fn check_function(
+    module: &Module,
+    name: &str,
+    params: &[ValType],
+    result: ValType,
+) -> anyhow::Result<()> {
+    let Some(ExternType::Func(function)) = module.get_export(name) else {
+        bail!("Module must export a function {}", name);
+    };
+
+    let params_match = function.params().len() == params.len()
+        && function
+            .params()
+            .zip(params)
+            .all(|(actual, expected)| ValType::eq(&actual, expected));
+    let results: Vec<ValType> = function.results().collect();
+    let results_match = results.len() == 1 && ValType::eq(&results[0], &result);
+    if !params_match || !results_match {
+        bail!(
+            "Function {} does not have the signature of the scoring ABI",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+/// Scorer running a WebAssembly module in a sandbox, see `ScorerPlugin` in the API for the ABI
+pub struct PluginScorer {
+    module: Module,
+    needs_expected: bool,
+}
+
+impl PluginScorer {
+    /// Scorer running the module with the given content hash. `load` is only called when the
+    /// module was not compiled before.
+    pub fn new(
+        content_hash: &str,
+        needs_expected: bool,
+        load: impl FnOnce() -> anyhow::Result<Vec<u8>>,
+    ) -> anyhow::Result<Self> {
+        let cached = MODULES.lock().unwrap().get(content_hash).cloned();
+        let module = match cached {
+            Some(module) => module,
+            None => {
+                let module = compile(&load()?)?;
+                cache(content_hash, module.clone());
+                module
+            }
+        };
+
+        Ok(PluginScorer {
+            module,
+            needs_expected,
+        })
+    }
+}
+
+impl Scorer for PluginScorer {
+    fn needs_expected(&self) -> bool {
+        self.needs_expected
+    }
+
+    fn score(&self, output: &Value, expected: Option<&Value>) -> anyhow::Result<f32> {
+        // A fresh instance per score, so that no state leaks between cases
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MEMORY_LIMIT)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&ENGINE, limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(FUEL_LIMIT)?;
+
+        let instance = Instance::new(&mut store, &self.module, &[]).map_err(plugin_error)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("Plugin does not export its memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let score = instance.get_typed_func::<(i32, i32, i32, i32), f32>(&mut store, "score")?;
+
+        let expected = expected.unwrap_or(&Value::Null);
+        let (output_ptr, output_len) =
+            write(&mut store, &alloc, &memory, output.to_string().as_bytes())?;
+        let (expected_ptr, expected_len) =
+            write(&mut store, &alloc, &memory, expected.to_string().as_bytes())?;
+
+        let value = score
+            .call(
+                &mut store,
+                (output_ptr, output_len, expected_ptr, expected_len),
+            )
+            .map_err(plugin_error)?;
+        if !(0.0..=1.0).contains(&value) {
+            bail!("Plugin returned {}, which is not between 0 and 1", value);
+        }
+
+        Ok(value)
+    }
+}
+
+/// Copy bytes into memory allocated by the plugin, returning their pointer and length
+fn write(
+    store: &mut Store<StoreLimits>,
+    alloc: &TypedFunc<i32, i32>,
+    memory: &Memory,
+    bytes: &[u8],
+) -> anyhow::Result<(i32, i32)> {
+    let len = i32::try_from(bytes.len()).map_err(|_| anyhow!("Value is too large"))?;
+    let ptr = alloc.call(&mut *store, len).map_err(plugin_error)?;
+    memory
+        .write(&mut *store, ptr as u32 as usize, bytes)
+        .map_err(|_| anyhow!("Plugin allocated memory out of bounds"))?;
+    Ok((ptr, len))
+}
+
+fn plugin_error(error: anyhow::Error) -> anyhow::Error {
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => anyhow!("Plugin exceeded its fuel limit"),
+        Some(trap) => anyhow!("Plugin trapped: {}", trap),
+        None => anyhow!("Plugin failed: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Bump allocator shared by the test modules
+    const ALLOC: &str = r#"
+        (memory (export "memory") 1)
+        (global $next (mut i32) (i32.const 1024))
+        (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+    "#;
+
+    fn plugin(name: &str, score: &str) -> anyhow::Result<PluginScorer> {
+        let text = format!("(module {} {})", ALLOC, score);
+        PluginScorer::new(name, true, || Ok(text.into_bytes()))
+    }
+
+    #[test]
+    fn test_plugin_reads_output_and_expected() {
+        // 1 when the first bytes of the JSON encodings are equal
+        let scorer = plugin(
+            "first-byte",
+            r#"(func (export "score") (param i32 i32 i32 i32) (result f32)
+                (f32.convert_i32_u (i32.eq
+                    (i32.load8_u (local.get 0))
+                    (i32.load8_u (local.get 2)))))"#,
+        )
+        .unwrap();
+
+        assert_eq!(scorer.score(&json!("a"), Some(&json!("b"))).unwrap(), 1.0);
+        assert_eq!(scorer.score(&json!(1), Some(&json!("b"))).unwrap(), 0.0);
+        assert_eq!(scorer.score(&json!(null), None).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_plugin_is_sandboxed() {
+        let looping = plugin(
+            "loop",
+            r#"(func (export "score") (param i32 i32 i32 i32) (result f32)
+                (loop $forever (br $forever))
+                (f32.const 0))"#,
+        )
+        .unwrap();
+        let error = looping.score(&json!("a"), None).unwrap_err();
+        assert!(error.to_string().contains("fuel"));
+
+        // Growing past the memory limit fails, which memory.grow reports as -1
+        let growing = plugin(
+            "grow",
+            r#"(func (export "score") (param i32 i32 i32 i32) (result f32)
+                (f32.convert_i32_s (i32.add (memory.grow (i32.const 2000)) (i32.const 1))))"#,
+        )
+        .unwrap();
+        assert_eq!(growing.score(&json!("a"), None).unwrap(), 0.0);
+
+        let nan = plugin(
+            "nan",
+            r#"(func (export "score") (param i32 i32 i32 i32) (result f32)
+                (f32.div (f32.const 0) (f32.const 0)))"#,
+        )
+        .unwrap();
+        assert!(nan.score(&json!("a"), None).is_err());
+    }
+
+    #[test]
+    fn test_plugin_scores_must_be_between_0_and_1() {
+        for (name, value) in [("negative", "-1"), ("above-one", "1.5")] {
+            let scorer = plugin(
+                name,
+                &format!(
+                    r#"(func (export "score") (param i32 i32 i32 i32) (result f32)
+                        (f32.const {}))"#,
+                    value
+                ),
+            )
+            .unwrap();
+            let error = scorer.score(&json!("a"), None).unwrap_err();
+            assert!(error.to_string().contains("not between 0 and 1"));
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_modules_outside_the_abi() {
+        let importing = format!(
+            r#"(module (import "env" "log" (func)) {}
+                (func (export "score") (param i32 i32 i32 i32) (result f32) (f32.const 1)))"#,
+            ALLOC
+        );
+        let wrong_signature = format!(
+            r#"(module {} (func (export "score") (param i32) (result f32) (f32.const 1)))"#,
+            ALLOC
+        );
+
+        assert!(validate("importing", importing.as_bytes()).is_err());
+        assert!(validate("wrong-signature", wrong_signature.as_bytes()).is_err());
+        assert!(validate("missing-score", format!("(module {})", ALLOC).as_bytes()).is_err());
+        assert!(validate("garbage", b"\0asm garbage").is_err());
+    }
+}
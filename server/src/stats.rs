@@ -0,0 +1,387 @@
+/// Significance level used to decide whether a change stands out from noise
+pub const SIGNIFICANCE_LEVEL: f64 = 0.05;
+/// Seed for the bootstrap resampler, fixed so identical runs produce identical intervals
+const BOOTSTRAP_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Result of a paired bootstrap over per-case score deltas
+#[derive(Debug, Clone, Copy)]
+pub struct BootstrapInterval {
+    pub mean: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub p_value: f64,
+}
+
+/// Count, mean and sample variance of a set of observations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub variance: f64,
+}
+
+impl Summary {
+    pub fn from_values(values: &[f64]) -> Self {
+        let mean = mean(values);
+        let variance = if values.len() > 1 {
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+        } else {
+            0.0
+        };
+
+        Summary {
+            count: values.len(),
+            mean,
+            variance,
+        }
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+}
+
+pub fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Standard normal cumulative distribution function
+pub fn normal_cdf(z: f64) -> f64 {
+    0.5 * (1.0 + erf(z / std::f64::consts::SQRT_2))
+}
+
+/// Abramowitz & Stegun 7.1.26 approximation (max error 1.5e-7)
+fn erf(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let x = x.abs();
+
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+
+    sign * (1.0 - poly * (-x * x).exp())
+}
+
+/// Two-sided p-value of Welch's t-test for a difference in means between two samples.
+///
+/// Returns `None` unless both samples have at least two observations.
+pub fn welch_t_test(a: &Summary, b: &Summary) -> Option<f64> {
+    if a.count < 2 || b.count < 2 {
+        return None;
+    }
+
+    let a_error = a.variance / a.count as f64;
+    let b_error = b.variance / b.count as f64;
+    let squared_error = a_error + b_error;
+    if squared_error <= 0.0 {
+        // Both samples are constant, so any difference is exact
+        return Some(if a.mean == b.mean { 1.0 } else { 0.0 });
+    }
+
+    let t = (b.mean - a.mean) / squared_error.sqrt();
+    let degrees_of_freedom = squared_error.powi(2)
+        / (a_error.powi(2) / (a.count - 1) as f64 + b_error.powi(2) / (b.count - 1) as f64);
+
+    Some(student_t_two_sided_p(t, degrees_of_freedom))
+}
+
+/// Two-sided tail probability of Student's t distribution
+fn student_t_two_sided_p(t: f64, degrees_of_freedom: f64) -> f64 {
+    let x = degrees_of_freedom / (degrees_of_freedom + t * t);
+    incomplete_beta(x, degrees_of_freedom / 2.0, 0.5).clamp(0.0, 1.0)
+}
+
+/// Regularized incomplete beta function I_x(a, b)
+fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+
+    // The continued fraction converges quickly only on this side of the mean
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - ln_front.exp() * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+/// Lentz evaluation of the incomplete beta continued fraction
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 300;
+    const EPSILON: f64 = 1e-12;
+    const TINY: f64 = 1e-300;
+
+    let guard = |v: f64| if v.abs() < TINY { TINY } else { v };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / guard(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / guard(1.0 + even * d);
+        c = guard(1.0 + even / c);
+        h *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / guard(1.0 + odd * d);
+        c = guard(1.0 + odd / c);
+        let delta = d * c;
+        h *= delta;
+
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+
+    h
+}
+
+/// Lanczos approximation of ln(Γ(x))
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    use std::f64::consts::PI;
+
+    if x < 0.5 {
+        // Reflection formula
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, coefficient) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += coefficient / (x + i as f64);
+    }
+    let t = x + 7.5;
+
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// Two-sided p-value of the Wilcoxon signed-rank test for paired deltas.
+///
+/// Uses the normal approximation with tie and continuity corrections; zero deltas are dropped.
+/// Returns `None` when every delta is zero.
+pub fn wilcoxon_signed_rank(deltas: &[f64]) -> Option<f64> {
+    let mut nonzero: Vec<f64> = deltas.iter().copied().filter(|d| *d != 0.0).collect();
+    if nonzero.is_empty() {
+        return None;
+    }
+    nonzero.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+
+    let n = nonzero.len();
+    let mut w_plus = 0.0;
+    let mut tie_correction = 0.0;
+
+    let mut i = 0;
+    while i < n {
+        let mut j = i;
+        while j + 1 < n && nonzero[j + 1].abs() == nonzero[i].abs() {
+            j += 1;
+        }
+
+        // Tied absolute values share the average of their ranks
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        let ties = (j - i + 1) as f64;
+        tie_correction += ties.powi(3) - ties;
+
+        w_plus += nonzero[i..=j].iter().filter(|d| **d > 0.0).count() as f64 * rank;
+        i = j + 1;
+    }
+
+    let n = n as f64;
+    let expected = n * (n + 1.0) / 4.0;
+    let variance = n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - tie_correction / 48.0;
+    if variance <= 0.0 {
+        return Some(1.0);
+    }
+
+    let z = ((w_plus - expected).abs() - 0.5).max(0.0) / variance.sqrt();
+    Some((2.0 * (1.0 - normal_cdf(z))).min(1.0))
+}
+
+/// Paired bootstrap of the mean delta, returning a percentile confidence interval and a two-sided
+/// p-value for the null hypothesis that the mean delta is zero.
+pub fn paired_bootstrap(
+    deltas: &[f64],
+    iterations: usize,
+    confidence: f64,
+) -> Option<BootstrapInterval> {
+    if deltas.is_empty() || iterations == 0 {
+        return None;
+    }
+
+    let mut rng = XorShift::new(BOOTSTRAP_SEED);
+    let mut means = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let mut sum = 0.0;
+        for _ in 0..deltas.len() {
+            sum += deltas[rng.next_index(deltas.len())];
+        }
+        means.push(sum / deltas.len() as f64);
+    }
+    means.sort_by(f64::total_cmp);
+
+    let alpha = 1.0 - confidence;
+    let at_or_below = means.iter().filter(|m| **m <= 0.0).count() as f64 / iterations as f64;
+    let at_or_above = means.iter().filter(|m| **m >= 0.0).count() as f64 / iterations as f64;
+
+    Some(BootstrapInterval {
+        mean: mean(deltas),
+        lower: percentile(&means, alpha / 2.0),
+        upper: percentile(&means, 1.0 - alpha / 2.0),
+        p_value: (2.0 * at_or_below.min(at_or_above)).min(1.0),
+    })
+}
+
+/// Nearest-rank percentile of an already sorted slice
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let index = ((sorted.len() - 1) as f64 * q.clamp(0.0, 1.0)).round() as usize;
+    sorted[index]
+}
+
+/// Small deterministic PRNG (xorshift64*), good enough for resampling
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        XorShift(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn next_index(&mut self, len: usize) -> usize {
+        (self.next_u64() % len as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_cdf() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-6);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-3);
+        assert!((normal_cdf(-1.96) - 0.025).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary::from_values(&[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(summary.count, 4);
+        assert!((summary.mean - 2.5).abs() < 1e-12);
+        assert!((summary.variance - 5.0 / 3.0).abs() < 1e-12);
+
+        let single = Summary::from_values(&[0.7]);
+        assert_eq!(single.variance, 0.0);
+    }
+
+    #[test]
+    fn test_student_t_matches_tables() {
+        // t = 2.228 is the 97.5th percentile with 10 degrees of freedom
+        assert!((student_t_two_sided_p(2.228, 10.0) - 0.05).abs() < 1e-3);
+        // t = 2.776 is the 97.5th percentile with 4 degrees of freedom
+        assert!((student_t_two_sided_p(2.776, 4.0) - 0.05).abs() < 1e-3);
+        assert!((student_t_two_sided_p(0.0, 4.0) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_welch_requires_repeated_trials() {
+        let single = Summary::from_values(&[0.5]);
+        let repeated = Summary::from_values(&[0.5, 0.6]);
+        assert!(welch_t_test(&single, &repeated).is_none());
+    }
+
+    #[test]
+    fn test_welch_noisy_samples_not_significant() {
+        let previous = Summary::from_values(&[0.2, 0.9, 0.5, 0.4]);
+        let current = Summary::from_values(&[0.3, 0.8, 0.6, 0.7]);
+        assert!(welch_t_test(&previous, &current).unwrap() > 0.05);
+    }
+
+    #[test]
+    fn test_welch_separated_samples_significant() {
+        let previous = Summary::from_values(&[0.90, 0.91, 0.89, 0.92]);
+        let current = Summary::from_values(&[0.50, 0.52, 0.49, 0.51]);
+        assert!(welch_t_test(&previous, &current).unwrap() < 0.001);
+    }
+
+    #[test]
+    fn test_wilcoxon_all_zero() {
+        assert!(wilcoxon_signed_rank(&[0.0, 0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_wilcoxon_small_sample_not_significant() {
+        let p = wilcoxon_signed_rank(&[-0.2, -0.1, -0.3]).unwrap();
+        assert!(p > 0.05);
+    }
+
+    #[test]
+    fn test_wilcoxon_consistent_shift_significant() {
+        let deltas: Vec<f64> = (1..=20).map(|i| -0.01 * i as f64).collect();
+        let p = wilcoxon_signed_rank(&deltas).unwrap();
+        assert!(p < 0.01);
+    }
+
+    #[test]
+    fn test_wilcoxon_symmetric_not_significant() {
+        let p = wilcoxon_signed_rank(&[0.1, -0.1, 0.2, -0.2, 0.3, -0.3]).unwrap();
+        assert!(p > 0.5);
+    }
+
+    #[test]
+    fn test_bootstrap_interval_contains_mean() {
+        let deltas = [0.1, 0.2, 0.15, 0.05, 0.12, 0.18, 0.09, 0.11];
+        let interval = paired_bootstrap(&deltas, 1000, 0.95).unwrap();
+
+        assert!(interval.lower <= interval.mean && interval.mean <= interval.upper);
+        assert!(interval.lower > 0.0);
+        assert!(interval.p_value < 0.05);
+    }
+
+    #[test]
+    fn test_bootstrap_is_deterministic() {
+        let deltas = [0.1, -0.2, 0.3, -0.05];
+        let a = paired_bootstrap(&deltas, 500, 0.95).unwrap();
+        let b = paired_bootstrap(&deltas, 500, 0.95).unwrap();
+
+        assert_eq!(a.lower, b.lower);
+        assert_eq!(a.upper, b.upper);
+        assert_eq!(a.p_value, b.p_value);
+    }
+
+    #[test]
+    fn test_bootstrap_empty() {
+        assert!(paired_bootstrap(&[], 100, 0.95).is_none());
+    }
+}
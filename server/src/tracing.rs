@@ -6,6 +6,8 @@ use std::collections::HashMap;
 use std::str::FromStr;
 use uuid::Uuid;
 
+use crate::online_eval;
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct Span {
@@ -14,6 +16,10 @@ struct Span {
     start_time: u64,
     end_time: u64,
     operation_name: String,
+    #[serde(default)]
+    attributes: serde_json::Map<String, serde_json::Value>,
+    input: Option<serde_json::Value>,
+    output: Option<serde_json::Value>,
     child_spans: Vec<Span>,
 }
 
@@ -34,11 +40,13 @@ pub async fn post(Json(payload): Json<TracingPayload>) -> (StatusCode, Json<()>)
     };
 
     let mut uuid_to_span_id: HashMap<String, i32> = HashMap::new();
+    let mut created_spans = Vec::new();
 
     fn process_span(
         span: Span,
         repo: &mut DieselRepository<ellmo_db::schema::span::table>,
         uuid_to_span_id: &mut HashMap<String, i32>,
+        created_spans: &mut Vec<ellmo_db::models::span::Span>,
     ) {
         // Convert start and end times to chrono::DateTime
         let start_time = chrono::Utc.timestamp_millis_opt(span.start_time as i64);
@@ -50,9 +58,33 @@ pub async fn post(Json(payload): Json<TracingPayload>) -> (StatusCode, Json<()>)
             chrono::LocalResult::Single(valid_end_time),
         ) = (start_time, end_time)
         {
-            let parent_span_id = span
-                .parent_span_id
-                .and_then(|uuid| uuid_to_span_id.get(&uuid).copied());
+            // Parents may also have been reported earlier, e.g. spans recorded by the server
+            let parent_span_id = span.parent_span_id.and_then(|uuid| {
+                uuid_to_span_id.get(&uuid).copied().or_else(|| {
+                    let uuid = Uuid::from_str(&uuid).ok()?;
+                    let parent = repo.find_by_external_uuid(uuid).ok()??;
+                    Some(parent.id)
+                })
+            });
+
+            // Spans are attributed to the prompt version named by their attributes
+            let prompt_version_id = match (
+                span.attributes.get("prompt.name"),
+                span.attributes.get("prompt.version"),
+            ) {
+                (
+                    Some(serde_json::Value::String(name)),
+                    Some(serde_json::Value::String(version)),
+                ) => DieselRepository::new(
+                    &mut *repo.connection,
+                    ellmo_db::schema::prompt_version::table,
+                )
+                .find_by_version(name, version)
+                .ok()
+                .flatten()
+                .map(|prompt_version| prompt_version.id),
+                _ => None,
+            };
 
             // Create a new InsertableSpan
             let insertable_span = ellmo_db::models::span::InsertableSpan {
@@ -61,6 +93,10 @@ pub async fn post(Json(payload): Json<TracingPayload>) -> (StatusCode, Json<()>)
                 operation_name: span.operation_name,
                 parent_span_id,
                 external_uuid: Uuid::from_str(&span.id).ok(),
+                attributes: serde_json::Value::Object(span.attributes),
+                input: span.input,
+                output: span.output,
+                prompt_version_id,
             };
 
             // Attempt to create the span in the repository
@@ -69,6 +105,7 @@ pub async fn post(Json(payload): Json<TracingPayload>) -> (StatusCode, Json<()>)
                     // If span was created successfully, add the UUID to span ID mapping
                     println!("Span created successfully");
                     uuid_to_span_id.insert(span.id, created_span.id);
+                    created_spans.push(created_span);
                 }
                 Err(e) => println!("Error creating span: {:?}", e),
             }
@@ -78,12 +115,16 @@ pub async fn post(Json(payload): Json<TracingPayload>) -> (StatusCode, Json<()>)
         }
 
         for child_span in span.child_spans {
-            process_span(child_span, repo, uuid_to_span_id);
+            process_span(child_span, repo, uuid_to_span_id, created_spans);
         }
     }
 
     for span in traces {
-        process_span(span, &mut repo, &mut uuid_to_span_id);
+        process_span(span, &mut repo, &mut uuid_to_span_id, &mut created_spans);
+    }
+
+    if let Err(e) = online_eval::schedule(&mut conn, &created_spans) {
+        println!("Failed to schedule online evals: {:?}", e);
     }
 
     (StatusCode::OK, Json(()))
@@ -0,0 +1,55 @@
+use std::cmp::Ordering;
+
+/// Parse a version as semver, tolerating a leading `v`
+pub fn parse_semver(version: &str) -> Option<semver::Version> {
+    semver::Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+}
+
+/// Order two versions by semver precedence, falling back to plain string order when either of
+/// them is not semver
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (parse_semver(a), parse_semver(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Whether `version` lies within the inclusive bounds
+pub fn in_range(version: &str, min: Option<&str>, max: Option<&str>) -> bool {
+    let above_min = match min {
+        Some(min) => compare_versions(version, min) != Ordering::Less,
+        None => true,
+    };
+    let below_max = match max {
+        Some(max) => compare_versions(version, max) != Ordering::Greater,
+        None => true,
+    };
+
+    above_min && below_max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_semver() {
+        assert_eq!(compare_versions("1.10.0", "1.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.0.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0-beta", "1.0.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_compare_non_semver() {
+        assert_eq!(compare_versions("2024-09-01", "2024-09-10"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_in_range() {
+        assert!(in_range("1.10.0", Some("1.9.0"), Some("2.0.0")));
+        assert!(in_range("1.9.0", Some("1.9.0"), None));
+        assert!(!in_range("1.8.0", Some("1.9.0"), None));
+        assert!(!in_range("2.0.1", None, Some("2.0.0")));
+        assert!(in_range("anything", None, None));
+    }
+}
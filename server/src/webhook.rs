@@ -0,0 +1,368 @@
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use diesel::prelude::*;
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use serde_json::json;
+use sha2::Sha256;
+
+use ellmo_db::{
+    establish_connection,
+    models::{
+        repository::{DieselRepository, Repository},
+        webhook_delivery::{
+            InsertableWebhookDelivery, WebhookDelivery, STATUS_DELIVERED, STATUS_FAILED,
+            STATUS_PENDING,
+        },
+        webhook_delivery_attempt::InsertableWebhookDeliveryAttempt,
+        webhook_endpoint::WebhookEndpoint,
+    },
+    schema::{webhook_delivery, webhook_delivery_attempt, webhook_endpoint},
+    try_establish_connection,
+};
+use ellmo_proto::ellmo::{EvalOutcome, GateVerdict, MetricOutcome, VersionedPrompt, WebhookEvent};
+
+use crate::queue::{Job, JOB_QUEUE};
+
+/// Attempts made before a delivery is given up on
+pub const MAX_ATTEMPTS: i32 = 6;
+/// Delay before the first retry, doubled after every further failed attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(10);
+/// Upper bound on how long an endpoint slow to respond holds up the job queue
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SIGNATURE_HEADER: &str = "X-Ellmo-Signature";
+const EVENT_HEADER: &str = "X-Ellmo-Event";
+const DELIVERY_HEADER: &str = "X-Ellmo-Delivery";
+
+lazy_static! {
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("Failed to build webhook HTTP client");
+}
+
+/// Recorded run that subscribed webhooks are told about
+pub struct RunNotice<'a> {
+    pub eval_name: &'a str,
+    pub prompt: &'a VersionedPrompt,
+    pub base_version: Option<&'a str>,
+    pub eval_run_id: i32,
+    pub outcome: EvalOutcome,
+    pub metric_outcomes: &'a [MetricOutcome],
+    pub gate: &'a GateVerdict,
+    pub branch: Option<&'a str>,
+    pub git_sha: Option<&'a str>,
+    pub ci_run_url: Option<&'a str>,
+}
+
+impl RunNotice<'_> {
+    /// Events raised by the run
+    fn events(&self) -> Vec<WebhookEvent> {
+        let mut events = Vec::new();
+        if self.outcome == EvalOutcome::Regression {
+            events.push(WebhookEvent::EvalRegression);
+        }
+        if !self.gate.passed {
+            events.push(WebhookEvent::GateFailure);
+        }
+        events
+    }
+
+    fn payload(&self, event: WebhookEvent) -> serde_json::Value {
+        let regressed_metrics: Vec<&str> = self
+            .metric_outcomes
+            .iter()
+            .filter(|metric| metric.outcome() == EvalOutcome::Regression)
+            .map(|metric| metric.metric.as_str())
+            .collect();
+
+        json!({
+            "event": event.as_str_name(),
+            "occurredAt": Utc::now(),
+            "evalName": self.eval_name,
+            "promptName": self.prompt.name,
+            "promptVersion": self.prompt.version,
+            "baseVersion": self.base_version,
+            "evalRunId": self.eval_run_id,
+            "outcome": self.outcome.as_str_name(),
+            "regressedMetrics": regressed_metrics,
+            "gate": {
+                "passed": self.gate.passed,
+                "reasons": self.gate.reasons,
+            },
+            "metadata": {
+                "branch": self.branch,
+                "gitSha": self.git_sha,
+                "ciRunUrl": self.ci_run_url,
+            },
+        })
+    }
+}
+
+/// Queue deliveries of the events raised by a run to every subscribed webhook
+pub fn notify_run(conn: &mut PgConnection, notice: &RunNotice) -> QueryResult<()> {
+    let now = Utc::now();
+    let mut delivery_ids = Vec::new();
+
+    for event in notice.events() {
+        let endpoints = DieselRepository::new(conn, webhook_endpoint::table).find_subscribed(
+            event.as_str_name(),
+            notice.eval_name,
+            &notice.prompt.name,
+        )?;
+        if endpoints.is_empty() {
+            continue;
+        }
+
+        let payload = notice.payload(event);
+        for endpoint in endpoints {
+            let delivery = DieselRepository::new(conn, webhook_delivery::table).create(
+                &InsertableWebhookDelivery {
+                    webhook_endpoint_id: endpoint.id,
+                    event: event.as_str_name().to_string(),
+                    payload: payload.clone(),
+                    status: STATUS_PENDING.to_string(),
+                    attempt_count: 0,
+                    next_attempt_at: Some(now),
+                    created_at: now,
+                    updated_at: now,
+                },
+            )?;
+            delivery_ids.push(delivery.id);
+        }
+    }
+
+    for delivery_id in delivery_ids {
+        enqueue(delivery_id, Duration::ZERO);
+    }
+
+    Ok(())
+}
+
+/// Queue the deliveries left pending, e.g. by a restart, at their scheduled time
+pub fn resume_pending() -> anyhow::Result<usize> {
+    let mut conn = try_establish_connection()?;
+    let pending = DieselRepository::new(&mut conn, webhook_delivery::table).find_pending()?;
+
+    let now = Utc::now();
+    for delivery in &pending {
+        let delay = delivery
+            .next_attempt_at
+            .and_then(|at| (at - now).to_std().ok())
+            .unwrap_or_default();
+        enqueue(delivery.id, delay);
+    }
+
+    Ok(pending.len())
+}
+
+/// Signature header value of a payload: the timestamp it was signed at and the HMAC-SHA256 of
+/// `"{timestamp}.{body}"` keyed with the secret of the webhook
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("t={},v1={:x}", timestamp, mac.finalize().into_bytes())
+}
+
+/// Delay before retrying after the given number of failed attempts
+fn retry_delay(failed_attempts: i32) -> Duration {
+    RETRY_BASE_DELAY * 2u32.pow(failed_attempts.clamp(1, MAX_ATTEMPTS) as u32 - 1)
+}
+
+fn enqueue(delivery_id: i32, delay: Duration) {
+    if delay.is_zero() {
+        JOB_QUEUE
+            .lock()
+            .expect("Job queue lock poisoned")
+            .add_job(Box::new(DeliverWebhook { delivery_id }));
+        return;
+    }
+
+    // Wait outside of the queue so retries don't hold up other jobs
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        enqueue(delivery_id, Duration::ZERO);
+    });
+}
+
+/// Single attempt to deliver an event, scheduling a retry when it fails
+struct DeliverWebhook {
+    delivery_id: i32,
+}
+
+#[async_trait::async_trait]
+impl Job for DeliverWebhook {
+    async fn execute(&self) {
+        if let Err(e) = self.attempt().await {
+            println!("Failed to deliver webhook {}: {}", self.delivery_id, e);
+        }
+    }
+}
+
+/// Result of posting a payload to an endpoint
+struct AttemptResult {
+    response_status: Option<i32>,
+    error: Option<String>,
+    duration: Duration,
+}
+
+impl AttemptResult {
+    fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl DeliverWebhook {
+    async fn attempt(&self) -> anyhow::Result<()> {
+        let Some((delivery, endpoint)) = self.load()? else {
+            // Delivered, given up on or removed along with its webhook in the meantime
+            return Ok(());
+        };
+
+        let result = post(&delivery, &endpoint).await;
+        let attempt = delivery.attempt_count + 1;
+
+        let mut conn = establish_connection();
+        let status = conn.transaction(|conn| {
+            DieselRepository::new(conn, webhook_delivery_attempt::table).create(
+                &InsertableWebhookDeliveryAttempt {
+                    webhook_delivery_id: delivery.id,
+                    attempt,
+                    response_status: result.response_status,
+                    error: result.error.clone(),
+                    duration_ms: result.duration.as_millis().min(i32::MAX as u128) as i32,
+                    created_at: Utc::now(),
+                },
+            )?;
+
+            let (status, next_attempt_at) = if result.succeeded() {
+                (STATUS_DELIVERED, None)
+            } else if attempt >= MAX_ATTEMPTS {
+                (STATUS_FAILED, None)
+            } else {
+                let delay = chrono::Duration::from_std(retry_delay(attempt))
+                    .unwrap_or(chrono::Duration::MAX);
+                (STATUS_PENDING, Some(Utc::now() + delay))
+            };
+
+            diesel::update(webhook_delivery::table.find(delivery.id))
+                .set((
+                    webhook_delivery::status.eq(status),
+                    webhook_delivery::attempt_count.eq(attempt),
+                    webhook_delivery::next_attempt_at.eq(next_attempt_at),
+                    webhook_delivery::updated_at.eq(Utc::now()),
+                ))
+                .execute(conn)?;
+
+            Ok::<_, diesel::result::Error>(status)
+        })?;
+
+        if status == STATUS_PENDING {
+            enqueue(delivery.id, retry_delay(attempt));
+        }
+
+        Ok(())
+    }
+
+    fn load(&self) -> QueryResult<Option<(WebhookDelivery, WebhookEndpoint)>> {
+        let mut conn = establish_connection();
+
+        webhook_delivery::table
+            .inner_join(webhook_endpoint::table)
+            .filter(webhook_delivery::id.eq(self.delivery_id))
+            .filter(webhook_delivery::status.eq(STATUS_PENDING))
+            .select((WebhookDelivery::as_select(), WebhookEndpoint::as_select()))
+            .first::<(WebhookDelivery, WebhookEndpoint)>(&mut conn)
+            .optional()
+    }
+}
+
+async fn post(delivery: &WebhookDelivery, endpoint: &WebhookEndpoint) -> AttemptResult {
+    let body = delivery.payload.to_string();
+    let signature = sign(&endpoint.secret, Utc::now().timestamp(), &body);
+
+    let started = Instant::now();
+    let response = HTTP_CLIENT
+        .post(&endpoint.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, signature)
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .body(body)
+        .send()
+        .await;
+    let duration = started.elapsed();
+
+    match response {
+        Ok(response) if response.status().is_success() => AttemptResult {
+            response_status: Some(response.status().as_u16() as i32),
+            error: None,
+            duration,
+        },
+        Ok(response) => AttemptResult {
+            response_status: Some(response.status().as_u16() as i32),
+            error: Some(format!("Endpoint responded with {}", response.status())),
+            duration,
+        },
+        Err(e) => AttemptResult {
+            response_status: None,
+            error: Some(e.to_string()),
+            duration,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_matches_reference_hmac() {
+        // HMAC-SHA256("secret", "1700000000.{}")
+        assert_eq!(
+            sign("secret", 1_700_000_000, "{}"),
+            format!(
+                "t=1700000000,v1={}",
+                "b8569b78799ff9e3cbff0fc2d63a33a2b57f3282abd07c37ae5e8e7d79a5f163"
+            )
+        );
+        assert_ne!(sign("secret", 1, "{}"), sign("other", 1, "{}"));
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::from_secs(10));
+        assert_eq!(retry_delay(2), Duration::from_secs(20));
+        assert_eq!(retry_delay(4), Duration::from_secs(80));
+    }
+
+    #[test]
+    fn test_events_of_run() {
+        let prompt = VersionedPrompt::default();
+        let failed_gate = GateVerdict {
+            passed: false,
+            reasons: vec!["Outcome could not be determined".to_string()],
+        };
+        let notice = RunNotice {
+            eval_name: "accuracy",
+            prompt: &prompt,
+            base_version: None,
+            eval_run_id: 1,
+            outcome: EvalOutcome::Unknown,
+            metric_outcomes: &[],
+            gate: &failed_gate,
+            branch: None,
+            git_sha: None,
+            ci_run_url: None,
+        };
+
+        assert_eq!(notice.events(), vec![WebhookEvent::GateFailure]);
+        assert_eq!(
+            notice.payload(WebhookEvent::GateFailure)["gate"]["reasons"][0],
+            "Outcome could not be determined"
+        );
+    }
+}